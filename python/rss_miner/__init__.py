@@ -5,23 +5,46 @@ This package provides Python bindings for the rss-miner Rust library,
 offering high-performance RSS/Atom feed discovery and OPML file generation.
 """
 
+import asyncio
+
 from .rss_miner import (
     find_feeds,
     find_feeds_parallel,
+    find_feeds_stream,
     read_urls,
     create_opml,
     PyRssFeed,
+    PyFeedStream,
 )
 
 # Create a convenient alias
 RssFeed = PyRssFeed
 
+
+async def find_feeds_async(urls, verbose=False):
+    """Async generator yielding feeds from `urls` as they are found.
+
+    Discovery runs on background threads; waiting for each feed is handed
+    to a worker thread so the event loop stays free.
+    """
+    stream = find_feeds_stream(urls, verbose)
+    done = object()
+    while True:
+        feed = await asyncio.to_thread(next, stream, done)
+        if feed is done:
+            return
+        yield feed
+
+
 __all__ = [
     "find_feeds",
     "find_feeds_parallel",
+    "find_feeds_stream",
+    "find_feeds_async",
     "read_urls",
     "create_opml",
     "PyRssFeed",
+    "PyFeedStream",
     "RssFeed",
 ]
 
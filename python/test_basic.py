@@ -15,6 +15,8 @@ def test_imports():
 
     assert hasattr(rss_miner, "find_feeds")
     assert hasattr(rss_miner, "find_feeds_parallel")
+    assert hasattr(rss_miner, "find_feeds_stream")
+    assert hasattr(rss_miner, "find_feeds_async")
     assert hasattr(rss_miner, "read_urls")
     assert hasattr(rss_miner, "create_opml")
     assert hasattr(rss_miner, "RssFeed")
@@ -1,346 +1,10664 @@
-use anyhow::{Context, Result};
-use quick_xml::events::Event;
+use anyhow::{Context, Result, bail};
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
 use quick_xml::reader::Reader;
 use quick_xml::writer::Writer;
 use rayon::prelude::*;
-use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{
+    CONTENT_TYPE, ETAG, HeaderMap, HeaderName, HeaderValue, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+    LAST_MODIFIED, LOCATION, REFERER, RETRY_AFTER,
+};
 use scraper::{Html, Selector};
-use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
 use std::fs;
-use std::io::Cursor;
-use std::path::Path;
+use std::io::{self, BufRead, BufReader, BufWriter, Cursor, Read, Write};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
 use url::Url;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RssFeed {
     pub title: String,
+    /// Canonical feed URL: the feed's `rel="self"` link when it declares a
+    /// valid one, otherwise the URL the feed was discovered at.
     pub url: String,
+    /// The URL the feed was actually discovered and fetched at.
+    pub raw_url: String,
     pub html_url: String,
     pub feed_type: FeedType,
+    /// How the feed was found; `None` for feeds not produced by discovery.
+    pub discovery_method: Option<DiscoveryMethod>,
+    /// The `ETag` the feed was served with, written to OPML output so
+    /// [`check_opml_feeds`] can revalidate the feed with a conditional
+    /// request.
+    pub etag: Option<String>,
+    /// The `Last-Modified` date the feed was served with; see
+    /// [`etag`](RssFeed::etag).
+    pub last_modified: Option<String>,
+    /// Categories declared by the feed itself.
+    pub categories: Vec<String>,
+    /// The feed's declared language as a BCP-47 tag, e.g. `en-US`.
+    pub language: Option<String>,
+    /// The feed's declared author; see [`FeedInfo::author`].
+    pub author: Option<String>,
+    /// Input category the feed was listed under, e.g. `News` for URLs
+    /// following a `[News]` line; OPML output nests feeds under it.
+    pub folder: Option<String>,
+    /// The feed is served over plain `http` but was found on an `https`
+    /// page, so browsers would block it as mixed content.
+    #[serde(default)]
+    pub mixed_content: bool,
+    /// The feed's format and version; see [`FeedInfo::format_version`].
+    #[serde(default)]
+    pub format_version: Option<String>,
+    /// The `<title>` of the HTML page the feed was discovered on, to tell
+    /// apart feeds with generic titles such as "RSS".
+    #[serde(default)]
+    pub source_title: Option<String>,
+}
+
+impl RssFeed {
+    /// Creates a feed with no discovery metadata.
+    pub fn new(
+        title: impl Into<String>,
+        url: impl Into<String>,
+        html_url: impl Into<String>,
+        feed_type: FeedType,
+    ) -> Self {
+        let url = url.into();
+        RssFeed {
+            title: title.into(),
+            raw_url: url.clone(),
+            url,
+            html_url: html_url.into(),
+            feed_type,
+            discovery_method: None,
+            etag: None,
+            last_modified: None,
+            categories: Vec::new(),
+            language: None,
+            author: None,
+            folder: None,
+            mixed_content: false,
+            format_version: None,
+            source_title: None,
+        }
+    }
+
+    /// A short identifier derived from the normalized feed URL, stable across
+    /// runs and releases, for tracking feeds without relying on titles.
+    ///
+    /// The URL's scheme and host are lowercased, default ports and fragments
+    /// dropped, and the result hashed with 64-bit FNV-1a.
+    pub fn id(&self) -> String {
+        let normalized = match Url::parse(self.url.trim()) {
+            Ok(mut url) => {
+                url.set_fragment(None);
+                url.to_string()
+            }
+            Err(_) => self.url.trim().to_string(),
+        };
+
+        let hash = normalized
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+            });
+        format!("{:016x}", hash)
+    }
+
+    /// Returns true if the title is missing or a placeholder derived from the
+    /// URL rather than taken from the page or the feed.
+    fn has_fallback_title(&self) -> bool {
+        let title = self.title.trim();
+        title.is_empty()
+            || title == UNTITLED_FEED
+            || title == extract_title_from_url(&self.html_url)
+            || title == extract_title_from_url(&self.url)
+    }
+
+    /// Folds metadata from a duplicate of this feed into `self`.
+    fn merge(&mut self, other: RssFeed) {
+        if self.etag.is_none() && self.last_modified.is_none() {
+            self.etag = other.etag.clone();
+            self.last_modified = other.last_modified.clone();
+        }
+        if self.has_fallback_title() && !other.has_fallback_title() {
+            self.title = other.title;
+        }
+        for category in other.categories {
+            if !self.categories.contains(&category) {
+                self.categories.push(category);
+            }
+        }
+        if self.discovery_method.is_none() {
+            self.discovery_method = other.discovery_method;
+        }
+        if self.language.is_none() {
+            self.language = other.language;
+        }
+        if self.author.is_none() {
+            self.author = other.author;
+        }
+        if self.folder.is_none() {
+            self.folder = other.folder;
+        }
+        if self.format_version.is_none() {
+            self.format_version = other.format_version;
+        }
+        if self.source_title.is_none() {
+            self.source_title = other.source_title;
+        }
+    }
+}
+
+const UNTITLED_FEED: &str = "Untitled Feed";
+
+/// The discovery strategy that produced a feed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscoveryMethod {
+    /// A `<link>` tag with a feed MIME type in the page HTML.
+    Link,
+    /// One of the well-known fallback paths such as `/feed` or `/rss.xml`.
+    CommonPath,
+    /// An `<a>` element whose text or href looks like a feed link.
+    Anchor,
+    /// The `.rss` variant of a fediverse actor URL.
+    Fediverse,
+    /// A feed URL built from the page URL of a [`KnownSite`].
+    KnownSite,
+    /// The `content` of a `<meta>` tag matched by
+    /// [`MinerConfig::meta_feed_selector`].
+    Meta,
+    /// A feed-like `<loc>` in the site's sitemap.
+    Sitemap,
+    /// A feed URL declared in the page's JSON-LD structured data.
+    JsonLd,
+    /// A feed that another discovered Atom feed links to, under
+    /// [`MinerConfig::follow_related_feeds`].
+    Related,
+    /// The input URL was a feed itself rather than a page.
+    Direct,
+}
+
+/// How candidate feeds are validated; see [`MinerConfig::validation_mode`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationMode {
+    /// Fully parse every candidate as RSS or Atom (or with a custom
+    /// validator), recording its metadata.
+    #[default]
+    Strict,
+    /// Trust an XML or feed Content-Type plus the document's root element,
+    /// without parsing the rest. Much cheaper on huge scans, but accepts
+    /// broken feeds and records no metadata beyond the type.
+    ContentType,
+}
+
+/// A TLS protocol version; see [`MinerConfig::min_tls_version`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum TlsVersion {
+    #[serde(rename = "1.2")]
+    Tls12,
+    #[serde(rename = "1.3")]
+    Tls13,
+}
+
+impl fmt::Display for TlsVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TlsVersion::Tls12 => write!(f, "TLS 1.2"),
+            TlsVersion::Tls13 => write!(f, "TLS 1.3"),
+        }
+    }
+}
+
+/// A site family with predictable feed URLs; see [`MinerConfig::known_sites`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KnownSite {
+    /// Channel (`/channel/<id>`) and playlist (`?list=<id>`) URLs map to
+    /// `/feeds/videos.xml`.
+    YouTube,
+    /// Subreddit (`/r/<name>`) and user (`/user/<name>`) URLs map to `.rss`.
+    Reddit,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum FeedType {
     Rss,
     Atom,
+    /// A JSON Feed (jsonfeed.org). Only recognized by the `feed-rs` parsing
+    /// backend or a custom [`FeedValidator`].
+    Json,
+    /// Served with a feed Content-Type but not parseable as RSS or Atom.
+    ///
+    /// Only produced when [`MinerConfig::keep_unknown`] is enabled.
+    Unknown,
+}
+
+impl FeedType {
+    /// Returns the lowercase name used in OPML `type` attributes.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FeedType::Rss => "rss",
+            FeedType::Atom => "atom",
+            FeedType::Json => "json",
+            FeedType::Unknown => "unknown",
+        }
+    }
+}
+
+/// Settings controlling how the miner talks to remote servers.
+///
+/// Can be loaded from a TOML file whose keys mirror the field names, see
+/// [`MinerConfig::from_toml_file`].
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MinerConfig {
+    /// Timeout applied to every HTTP request. Written in TOML as whole seconds.
+    #[serde(deserialize_with = "deserialize_secs")]
+    pub timeout: Duration,
+    /// Per-host overrides of `timeout`, keyed by host name (or `host:port`),
+    /// matched exactly. Written in TOML as a table of whole seconds.
+    #[serde(deserialize_with = "deserialize_secs_map")]
+    pub host_timeouts: HashMap<String, Duration>,
+    /// Extra `(name, value)` headers sent with every page and feed request.
+    pub extra_headers: Vec<(String, String)>,
+    /// Capture feeds that fail to parse but are served with a feed
+    /// Content-Type as [`FeedType::Unknown`] instead of dropping them.
+    pub keep_unknown: bool,
+    /// Send a cheap HEAD request before fetching each common feed path and
+    /// skip paths whose status or Content-Type rule out a feed.
+    pub use_head_probe: bool,
+    /// Accept invalid or self-signed TLS certificates. Only use this for
+    /// trusted hosts such as intranet sites; it disables MITM protection.
+    pub danger_accept_invalid_certs: bool,
+    /// When a page has no feed `<link>` tags, probe `<a>` elements whose text
+    /// (e.g. "RSS", "Subscribe") or href looks like a feed.
+    pub scan_anchors: bool,
+    /// When a page has no feed `<link>` or `<meta>` tags, look for feed
+    /// URLs declared in its `<script type="application/ld+json">` blocks,
+    /// such as a `webFeed` property or a `SubscribeAction` target.
+    pub scan_json_ld: bool,
+    /// Maximum number of feed-like anchors validated per page.
+    pub max_anchor_probes: usize,
+    /// How many AMP pages in a row to leave for their
+    /// `<link rel="canonical">` page when they advertise no feeds, since
+    /// AMP versions often drop the feed links the regular page has.
+    /// `0` disables following.
+    pub amp_follow_depth: usize,
+    /// Title feeds found on IP-address sites, which otherwise fall back to
+    /// "Feed at <ip>", after the address's reverse-DNS name. Off by default
+    /// since each lookup can take seconds.
+    pub reverse_dns_titles: bool,
+    /// For fediverse actor URLs such as `https://host/@user`, probe the
+    /// Mastodon-style `https://host/@user.rss` feed before fetching the page.
+    pub probe_fediverse: bool,
+    /// Refuse connections that negotiate an older TLS version than this.
+    /// Written in TOML as `"1.2"` or `"1.3"`.
+    pub min_tls_version: Option<TlsVersion>,
+    /// Speak HTTP/2 from the first byte instead of negotiating it. Only use
+    /// this when every host is known to support h2 over cleartext or TLS.
+    pub http2_prior_knowledge: bool,
+    /// Never upgrade to HTTP/2, for servers with broken h2 support.
+    pub http1_only: bool,
+    /// Site families whose feed URLs are built directly from the page URL
+    /// (e.g. a YouTube channel's `videos.xml`) before fetching the page.
+    pub known_sites: Vec<KnownSite>,
+    /// How many times to retry a page or feed request answered with 429 Too
+    /// Many Requests or 503 Service Unavailable.
+    pub max_retries: u32,
+    /// Most HTTP requests in flight at once across all workers, whatever
+    /// the thread count: one URL's discovery can issue several requests.
+    /// `None` leaves requests unlimited.
+    pub max_inflight_requests: Option<usize>,
+    /// Longest wait before a retry, however long `Retry-After` asks for.
+    /// Written in TOML as whole seconds.
+    #[serde(deserialize_with = "deserialize_secs")]
+    pub max_retry_wait: Duration,
+    /// Longest random delay added to each retry's wait, so workers retrying
+    /// the same host don't all hit it at the same instant. Written in TOML
+    /// as whole milliseconds.
+    #[serde(deserialize_with = "deserialize_millis")]
+    pub retry_jitter: Duration,
+    /// Send the page a feed was found on as the `Referer` when fetching the
+    /// feed, for servers with naive hotlink protection.
+    pub send_referer: bool,
+    /// Feed URL patterns to drop from discovery results. A pattern with `*`
+    /// wildcards must match the whole URL; any other pattern matches as a
+    /// substring. Matching ignores ASCII case.
+    pub feed_url_denylist: Vec<String>,
+    /// Record a [`LogEntry`] per URL in [`DiscoveryReport::log`].
+    pub event_log: bool,
+    /// Break fetches, feeds, and errors down by host in
+    /// [`DiscoveryReport::host_stats`].
+    pub host_stats: bool,
+    /// Directory holding a validation cache shared between runs: feeds
+    /// validated within `cache_ttl`, with the same `validation_mode`,
+    /// `sample_titles`, and `keep_unknown`, are not fetched again. Unused
+    /// when there are `custom_validators`.
+    pub cache_dir: Option<PathBuf>,
+    /// How long a cached validation stays fresh. Written in TOML as whole
+    /// seconds.
+    #[serde(deserialize_with = "deserialize_secs")]
+    pub cache_ttl: Duration,
+    /// As a last resort, look for feed-like URLs in the site's
+    /// `/sitemap.xml` (or gzipped `/sitemap.xml.gz`).
+    pub scan_sitemap: bool,
+    /// Only follow feed-typed `<link>` tags whose `rel` includes `alternate`
+    /// or is missing, skipping mistyped ones such as `rel="stylesheet"`.
+    pub require_alternate_rel: bool,
+    /// Paths probed in order, relative to the site, when the page
+    /// advertises no feed; the first that validates is kept.
+    pub probe_paths: Vec<String>,
+    /// CSS selector for `<meta>` tags in the page head whose `content` is a
+    /// feed URL, tried when no `<link>` tag yields a feed. Empty disables it.
+    pub meta_feed_selector: String,
+    /// Only follow redirects that stay on the same host; a redirect to
+    /// another host is returned unfollowed.
+    pub same_host_redirects: bool,
+    /// When `same_host_redirects` stops a feed URL's redirect, validate the
+    /// redirect target and record the feed at its new URL instead of
+    /// dropping it.
+    pub follow_feed_redirects: bool,
+    /// Most feeds to keep per site, preferring the site's main feed over
+    /// comment, category, tag, and author feeds. `None` keeps them all; a
+    /// cap of zero is treated as one.
+    pub max_feeds_per_site: Option<usize>,
+    /// Most pages to fetch across a whole run. Once reached, the remaining
+    /// URLs are skipped and counted in [`DiscoveryReport::urls_skipped`].
+    pub max_page_fetches: Option<usize>,
+    /// How thoroughly candidate feeds are checked.
+    pub validation_mode: ValidationMode,
+    /// Number of item titles to record in [`FeedInfo::sample_titles`],
+    /// capped at [`MAX_SAMPLE_TITLES`].
+    pub sample_titles: usize,
+    /// Also look for feeds on the [`PROBED_SUBDOMAINS`] of each input
+    /// site, e.g. `blog.example.com` for `example.com`.
+    pub probe_subdomains: bool,
+    /// Also validate and keep the feeds that discovered Atom feeds point to
+    /// (see [`FeedInfo::related_feeds`]), at most [`MAX_RELATED_FEEDS`] per
+    /// site. Feeds found this way are not followed further.
+    pub follow_related_feeds: bool,
+    /// Drop feeds flagged as [`mixed_content`](RssFeed::mixed_content).
+    pub exclude_mixed_content: bool,
+    /// `(host, name, value)` cookies sent with every request to exactly
+    /// that host, e.g. a session cookie for a private feed. They are never
+    /// sent to other hosts, subdomains included.
+    pub cookies: Vec<(String, String, String)>,
+    /// Stop discovering a site's feeds at the first one that validates,
+    /// skipping its remaining links and fallback strategies.
+    pub first_feed_only: bool,
+    /// Validators for additional feed formats, tried in order on bodies
+    /// that don't parse as RSS or Atom. Not settable from TOML.
+    #[serde(skip)]
+    pub custom_validators: Vec<Arc<dyn FeedValidator>>,
+}
+
+impl Default for MinerConfig {
+    fn default() -> Self {
+        MinerConfig {
+            timeout: Duration::from_secs(10),
+            host_timeouts: HashMap::new(),
+            extra_headers: Vec::new(),
+            keep_unknown: false,
+            use_head_probe: false,
+            danger_accept_invalid_certs: false,
+            scan_anchors: false,
+            scan_json_ld: false,
+            max_anchor_probes: 5,
+            amp_follow_depth: 1,
+            reverse_dns_titles: false,
+            probe_fediverse: false,
+            min_tls_version: None,
+            http2_prior_knowledge: false,
+            http1_only: false,
+            known_sites: Vec::new(),
+            max_retries: 0,
+            max_inflight_requests: None,
+            max_retry_wait: Duration::from_secs(30),
+            retry_jitter: Duration::from_millis(500),
+            send_referer: false,
+            feed_url_denylist: Vec::new(),
+            event_log: false,
+            host_stats: false,
+            cache_dir: None,
+            cache_ttl: Duration::from_secs(24 * 60 * 60),
+            scan_sitemap: false,
+            require_alternate_rel: false,
+            probe_paths: DEFAULT_PROBE_PATHS.map(str::to_string).to_vec(),
+            meta_feed_selector: DEFAULT_META_FEED_SELECTOR.to_string(),
+            same_host_redirects: false,
+            follow_feed_redirects: false,
+            max_feeds_per_site: None,
+            max_page_fetches: None,
+            validation_mode: ValidationMode::Strict,
+            sample_titles: 0,
+            probe_subdomains: false,
+            follow_related_feeds: false,
+            exclude_mixed_content: false,
+            cookies: Vec::new(),
+            first_feed_only: false,
+            custom_validators: Vec::new(),
+        }
+    }
+}
+
+impl MinerConfig {
+    /// Returns true if the feed's canonical or discovered URL matches a
+    /// [`feed_url_denylist`](Self::feed_url_denylist) pattern.
+    pub fn denies_feed(&self, feed: &RssFeed) -> bool {
+        self.feed_url_denylist.iter().any(|pattern| {
+            url_matches_pattern(&feed.url, pattern) || url_matches_pattern(&feed.raw_url, pattern)
+        })
+    }
+
+    /// Returns true if discovery should drop the feed: it is denylisted, or
+    /// it is mixed content and [`exclude_mixed_content`](Self::exclude_mixed_content)
+    /// is set.
+    pub fn excludes_feed(&self, feed: &RssFeed) -> bool {
+        self.denies_feed(feed) || (self.exclude_mixed_content && feed.mixed_content)
+    }
+
+    /// The timeout for a request to `url`: its entry in
+    /// [`host_timeouts`](Self::host_timeouts) if there is one, otherwise
+    /// the global [`timeout`](Self::timeout).
+    pub fn timeout_for(&self, url: &str) -> Duration {
+        if self.host_timeouts.is_empty() {
+            return self.timeout;
+        }
+        let Ok(url) = Url::parse(url) else {
+            return self.timeout;
+        };
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        url.port()
+            .and_then(|port| self.host_timeouts.get(&format!("{}:{}", host, port)))
+            .or_else(|| self.host_timeouts.get(&host))
+            .copied()
+            .unwrap_or(self.timeout)
+    }
+
+    /// Parses a TOML scan profile. Missing keys keep their default values.
+    ///
+    /// ```toml
+    /// timeout = 30
+    /// extra_headers = [["X-Api-Key", "secret"]]
+    /// keep_unknown = true
+    /// ```
+    pub fn from_toml_str(toml: &str) -> Result<Self> {
+        let config: MinerConfig = toml::from_str(toml).context("Invalid config file")?;
+        for (name, value) in &config.extra_headers {
+            validate_header(name, value)?;
+        }
+        Ok(config)
+    }
+
+    /// Reads and parses a TOML scan profile from `path`.
+    pub fn from_toml_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .context(format!("Failed to read config file: {}", path.display()))?;
+        Self::from_toml_str(&content)
+            .context(format!("Failed to load config file: {}", path.display()))
+    }
+}
+
+fn deserialize_secs<'de, D>(deserializer: D) -> std::result::Result<Duration, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    u64::deserialize(deserializer).map(Duration::from_secs)
+}
+
+fn deserialize_millis<'de, D>(deserializer: D) -> std::result::Result<Duration, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    u64::deserialize(deserializer).map(Duration::from_millis)
+}
+
+fn deserialize_secs_map<'de, D>(
+    deserializer: D,
+) -> std::result::Result<HashMap<String, Duration>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let secs = HashMap::<String, u64>::deserialize(deserializer)?;
+    Ok(secs
+        .into_iter()
+        .map(|(host, secs)| (host.to_ascii_lowercase(), Duration::from_secs(secs)))
+        .collect())
+}
+
+/// Builds the shared HTTP client used for all page and feed requests.
+///
+/// # Errors
+///
+/// Returns an error if one of the configured extra headers has an invalid
+/// name or value, or if the underlying client cannot be constructed.
+pub fn build_client(config: &MinerConfig) -> Result<Client> {
+    if config.danger_accept_invalid_certs {
+        eprintln!(
+            "WARNING: TLS certificate verification is DISABLED. \
+             Connections can be intercepted; only use this with trusted hosts."
+        );
+    }
+
+    client_builder(config)?
+        .build()
+        .context("Failed to build HTTP client")
+}
+
+/// Builds a client like [`build_client`] that never follows redirects, for
+/// [`trace_redirects`].
+pub fn build_trace_client(config: &MinerConfig) -> Result<Client> {
+    client_builder(config)?
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .context("Failed to build HTTP client")
+}
+
+/// Most redirects followed for one request.
+const MAX_REDIRECTS: usize = 10;
+
+fn client_builder(config: &MinerConfig) -> Result<reqwest::blocking::ClientBuilder> {
+    let mut headers = HeaderMap::new();
+    for (name, value) in &config.extra_headers {
+        let (name, value) = validate_header(name, value)?;
+        headers.append(name, value);
+    }
+
+    let mut builder = Client::builder()
+        .timeout(config.timeout)
+        .default_headers(headers)
+        .danger_accept_invalid_certs(config.danger_accept_invalid_certs);
+    if let Some(version) = config.min_tls_version {
+        builder = builder.min_tls_version(match version {
+            TlsVersion::Tls12 => reqwest::tls::Version::TLS_1_2,
+            TlsVersion::Tls13 => reqwest::tls::Version::TLS_1_3,
+        });
+    }
+    match (config.http2_prior_knowledge, config.http1_only) {
+        (true, true) => bail!("http2_prior_knowledge and http1_only are mutually exclusive"),
+        (true, false) => builder = builder.http2_prior_knowledge(),
+        (false, true) => builder = builder.http1_only(),
+        (false, false) => {}
+    }
+    if !config.cookies.is_empty() {
+        builder = builder.cookie_provider(Arc::new(HostCookies::new(&config.cookies)?));
+    }
+    if config.same_host_redirects {
+        builder = builder.redirect(reqwest::redirect::Policy::custom(|attempt| {
+            let from_host = attempt.previous().last().and_then(|url| url.host_str());
+            if attempt.previous().len() > MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else if attempt.url().host_str() == from_host {
+                attempt.follow()
+            } else {
+                attempt.stop()
+            }
+        }));
+    }
+
+    Ok(builder)
+}
+
+/// One response in a redirect chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedirectHop {
+    pub status: u16,
+    pub url: String,
+}
+
+/// Requests `url` and follows its redirects one at a time, returning each
+/// response's status and URL in order, ending with the first response that
+/// is not a redirect. `client` must not follow redirects itself; see
+/// [`build_trace_client`].
+///
+/// # Errors
+///
+/// Returns an error if a request fails, a redirect has no usable
+/// `Location`, or the chain is longer than 10 redirects.
+pub fn trace_redirects(url: &str, client: &Client) -> Result<Vec<RedirectHop>> {
+    let mut hops = Vec::new();
+    let mut current = url.to_string();
+    loop {
+        let response = client
+            .get(&current)
+            .send()
+            .with_context(|| format!("Failed to fetch {}", current))?;
+        let status = response.status();
+        hops.push(RedirectHop {
+            status: status.as_u16(),
+            url: current.clone(),
+        });
+        if !status.is_redirection() {
+            return Ok(hops);
+        }
+        if hops.len() > MAX_REDIRECTS {
+            bail!("Too many redirects from {}", url);
+        }
+        let location = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .with_context(|| format!("Redirect from {} has no Location", current))?;
+        current = resolve_url(&current, location)?;
+    }
+}
+
+/// Cookie store holding the fixed [`MinerConfig::cookies`]. Cookies set by
+/// servers are ignored, so requests only ever carry the configured ones.
+struct HostCookies {
+    /// Ready-made `Cookie` header values by lowercased host.
+    by_host: HashMap<String, HeaderValue>,
+}
+
+impl HostCookies {
+    fn new(cookies: &[(String, String, String)]) -> Result<Self> {
+        let mut pairs: HashMap<String, Vec<String>> = HashMap::new();
+        for (host, name, value) in cookies {
+            pairs
+                .entry(host.trim().to_ascii_lowercase())
+                .or_default()
+                .push(format!("{}={}", name.trim(), value.trim()));
+        }
+
+        let mut by_host = HashMap::new();
+        for (host, pairs) in pairs {
+            let header = HeaderValue::from_str(&pairs.join("; "))
+                .context(format!("Invalid cookie for host: {}", host))?;
+            by_host.insert(host, header);
+        }
+        Ok(HostCookies { by_host })
+    }
+}
+
+impl reqwest::cookie::CookieStore for HostCookies {
+    fn set_cookies(&self, _cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, _url: &Url) {}
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        let host = url.host_str()?.to_ascii_lowercase();
+        self.by_host.get(&host).cloned()
+    }
+}
+
+/// Parses a `host:name=value` cookie specification as accepted by
+/// `--cookie`.
+pub fn parse_cookie(input: &str) -> Result<(String, String, String)> {
+    let invalid = || format!("Invalid cookie (expected \"host:name=value\"): {}", input);
+    let (host, cookie) = input.split_once(':').with_context(invalid)?;
+    let (name, value) = cookie.split_once('=').with_context(invalid)?;
+    let (host, name) = (host.trim(), name.trim());
+    if host.is_empty() || name.is_empty() {
+        bail!(invalid());
+    }
+    Ok((host.to_string(), name.to_string(), value.trim().to_string()))
+}
+
+/// Parses a `host=seconds` timeout override as accepted by
+/// `--timeout-per-host`.
+pub fn parse_host_timeout(input: &str) -> Result<(String, Duration)> {
+    let invalid = || {
+        format!(
+            "Invalid host timeout (expected \"host=seconds\"): {}",
+            input
+        )
+    };
+    let (host, secs) = input.split_once('=').with_context(invalid)?;
+    let host = host.trim().to_ascii_lowercase();
+    let secs: u64 = secs.trim().parse().ok().with_context(invalid)?;
+    if host.is_empty() {
+        bail!(invalid());
+    }
+    Ok((host, Duration::from_secs(secs)))
+}
+
+/// Matches a denylist pattern against a URL; see
+/// [`MinerConfig::feed_url_denylist`].
+fn url_matches_pattern(url: &str, pattern: &str) -> bool {
+    let url = url.to_ascii_lowercase();
+    let pattern = pattern.trim().to_ascii_lowercase();
+    if pattern.is_empty() {
+        return false;
+    }
+    if !pattern.contains('*') {
+        return url.contains(&pattern);
+    }
+
+    // Every literal piece must appear in order, anchored at the ends unless
+    // the pattern starts or ends with a wildcard
+    let pieces: Vec<&str> = pattern.split('*').collect();
+    let (first, last) = (pieces[0], pieces[pieces.len() - 1]);
+    if !url.starts_with(first) || !url[first.len()..].ends_with(last) {
+        return false;
+    }
+
+    let mut rest = &url[first.len()..url.len() - last.len()];
+    for piece in &pieces[1..pieces.len() - 1] {
+        match rest.find(piece) {
+            Some(index) => rest = &rest[index + piece.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Parses a `Name: Value` header specification as accepted by `--header`.
+pub fn parse_header(input: &str) -> Result<(String, String)> {
+    let (name, value) = input.split_once(':').context(format!(
+        "Invalid header (expected \"Name: Value\"): {}",
+        input
+    ))?;
+    let (name, value) = (name.trim(), value.trim());
+    validate_header(name, value)?;
+    Ok((name.to_string(), value.to_string()))
+}
+
+fn validate_header(name: &str, value: &str) -> Result<(HeaderName, HeaderValue)> {
+    let header_name = HeaderName::from_bytes(name.as_bytes())
+        .context(format!("Invalid header name: {}", name))?;
+    let header_value =
+        HeaderValue::from_str(value).context(format!("Invalid value for header {}", name))?;
+    Ok((header_name, header_value))
+}
+
+/// Options controlling how input files are read.
+#[derive(Debug, Clone, Default)]
+pub struct InputOptions {
+    /// Expand `{1..5}` ranges in plain-text lines (see [`expand_url_ranges`]).
+    pub expand_ranges: bool,
+    /// Keep fediverse actor URLs such as `https://host/@user` intact instead
+    /// of reducing them to their domain, so each account is probed.
+    pub keep_actor_paths: bool,
+    /// Keep URLs of these site families intact instead of reducing them to
+    /// their domain, since each channel or community has its own feed.
+    pub known_sites: Vec<KnownSite>,
+    /// When the input path is a directory, also read `.txt` files in its
+    /// subdirectories.
+    pub recursive: bool,
+    /// Input URLs to leave out. They are matched as written, before being
+    /// reduced to their domain, so an exact URL with a path can match.
+    pub exclusions: UrlExclusions,
 }
 
 pub fn read_urls_from_file(path: &Path) -> Result<Vec<String>> {
-    let content =
-        fs::read_to_string(path).context(format!("Failed to read file: {}", path.display()))?;
+    read_urls_from_file_with_options(path, &InputOptions::default())
+}
 
+/// Like [`read_urls_from_file`], applying [`InputOptions`].
+pub fn read_urls_from_file_with_options(
+    path: &Path,
+    options: &InputOptions,
+) -> Result<Vec<String>> {
     let mut urls = Vec::new();
     let mut seen = HashSet::new();
 
-    for line in content.lines() {
+    for entry in read_input_lines(path, options)? {
+        let normalized = normalize_input_url(&entry.url, options);
+        if seen.insert(normalized.clone()) {
+            urls.push(normalized);
+        }
+    }
+
+    Ok(urls)
+}
+
+/// Returns the URLs of an input file, or of every `.txt` file in an input
+/// directory, each with the `[Category]` it was listed under.
+fn read_input_lines(path: &Path, options: &InputOptions) -> Result<Vec<InputEntry>> {
+    let files = if path.is_dir() {
+        input_files_in_dir(path, options.recursive)?
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut entries = Vec::new();
+    for file in files {
+        let reader =
+            open_input_file(&file).context(format!("Failed to read file: {}", file.display()))?;
+        let lines = parse_input_lines(reader)
+            .context(format!("Failed to read file: {}", file.display()))?;
+
+        for (url, category) in lines {
+            let url = with_default_scheme(&normalize_feed_scheme(&url));
+            let urls = if options.expand_ranges {
+                expand_url_ranges(&url)?
+            } else {
+                vec![url]
+            };
+            entries.extend(
+                urls.into_iter()
+                    .filter(|url| !options.exclusions.excludes(url))
+                    .map(|url| InputEntry {
+                        category: category.clone(),
+                        ..InputEntry::new(url)
+                    }),
+            );
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Reads one URL per line, skipping blank lines, `#` comments, and
+/// `[Category]` headers. Lines are trimmed but otherwise returned as
+/// written, duplicates included.
+pub fn parse_urls_from_reader<R: BufRead>(reader: R) -> Result<Vec<String>> {
+    Ok(parse_input_lines(reader)?
+        .into_iter()
+        .map(|(url, _)| url)
+        .collect())
+}
+
+/// Like [`parse_urls_from_reader`], pairing each URL with the most recent
+/// `[Category]` header. An empty `[]` header ends the current category.
+fn parse_input_lines<R: BufRead>(reader: R) -> Result<Vec<(String, Option<String>)>> {
+    let mut lines = Vec::new();
+    let mut category = None;
+
+    for line in reader.lines() {
+        let line = line?;
         let trimmed = line.trim();
         if trimmed.is_empty() || trimmed.starts_with('#') {
             continue;
         }
 
-        let normalized = normalize_to_domain_url(trimmed);
-        if seen.insert(normalized.clone()) {
-            urls.push(normalized);
+        if let Some(name) = trimmed
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+        {
+            category = non_empty(name);
+            continue;
         }
+
+        lines.push((trimmed.to_string(), category.clone()));
     }
 
-    Ok(urls)
+    Ok(lines)
 }
 
-pub fn find_rss_feeds(url: &str, client: &Client) -> Result<Vec<RssFeed>> {
-    // Fetch the page
-    let response = client.get(url).send()?;
-    let html_content = response.text()?;
-    let document = if let Some(head_html) = extract_head_html(&html_content) {
-        Html::parse_fragment(head_html)
+/// Opens an input file for line reading, transparently decompressing it if
+/// it is gzip data (such as a `.txt.gz` list), detected by its magic bytes.
+fn open_input_file(path: &Path) -> io::Result<Box<dyn BufRead>> {
+    let mut reader = BufReader::new(fs::File::open(path)?);
+    if reader.fill_buf()?.starts_with(&[0x1f, 0x8b]) {
+        Ok(Box::new(BufReader::new(flate2::read::GzDecoder::new(
+            reader,
+        ))))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// Lists the `.txt` (and gzipped `.txt.gz`) files in `dir`, sorted by path
+/// so the merged input order is stable.
+fn input_files_in_dir(dir: &Path, recursive: bool) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let entries =
+        fs::read_dir(dir).context(format!("Failed to read directory: {}", dir.display()))?;
+
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            if recursive {
+                files.extend(input_files_in_dir(&path, true)?);
+            }
+        } else if path.file_name().is_some_and(|name| {
+            let name = name.to_string_lossy().to_ascii_lowercase();
+            name.ends_with(".txt") || name.ends_with(".txt.gz")
+        }) {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Upper bound on the URLs a single input line may expand to.
+const MAX_RANGE_EXPANSION: usize = 10_000;
+
+/// Expands shell-style numeric ranges, so `https://x/p{1..3}` becomes
+/// `https://x/p1`, `https://x/p2` and `https://x/p3`.
+///
+/// Ranges may count down (`{3..1}`), keep zero padding (`{01..10}`), and
+/// combine (`{1..2}-{1..2}` yields four URLs). Braces that don't hold a
+/// `start..end` range are left untouched.
+pub fn expand_url_ranges(line: &str) -> Result<Vec<String>> {
+    let mut expanded = vec![String::new()];
+    let mut rest = line;
+
+    while let Some(open) = rest.find('{') {
+        let (prefix, after_open) = rest.split_at(open);
+        let range = after_open
+            .find('}')
+            .and_then(|close| Some((parse_brace_range(&after_open[1..close])?, close)));
+
+        let Some((values, close)) = range else {
+            // Not a range: keep the brace literally and scan past it
+            for url in &mut expanded {
+                url.push_str(prefix);
+                url.push('{');
+            }
+            rest = &after_open[1..];
+            continue;
+        };
+
+        if expanded.len() * values.len() > MAX_RANGE_EXPANSION {
+            bail!(
+                "Range expansion of '{}' exceeds {} URLs",
+                line,
+                MAX_RANGE_EXPANSION
+            );
+        }
+        expanded = expanded
+            .iter()
+            .flat_map(|url| {
+                values
+                    .iter()
+                    .map(move |value| format!("{}{}{}", url, prefix, value))
+            })
+            .collect();
+        rest = &after_open[close + 1..];
+    }
+
+    for url in &mut expanded {
+        url.push_str(rest);
+    }
+    Ok(expanded)
+}
+
+/// Parses the inside of a `{start..end}` brace into its values.
+fn parse_brace_range(inner: &str) -> Option<Vec<String>> {
+    let (start, end) = inner.split_once("..")?;
+    let is_number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    if !is_number(start) || !is_number(end) {
+        return None;
+    }
+
+    let from: u64 = start.parse().ok()?;
+    let to: u64 = end.parse().ok()?;
+    if from.abs_diff(to) >= MAX_RANGE_EXPANSION as u64 {
+        return None;
+    }
+
+    let padded = |s: &str| s.len() > 1 && s.starts_with('0');
+    let width = if padded(start) || padded(end) {
+        start.len().max(end.len())
     } else {
-        Html::parse_document(&html_content)
+        0
     };
 
-    let mut feeds = Vec::new();
+    let values: Vec<u64> = if from <= to {
+        (from..=to).collect()
+    } else {
+        (to..=from).rev().collect()
+    };
+    Some(
+        values
+            .into_iter()
+            .map(|value| format!("{:0width$}", value, width = width))
+            .collect(),
+    )
+}
 
-    // Look for RSS/Atom feed links in the HTML
-    let link_selector =
-        Selector::parse("link[type='application/rss+xml'], link[type='application/atom+xml']")
-            .expect("Failed to parse CSS selector");
+/// One input URL together with optional per-URL overrides.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct InputEntry {
+    pub url: String,
+    /// Title to use for every feed discovered from this URL.
+    #[serde(default, deserialize_with = "deserialize_non_empty")]
+    pub title: Option<String>,
+    /// Keep only feeds of this type for this URL. Deserializes from any
+    /// [`FeedType`] name: `rss`, `atom`, `json` (needs a parser that
+    /// recognizes JSON Feed), or `unknown` (needs
+    /// [`MinerConfig::keep_unknown`]).
+    #[serde(default)]
+    pub feed_type: Option<FeedType>,
+    /// OPML folder for feeds discovered from this URL; set by `[Category]`
+    /// lines in plain-text input.
+    #[serde(default, deserialize_with = "deserialize_non_empty")]
+    pub category: Option<String>,
+}
 
-    for element in document.select(&link_selector) {
-        if let Some(href) = element.value().attr("href") {
-            let feed_url = resolve_url(url, href)?;
+impl InputEntry {
+    pub fn new(url: impl Into<String>) -> Self {
+        InputEntry {
+            url: url.into(),
+            title: None,
+            feed_type: None,
+            category: None,
+        }
+    }
+}
 
-            // Validate the feed and get its type
-            if let Some(feed_type) = validate_rss_feed(&feed_url, client) {
-                let title = element
-                    .value()
-                    .attr("title")
-                    .unwrap_or("Untitled Feed")
-                    .to_string();
-
-                feeds.push(RssFeed {
-                    title,
-                    url: feed_url,
-                    html_url: url.to_string(),
-                    feed_type,
-                });
+/// Treats empty strings (e.g. blank CSV cells) as missing values.
+fn deserialize_non_empty<'de, D>(deserializer: D) -> std::result::Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.filter(|v| !v.trim().is_empty()))
+}
+
+/// Reads input entries, choosing the format by file extension.
+///
+/// * `.json` - an array of objects with `url` and optional `title`,
+///   `feed_type` (`"rss"`, `"atom"`, `"json"`, `"unknown"`), and `category`
+///   keys
+/// * `.csv` - a header row naming the same columns, then one entry per row
+/// * anything else - plain text as accepted by [`read_urls_from_file`]
+///
+/// URLs are reduced to their domain and deduplicated like plain text input;
+/// the first entry for a domain wins.
+pub fn read_input_entries(path: &Path) -> Result<Vec<InputEntry>> {
+    read_input_entries_with_options(path, &InputOptions::default())
+}
+
+/// Like [`read_input_entries`], applying [`InputOptions`]. Range expansion
+/// only applies to plain-text input.
+pub fn read_input_entries_with_options(
+    path: &Path,
+    options: &InputOptions,
+) -> Result<Vec<InputEntry>> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+
+    let entries: Vec<InputEntry> = match extension.as_deref() {
+        Some("json") => {
+            let content = fs::read_to_string(path)
+                .context(format!("Failed to read file: {}", path.display()))?;
+            serde_json::from_str(&content)
+                .context(format!("Invalid JSON input file: {}", path.display()))?
+        }
+        Some("csv") => csv::Reader::from_path(path)
+            .context(format!("Failed to read file: {}", path.display()))?
+            .deserialize()
+            .collect::<std::result::Result<_, _>>()
+            .context(format!("Invalid CSV input file: {}", path.display()))?,
+        _ => read_input_lines(path, options)?,
+    };
+
+    let mut seen = HashSet::new();
+    Ok(entries
+        .into_iter()
+        .filter_map(|entry| {
+            let url = with_default_scheme(&normalize_feed_scheme(entry.url.trim()));
+            (!options.exclusions.excludes(&url)).then(|| InputEntry {
+                url: normalize_input_url(&url, options),
+                ..entry
+            })
+        })
+        .filter(|entry| seen.insert(entry.url.clone()))
+        .collect())
+}
+
+/// Picks `count` of `items` at random, keeping their input order, so a
+/// quick scan of a sample can estimate a large list's feed yield. The same
+/// `seed` always picks the same items; all items are kept when there are
+/// no more than `count`.
+pub fn sample_inputs<T>(items: Vec<T>, count: usize, seed: u64) -> Vec<T> {
+    if items.len() <= count {
+        return items;
+    }
+
+    // Partial Fisher-Yates over the indices, then restore input order
+    let mut rng = JitterRng::new(seed);
+    let mut indices: Vec<usize> = (0..items.len()).collect();
+    for i in 0..count {
+        let remaining = (indices.len() - i) as u64;
+        let j = i + (rng.next_u64() % remaining) as usize;
+        indices.swap(i, j);
+    }
+    let mut chosen = vec![false; items.len()];
+    for &index in &indices[..count] {
+        chosen[index] = true;
+    }
+    items
+        .into_iter()
+        .zip(chosen)
+        .filter_map(|(item, chosen)| chosen.then_some(item))
+        .collect()
+}
+
+/// URLs and hosts to leave out of a run, read from a file in the input
+/// format. A line naming just a site, such as `example.com` or
+/// `https://example.com/`, excludes every URL on that host, `www.` or not;
+/// a line with a path or query excludes only that exact URL.
+#[derive(Debug, Clone, Default)]
+pub struct UrlExclusions {
+    urls: HashSet<String>,
+    hosts: HashSet<String>,
+}
+
+impl UrlExclusions {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let mut exclusions = UrlExclusions::default();
+        for url in read_feed_urls_from_file(path)? {
+            let Ok(parsed) = Url::parse(&url) else {
+                continue;
+            };
+            match exclusion_host(&parsed) {
+                Some(host) if parsed.path() == "/" && parsed.query().is_none() => {
+                    exclusions.hosts.insert(host);
+                }
+                _ => {
+                    exclusions
+                        .urls
+                        .insert(url.trim_end_matches('/').to_string());
+                }
             }
         }
+        Ok(exclusions)
     }
 
-    // If no feeds found in HTML, try common RSS feed URLs
-    if feeds.is_empty() {
-        let common_paths = vec![
-            "/feed",
-            "/rss",
-            "/feed.xml",
-            "/rss.xml",
-            "/atom.xml",
-            "/index.xml",
-        ];
+    /// Returns true if `url` is listed exactly or is on an excluded host.
+    pub fn excludes(&self, url: &str) -> bool {
+        self.urls.contains(url.trim_end_matches('/'))
+            || Url::parse(url)
+                .ok()
+                .and_then(|url| exclusion_host(&url))
+                .is_some_and(|host| self.hosts.contains(&host))
+    }
+
+    pub fn len(&self) -> usize {
+        self.urls.len() + self.hosts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The host a [`UrlExclusions`] entry matches, without any `www.`.
+fn exclusion_host(url: &Url) -> Option<String> {
+    let host = url.host_str()?;
+    Some(host.strip_prefix("www.").unwrap_or(host).to_string())
+}
+
+/// Applies per-URL [`InputEntry`] overrides to discovered feeds, matched by
+/// the feed's `html_url`.
+#[derive(Debug, Clone, Default)]
+pub struct InputOverrides {
+    entries: HashMap<String, InputEntry>,
+}
+
+impl InputOverrides {
+    pub fn new(entries: &[InputEntry]) -> Self {
+        InputOverrides {
+            entries: entries
+                .iter()
+                .filter(|entry| {
+                    entry.title.is_some() || entry.feed_type.is_some() || entry.category.is_some()
+                })
+                .map(|entry| (entry.url.clone(), entry.clone()))
+                .collect(),
+        }
+    }
+
+    /// Returns the feed with overrides applied, or `None` if its entry
+    /// expects a different feed type.
+    pub fn apply(&self, mut feed: RssFeed) -> Option<RssFeed> {
+        if let Some(entry) = self.entries.get(&feed.html_url) {
+            if entry
+                .feed_type
+                .is_some_and(|expected| expected != feed.feed_type)
+            {
+                return None;
+            }
+            if let Some(title) = &entry.title {
+                feed.title = title.clone();
+            }
+            if entry.category.is_some() {
+                feed.folder = entry.category.clone();
+            }
+        }
+        Some(feed)
+    }
+
+    pub fn apply_all(&self, feeds: Vec<RssFeed>) -> Vec<RssFeed> {
+        feeds
+            .into_iter()
+            .filter_map(|feed| self.apply(feed))
+            .collect()
+    }
+}
+
+/// Reads feed URLs from a file without reducing them to their domain.
+///
+/// Uses the same comment and blank-line rules as [`read_urls_from_file`].
+pub fn read_feed_urls_from_file(path: &Path) -> Result<Vec<String>> {
+    read_feed_urls_from_file_with_options(path, &InputOptions::default())
+}
+
+/// Like [`read_feed_urls_from_file`], applying [`InputOptions`].
+pub fn read_feed_urls_from_file_with_options(
+    path: &Path,
+    options: &InputOptions,
+) -> Result<Vec<String>> {
+    let mut seen = HashSet::new();
+    Ok(read_input_lines(path, options)?
+        .into_iter()
+        .map(|entry| entry.url)
+        .filter(|url| seen.insert(url.clone()))
+        .collect())
+}
+
+pub fn find_rss_feeds(url: &str, client: &Client) -> Result<Vec<RssFeed>> {
+    find_rss_feeds_with_config(url, client, &MinerConfig::default())
+}
+
+pub fn find_rss_feeds_with_config(
+    url: &str,
+    client: &Client,
+    config: &MinerConfig,
+) -> Result<Vec<RssFeed>> {
+    let stats = RunStats::new(config);
+    let feeds =
+        find_rss_feeds_recorded(url, client, config, &stats).map(|discovery| discovery.feeds);
+    stats.save_validation_cache();
+    feeds
+}
+
+/// Finds the feeds advertised by already-fetched HTML, as if it had been
+/// fetched from `base_url`: `<link>` tags, `<meta>` tags, and feed-like
+/// anchors, resolved against the page's `<base href>` if it has one. Only
+/// the candidates are fetched, to validate them.
+pub fn find_feeds_in_html(html: &str, base_url: &str, client: &Client) -> Result<Vec<RssFeed>> {
+    let config = MinerConfig {
+        scan_anchors: true,
+        ..Default::default()
+    };
+    find_feeds_in_html_with_config(html, base_url, client, &config)
+}
+
+pub fn find_feeds_in_html_with_config(
+    html: &str,
+    base_url: &str,
+    client: &Client,
+    config: &MinerConfig,
+) -> Result<Vec<RssFeed>> {
+    let stats = RunStats::new(config);
+    let feeds = html_feeds(html, base_url, client, config, &stats).map(|(feeds, _)| feeds);
+    stats.save_validation_cache();
+    let mut feeds = feeds?;
+    feeds.retain(|feed| !config.excludes_feed(feed));
+    set_source_title(&mut feeds, html);
+    Ok(feeds)
+}
+
+/// Default [`MinerConfig::probe_paths`], in the order they are tried.
+pub const DEFAULT_PROBE_PATHS: [&str; 6] = [
+    "/feed",
+    "/rss",
+    "/feed.xml",
+    "/rss.xml",
+    "/atom.xml",
+    "/index.xml",
+];
+
+/// Default [`MinerConfig::meta_feed_selector`]: `<meta name="alternate">`
+/// tags pointing at something that looks like a feed file.
+pub const DEFAULT_META_FEED_SELECTOR: &str = "meta[name='alternate'][content*='.xml'], \
+     meta[name='alternate'][content*='.rss'], meta[name='alternate'][content*='.atom']";
+
+const FEED_LINK_SELECTOR: &str =
+    "link[type='application/rss+xml'], link[type='application/atom+xml']";
+
+/// Fetches `url` once and returns the feeds its `<link>` tags advertise,
+/// without fetching or validating any of them.
+///
+/// Much faster than [`find_rss_feeds`] but less accurate: the links may be
+/// dead, and the feed type is taken from the tag's `type` attribute.
+pub fn peek_feeds(url: &str, client: &Client) -> Result<Vec<RssFeed>> {
+    peek_feeds_with_config(url, client, &MinerConfig::default())
+}
+
+/// Like [`peek_feeds`], fetching the page with `config`'s timeouts,
+/// retries, and request limit.
+pub fn peek_feeds_with_config(
+    url: &str,
+    client: &Client,
+    config: &MinerConfig,
+) -> Result<Vec<RssFeed>> {
+    let stats = RunStats::new(config);
+    let permit = stats.request_permit();
+    let response = send_with_retries(get_request(client, url, config), config)?;
+    let html_content = read_text(response, url, &stats)?;
+    drop(permit);
+    let document = parse_page_head(&html_content);
+    let base_url = document_base_url(&document, url);
+    let link_selector = Selector::parse(FEED_LINK_SELECTOR).expect("Failed to parse CSS selector");
+
+    let mut feeds = Vec::new();
+    for element in document.select(&link_selector) {
+        let Some(href) = element.value().attr("href") else {
+            continue;
+        };
+        let feed_type = match element.value().attr("type") {
+            Some("application/atom+xml") => FeedType::Atom,
+            _ => FeedType::Rss,
+        };
+        let title = element.value().attr("title").unwrap_or(UNTITLED_FEED);
+
+        let mut feed = RssFeed::new(title, resolve_url(&base_url, href)?, url, feed_type);
+        feed.discovery_method = Some(DiscoveryMethod::Link);
+        feeds.push(feed);
+    }
+
+    Ok(feeds)
+}
+
+/// Returns the URL relative links on a page resolve against: its
+/// `<base href>` if it declares a valid one, otherwise the page URL.
+fn document_base_url(document: &Html, page_url: &str) -> String {
+    let base_selector = Selector::parse("base[href]").expect("Failed to parse CSS selector");
+    document
+        .select(&base_selector)
+        .next()
+        .and_then(|element| element.value().attr("href"))
+        .and_then(|href| resolve_url(page_url, href).ok())
+        .unwrap_or_else(|| page_url.to_string())
+}
+
+/// Parses just the `<head>` of a page when it can be located, since that is
+/// where feed `<link>` tags live.
+fn parse_page_head(html_content: &str) -> Html {
+    if let Some(head_html) = extract_head_html(html_content) {
+        Html::parse_fragment(head_html)
+    } else {
+        Html::parse_document(html_content)
+    }
+}
+
+/// Why a successfully processed URL yielded no feeds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoFeedsReason {
+    /// The page answered with this HTTP error status, and no common feed
+    /// path worked either.
+    PageStatus(u16),
+    /// The page is a bot-protection challenge (e.g. Cloudflare's "Just a
+    /// moment..."), so its real content was never seen.
+    ChallengePage,
+    /// The page advertised feeds in `<link>` tags, but none of them
+    /// validated and no fallback worked.
+    InvalidFeedLinks,
+    /// The page has no feed `<link>` tags and no probed anchor, common
+    /// path, or sitemap entry served a valid feed.
+    NoFeedLinks,
+    /// Feeds were found, but [`MinerConfig::feed_url_denylist`] or
+    /// [`MinerConfig::exclude_mixed_content`] excluded all of them.
+    Denylisted,
+}
+
+impl fmt::Display for NoFeedsReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NoFeedsReason::PageStatus(status) => write!(f, "page returned HTTP {}", status),
+            NoFeedsReason::ChallengePage => write!(f, "blocked by a bot challenge page"),
+            NoFeedsReason::InvalidFeedLinks => write!(f, "advertised feeds failed validation"),
+            NoFeedsReason::NoFeedLinks => write!(f, "no feed links and no common feed paths"),
+            NoFeedsReason::Denylisted => {
+                write!(f, "all feeds excluded by the denylist or as mixed content")
+            }
+        }
+    }
+}
+
+/// Returned for URLs left unfetched once [`MinerConfig::max_page_fetches`]
+/// pages have been fetched.
+#[derive(Debug)]
+struct PageBudgetExhausted;
+
+impl fmt::Display for PageBudgetExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "page fetch limit reached")
+    }
+}
+
+impl std::error::Error for PageBudgetExhausted {}
+
+/// The feeds found for one URL, and why there were none if so.
+struct UrlDiscovery {
+    feeds: Vec<RssFeed>,
+    /// Set exactly when `feeds` is empty.
+    no_feeds_reason: Option<NoFeedsReason>,
+}
+
+fn find_rss_feeds_recorded(
+    url: &str,
+    client: &Client,
+    config: &MinerConfig,
+    stats: &RunStats,
+) -> Result<UrlDiscovery> {
+    let mut discovery = find_page_feeds(url, client, config, stats);
+    let found_any = discovery
+        .as_ref()
+        .is_ok_and(|discovery| !discovery.feeds.is_empty());
+    if config.probe_subdomains && !(config.first_feed_only && found_any) {
+        // Most guesses won't resolve; only the feeds they turn up matter
+        let mut found = Vec::new();
+        for subdomain_url in subdomain_urls(url) {
+            if let Ok(subdomain) = find_page_feeds(&subdomain_url, client, config, stats) {
+                found.extend(subdomain.feeds);
+            }
+            if config.first_feed_only && !found.is_empty() {
+                break;
+            }
+        }
+        if !found.is_empty() {
+            let mut feeds = discovery.map(|page| page.feeds).unwrap_or_default();
+            feeds.extend(found);
+            discovery = Ok(UrlDiscovery {
+                feeds,
+                no_feeds_reason: None,
+            });
+        }
+    }
+
+    let mut discovery = discovery?;
+    if config.follow_related_feeds && !config.first_feed_only {
+        let related = related_feeds(&discovery.feeds, client, config, stats);
+        discovery.feeds.extend(related);
+    }
+    if !discovery.feeds.is_empty() {
+        discovery.feeds.retain(|feed| !config.excludes_feed(feed));
+        if discovery.feeds.is_empty() {
+            discovery.no_feeds_reason = Some(NoFeedsReason::Denylisted);
+        }
+    }
+    if let Some(limit) = config.max_feeds_per_site {
+        discovery.feeds = cap_feeds_per_site(discovery.feeds, limit.max(1));
+    }
+    if config.reverse_dns_titles {
+        name_ip_feeds(&mut discovery.feeds);
+    }
+    Ok(discovery)
+}
+
+/// Upper bound on the feeds kept per site by
+/// [`MinerConfig::follow_related_feeds`].
+pub const MAX_RELATED_FEEDS: usize = 5;
+
+/// Validates the feeds that `feeds` point to, skipping ones already found,
+/// and returns the first [`MAX_RELATED_FEEDS`] that are valid.
+fn related_feeds(
+    feeds: &[RssFeed],
+    client: &Client,
+    config: &MinerConfig,
+    stats: &RunStats,
+) -> Vec<RssFeed> {
+    let mut seen: HashSet<String> = feeds
+        .iter()
+        .flat_map(|feed| [feed.url.clone(), feed.raw_url.clone()])
+        .collect();
+    let mut related = Vec::new();
+    for feed in feeds {
+        for feed_url in stats.related_feeds(&feed.raw_url) {
+            if related.len() >= MAX_RELATED_FEEDS {
+                return related;
+            }
+            if !seen.insert(feed_url.clone()) {
+                continue;
+            }
+            if let Some(info) =
+                validate_rss_feed(&feed_url, Some(&feed.html_url), client, config, stats)
+            {
+                let title = match info.title.trim() {
+                    "" => extract_title_from_url(&feed_url),
+                    title => title.to_string(),
+                };
+                related.push(discovered_feed(
+                    &title,
+                    feed_url,
+                    &feed.html_url,
+                    info,
+                    DiscoveryMethod::Related,
+                ));
+            }
+        }
+    }
+    related
+}
+
+/// Subdomains probed by [`MinerConfig::probe_subdomains`].
+pub const PROBED_SUBDOMAINS: [&str; 3] = ["blog", "www", "news"];
+
+/// Builds the root URLs of the [`PROBED_SUBDOMAINS`] of `url`'s site,
+/// leaving out `url`'s own host. Hosts that are IP addresses or a single
+/// label get none.
+fn subdomain_urls(url: &str) -> Vec<String> {
+    let Ok(parsed) = Url::parse(url) else {
+        return Vec::new();
+    };
+    let Some(url::Host::Domain(host)) = parsed.host() else {
+        return Vec::new();
+    };
+    let host = host.to_ascii_lowercase();
+    let domain = match host.split_once('.') {
+        Some((label, rest)) if PROBED_SUBDOMAINS.contains(&label) => rest,
+        _ => host.as_str(),
+    };
+    if !domain.contains('.') {
+        return Vec::new();
+    }
+
+    PROBED_SUBDOMAINS
+        .iter()
+        .map(|label| format!("{}.{}", label, domain))
+        .filter(|subdomain| *subdomain != host)
+        .filter_map(|subdomain| {
+            let mut root = parsed.clone();
+            root.set_host(Some(&subdomain)).ok()?;
+            root.set_path("/");
+            root.set_query(None);
+            root.set_fragment(None);
+            Some(root.to_string())
+        })
+        .collect()
+}
+
+/// Keeps at most `limit` feeds per `html_url` host, choosing each site's
+/// most main-feed-like URLs and otherwise keeping discovery order.
+fn cap_feeds_per_site(feeds: Vec<RssFeed>, limit: usize) -> Vec<RssFeed> {
+    let mut ranked: Vec<(usize, &RssFeed)> = feeds.iter().enumerate().collect();
+    ranked.sort_by_key(|(index, feed)| (secondary_feed_rank(&feed.url), *index));
+
+    let mut per_site: HashMap<String, usize> = HashMap::new();
+    let mut kept: Vec<usize> = ranked
+        .into_iter()
+        .filter(|(_, feed)| {
+            let count = per_site
+                .entry(host_key(&feed.html_url).unwrap_or_default())
+                .or_default();
+            *count += 1;
+            *count <= limit
+        })
+        .map(|(index, _)| index)
+        .collect();
+    kept.sort_unstable();
+
+    let mut feeds: Vec<Option<RssFeed>> = feeds.into_iter().map(Some).collect();
+    kept.into_iter()
+        .filter_map(|index| feeds[index].take())
+        .collect()
+}
+
+/// Orders feed URLs from most to least likely to be a site's main feed:
+/// comment, category, tag, and author feeds last, then deeper paths.
+fn secondary_feed_rank(url: &str) -> (bool, usize) {
+    const SECONDARY_MARKERS: [&str; 5] = ["comment", "/category/", "/tag/", "/tags/", "/author/"];
+    let lower = url.to_ascii_lowercase();
+    let secondary = SECONDARY_MARKERS
+        .iter()
+        .any(|marker| lower.contains(marker));
+    let depth = Url::parse(url)
+        .ok()
+        .and_then(|url| {
+            url.path_segments()
+                .map(|segments| segments.filter(|s| !s.is_empty()).count())
+        })
+        .unwrap_or(usize::MAX);
+    (secondary, depth)
+}
+
+/// Recognizes bot-protection interstitials served instead of the page.
+fn is_challenge_page(status: StatusCode, headers: &HeaderMap, body: &str) -> bool {
+    if headers.contains_key("cf-mitigated") {
+        return true;
+    }
+    matches!(
+        status,
+        StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+    ) && [
+        "<title>Just a moment...</title>",
+        "challenge-platform",
+        "cf-browser-verification",
+        "captcha",
+    ]
+    .iter()
+    .any(|marker| body.contains(marker))
+}
+
+/// Runs every discovery strategy for one page, before run-wide filtering.
+fn find_page_feeds(
+    url: &str,
+    client: &Client,
+    config: &MinerConfig,
+    stats: &RunStats,
+) -> Result<UrlDiscovery> {
+    find_page_feeds_following(
+        url,
+        client,
+        config,
+        stats,
+        config.amp_follow_depth,
+        MAX_META_REFRESHES,
+    )
+}
+
+/// Most `<meta http-equiv="refresh">` redirects followed from one page.
+const MAX_META_REFRESHES: usize = 3;
+
+/// [`find_page_feeds`], allowed to leave `amp_follows` more AMP pages for
+/// their canonical page and to follow `refreshes` more meta refreshes.
+fn find_page_feeds_following(
+    url: &str,
+    client: &Client,
+    config: &MinerConfig,
+    stats: &RunStats,
+    amp_follows: usize,
+    refreshes: usize,
+) -> Result<UrlDiscovery> {
+    // Some sites have predictable feed URLs but don't always advertise them
+    if let Some(feed_url) = known_site_feed_url(url, &config.known_sites)
+        && let Some(info) = validate_rss_feed(&feed_url, Some(url), client, config, stats)
+    {
+        let title = match info.title.as_str() {
+            "" => extract_title_from_url(url),
+            title => title.to_string(),
+        };
+        return Ok(UrlDiscovery {
+            feeds: vec![discovered_feed(
+                &title,
+                feed_url,
+                url,
+                info,
+                DiscoveryMethod::KnownSite,
+            )],
+            no_feeds_reason: None,
+        });
+    }
+
+    // Mastodon serves every account's posts at `<profile>.rss`
+    if config.probe_fediverse
+        && let Some(feed_url) = fediverse_actor_feed_url(url)
+        && let Some(info) = validate_rss_feed(&feed_url, Some(url), client, config, stats)
+    {
+        // "@user@host", the account's fediverse address
+        let handle = feed_url.rsplit('/').next().unwrap_or_default();
+        let host = Url::parse(url)?.host_str().unwrap_or_default().to_string();
+        let title = format!("{}@{}", handle.trim_end_matches(".rss"), host);
+        return Ok(UrlDiscovery {
+            feeds: vec![discovered_feed(
+                &title,
+                feed_url,
+                url,
+                info,
+                DiscoveryMethod::Fediverse,
+            )],
+            no_feeds_reason: None,
+        });
+    }
+
+    // Fetch the page, unless the run has used up its page budget
+    if let Some(limit) = config.max_page_fetches
+        && stats.pages_fetched.fetch_add(1, Ordering::SeqCst) >= limit
+    {
+        return Err(PageBudgetExhausted.into());
+    }
+    let permit = stats.request_permit();
+    let started = Instant::now();
+    let page = send_with_retries(get_request(client, url, config), config).and_then(|response| {
+        let status = response.status();
+        let headers = response.headers().clone();
+        read_text(response, url, stats).map(|body| (status, headers, body))
+    });
+    stats.record_page(url, started.elapsed());
+    drop(permit);
+    let (page_status, page_headers, html_content) = match (page, config.min_tls_version) {
+        (Ok(page), _) => page,
+        (Err(e), Some(version)) if is_tls_version_error(&e) => {
+            return Err(anyhow::Error::new(e)
+                .context(format!("server does not support {} or newer", version)));
+        }
+        (Err(e), _) => match tls_error(&e) {
+            Some(tls) => return Err(anyhow::Error::new(e).context(format!("TLS error: {}", tls))),
+            None => return Err(e.into()),
+        },
+    };
+
+    // Every strategy below works from this one fetched body, and a URL
+    // that is a feed itself needs no second fetch to validate it
+    if page_status.is_success()
+        && let Some(feed) = direct_feed(url, &page_headers, &html_content, config, stats)
+    {
+        return Ok(UrlDiscovery {
+            feeds: vec![feed],
+            no_feeds_reason: None,
+        });
+    }
+    let (mut feeds, feed_links) = html_feeds(&html_content, url, client, config, stats)?;
+
+    // Redirect stubs only point at the page that has the feed links
+    if feeds.is_empty()
+        && refreshes > 0
+        && let Some(target_url) = meta_refresh_url(&html_content, url)
+        && let Ok(target) = find_page_feeds_following(
+            &target_url,
+            client,
+            config,
+            stats,
+            amp_follows,
+            refreshes - 1,
+        )
+        && !target.feeds.is_empty()
+    {
+        return Ok(target);
+    }
+
+    // AMP versions often drop the feed links of the page they mirror
+    if feeds.is_empty()
+        && amp_follows > 0
+        && let Some(canonical_url) = amp_canonical_url(&html_content, url)
+        && let Ok(canonical) = find_page_feeds_following(
+            &canonical_url,
+            client,
+            config,
+            stats,
+            amp_follows - 1,
+            refreshes,
+        )
+        && !canonical.feeds.is_empty()
+    {
+        return Ok(canonical);
+    }
+
+    // If no feeds found in HTML, try common RSS feed URLs
+    if feeds.is_empty() {
+        for path in &config.probe_paths {
+            if let Ok(feed_url) = resolve_url(url, path)
+                && (!config.use_head_probe || head_probe_allows(&feed_url, client, config, stats))
+                && let Some(info) = validate_rss_feed(&feed_url, Some(url), client, config, stats)
+            {
+                feeds.push(discovered_feed(
+                    &extract_title_from_url(url),
+                    feed_url,
+                    url,
+                    info,
+                    DiscoveryMethod::CommonPath,
+                ));
+                break; // Only add the first valid common feed found
+            }
+        }
+    }
+
+    // Sitemaps list every URL on the site, feeds included
+    if feeds.is_empty() && config.scan_sitemap {
+        for feed_url in sitemap_feed_candidates(url, client, config, stats) {
+            if let Some(info) = validate_rss_feed(&feed_url, Some(url), client, config, stats) {
+                feeds.push(discovered_feed(
+                    &extract_title_from_url(url),
+                    feed_url,
+                    url,
+                    info,
+                    DiscoveryMethod::Sitemap,
+                ));
+                break;
+            }
+        }
+    }
+
+    set_source_title(&mut feeds, &html_content);
+
+    let no_feeds_reason = feeds.is_empty().then(|| {
+        if is_challenge_page(page_status, &page_headers, &html_content) {
+            NoFeedsReason::ChallengePage
+        } else if !page_status.is_success() {
+            NoFeedsReason::PageStatus(page_status.as_u16())
+        } else if feed_links > 0 {
+            NoFeedsReason::InvalidFeedLinks
+        } else {
+            NoFeedsReason::NoFeedLinks
+        }
+    });
+    Ok(UrlDiscovery {
+        feeds,
+        no_feeds_reason,
+    })
+}
+
+/// Runs the `<link>`, `<meta>`, and anchor strategies on a page's HTML,
+/// resolving candidates against its `<base href>` or `page_url`, and
+/// validates them. Also returns how many feed links the page advertised.
+fn html_feeds(
+    html_content: &str,
+    page_url: &str,
+    client: &Client,
+    config: &MinerConfig,
+    stats: &RunStats,
+) -> Result<(Vec<RssFeed>, usize)> {
+    // Every HTML strategy below works from this one parsed body
+    let document = parse_page_head(html_content);
+    let base_url = document_base_url(&document, page_url);
+
+    let mut feeds = Vec::new();
+
+    // Look for RSS/Atom feed links in the HTML
+    let link_selector = Selector::parse(FEED_LINK_SELECTOR).expect("Failed to parse CSS selector");
+    let mut feed_links = 0;
+
+    for element in document.select(&link_selector) {
+        if config.require_alternate_rel && !rel_is_alternate(element.value().attr("rel")) {
+            continue;
+        }
+        if let Some(href) = element.value().attr("href") {
+            feed_links += 1;
+            let feed_url = resolve_url(&base_url, href)?;
+
+            // Validate the feed and get its type
+            if let Some(info) = validate_rss_feed(&feed_url, Some(page_url), client, config, stats)
+            {
+                let title = element.value().attr("title").unwrap_or(UNTITLED_FEED);
+
+                feeds.push(discovered_feed(
+                    title,
+                    feed_url,
+                    page_url,
+                    info,
+                    DiscoveryMethod::Link,
+                ));
+                if config.first_feed_only {
+                    break;
+                }
+            }
+        }
+    }
+
+    // Some CMSs advertise the feed in a <meta> tag instead
+    if feeds.is_empty()
+        && !config.meta_feed_selector.is_empty()
+        && let Ok(meta_selector) = Selector::parse(&config.meta_feed_selector)
+    {
+        for element in document.select(&meta_selector) {
+            let Some(content) = element.value().attr("content") else {
+                continue;
+            };
+            feed_links += 1;
+            let Ok(feed_url) = resolve_url(&base_url, content.trim()) else {
+                continue;
+            };
+            if feed_url.starts_with("http")
+                && let Some(info) =
+                    validate_rss_feed(&feed_url, Some(page_url), client, config, stats)
+            {
+                feeds.push(discovered_feed(
+                    UNTITLED_FEED,
+                    feed_url,
+                    page_url,
+                    info,
+                    DiscoveryMethod::Meta,
+                ));
+                if config.first_feed_only {
+                    break;
+                }
+            }
+        }
+    }
+
+    // The head alone is not enough for the strategies below
+    let full_document = std::cell::OnceCell::new();
+    let full_document = || full_document.get_or_init(|| Html::parse_document(html_content));
+
+    // Structured-data sites may declare the feed in JSON-LD
+    if feeds.is_empty() && config.scan_json_ld {
+        for feed_url in json_ld_feed_urls(full_document(), &base_url) {
+            feed_links += 1;
+            if let Some(info) = validate_rss_feed(&feed_url, Some(page_url), client, config, stats)
+            {
+                feeds.push(discovered_feed(
+                    &extract_title_from_url(page_url),
+                    feed_url,
+                    page_url,
+                    info,
+                    DiscoveryMethod::JsonLd,
+                ));
+                if config.first_feed_only {
+                    break;
+                }
+            }
+        }
+    }
+
+    // Minimalist pages may only link their feed from the body
+    if feeds.is_empty() && config.scan_anchors {
+        for feed_url in feed_like_anchors(full_document(), &base_url, config.max_anchor_probes) {
+            if let Some(info) = validate_rss_feed(&feed_url, Some(page_url), client, config, stats)
+            {
+                feeds.push(discovered_feed(
+                    &extract_title_from_url(page_url),
+                    feed_url,
+                    page_url,
+                    info,
+                    DiscoveryMethod::Anchor,
+                ));
+                if config.first_feed_only {
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok((feeds, feed_links))
+}
+
+/// Records the page's `<title>`, whitespace collapsed, as the
+/// [`RssFeed::source_title`] of the feeds found on it.
+fn set_source_title(feeds: &mut [RssFeed], html_content: &str) {
+    if feeds.is_empty() {
+        return;
+    }
+    let document = parse_page_head(html_content);
+    let title_selector = Selector::parse("title").expect("Failed to parse CSS selector");
+    let title = document
+        .select(&title_selector)
+        .next()
+        .map(|title| normalize_title(&title.text().collect::<String>(), 0))
+        .filter(|title| !title.is_empty());
+    for feed in feeds {
+        feed.source_title = title.clone();
+    }
+}
+
+/// Returns the `<link rel="canonical">` URL of an AMP page, one whose
+/// `<html>` tag has the `amp` or `⚡` attribute. Returns `None` for other
+/// pages and for AMP pages that are their own canonical page.
+fn amp_canonical_url(html_content: &str, page_url: &str) -> Option<String> {
+    let lower = html_content.to_ascii_lowercase();
+    let start = lower.find("<html")?;
+    let end = start + lower[start..].find('>')?;
+    let is_amp = html_content[start + "<html".len()..end]
+        .split_ascii_whitespace()
+        .map(|attribute| attribute.split('=').next().unwrap_or_default())
+        .any(|name| name.eq_ignore_ascii_case("amp") || name == "⚡");
+    if !is_amp {
+        return None;
+    }
+
+    let document = parse_page_head(html_content);
+    let base_url = document_base_url(&document, page_url);
+    let canonical_selector =
+        Selector::parse("link[rel~='canonical'][href]").expect("Failed to parse CSS selector");
+    let href = document
+        .select(&canonical_selector)
+        .next()?
+        .value()
+        .attr("href")?;
+    let canonical_url = resolve_url(&base_url, href.trim()).ok()?;
+    (canonical_url.starts_with("http") && canonical_url != page_url).then_some(canonical_url)
+}
+
+/// Returns the target of a page's `<meta http-equiv="refresh">` redirect,
+/// e.g. `/blog/` for `content="0; url=/blog/"`, resolved against its
+/// `<base href>`. A refresh that just reloads the page yields `None`.
+fn meta_refresh_url(html_content: &str, page_url: &str) -> Option<String> {
+    let document = parse_page_head(html_content);
+    let base_url = document_base_url(&document, page_url);
+    let refresh_selector =
+        Selector::parse("meta[http-equiv][content]").expect("Failed to parse CSS selector");
+    let content = document
+        .select(&refresh_selector)
+        .find(|element| {
+            element
+                .value()
+                .attr("http-equiv")
+                .is_some_and(|value| value.trim().eq_ignore_ascii_case("refresh"))
+        })?
+        .value()
+        .attr("content")?;
+
+    // `<delay>; url=<target>`, where `url=` and the quotes are optional
+    let (_, target) = content.split_once([';', ','])?;
+    let target = target.trim();
+    let target = match target.get(..3) {
+        Some(key) if key.eq_ignore_ascii_case("url") => {
+            target[3..].trim_start().strip_prefix('=')?.trim_start()
+        }
+        _ => target,
+    };
+    let target = target.trim_matches(|c| c == '"' || c == '\'').trim();
+    if target.is_empty() {
+        return None;
+    }
+    let target_url = resolve_url(&base_url, target).ok()?;
+    (target_url.starts_with("http") && target_url != page_url).then_some(target_url)
+}
+
+/// Returns the fetched page at `url` as a feed if its body is one, for
+/// input URLs that point straight at a feed.
+fn direct_feed(
+    url: &str,
+    headers: &HeaderMap,
+    body: &str,
+    config: &MinerConfig,
+    stats: &RunStats,
+) -> Option<RssFeed> {
+    let header = |name| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let content_type = header(CONTENT_TYPE);
+    sniff_feed_type(content_type.as_deref(), body)?;
+
+    let fetched = FetchedFeed {
+        body: body.to_string(),
+        content_type,
+        etag: header(ETAG),
+        last_modified: header(LAST_MODIFIED),
+        moved_to: None,
+    };
+    let info = validate_fetched_feed(url, fetched, config, stats)?;
+    let title = match info.title.as_str() {
+        "" => extract_title_from_url(url),
+        title => title.to_string(),
+    };
+    Some(discovered_feed(
+        &title,
+        url.to_string(),
+        url,
+        info,
+        DiscoveryMethod::Direct,
+    ))
+}
+
+/// Returns true if a `<link>` tag's `rel` is absent or includes
+/// `alternate`, as it should for a feed.
+fn rel_is_alternate(rel: Option<&str>) -> bool {
+    rel.is_none_or(|rel| {
+        rel.split_ascii_whitespace()
+            .any(|token| token.eq_ignore_ascii_case("alternate"))
+    })
+}
+
+/// Finds the rustls error behind a failed request, if it failed in the
+/// TLS layer. Wrapping I/O errors are looked inside, since their
+/// `source()` skips the error they wrap.
+fn rustls_error<'a>(err: &'a (dyn std::error::Error + 'static)) -> Option<&'a rustls::Error> {
+    let mut source = Some(err);
+    while let Some(err) = source {
+        if let Some(tls) = err.downcast_ref::<rustls::Error>() {
+            return Some(tls);
+        }
+        if let Some(inner) = err.downcast_ref::<io::Error>().and_then(io::Error::get_ref)
+            && let Some(tls) = rustls_error(inner)
+        {
+            return Some(tls);
+        }
+        source = err.source();
+    }
+    None
+}
+
+/// Returns the underlying TLS error if `err` is a failed TLS handshake,
+/// such as an expired, self-signed, or mismatched certificate. DNS,
+/// connection, timeout, and malformed-response failures are not.
+fn tls_error(err: &(dyn std::error::Error + 'static)) -> Option<String> {
+    rustls_error(err).map(|tls| tls.to_string())
+}
+
+/// Recognizes a handshake that failed because the server and client share
+/// no TLS version, either side having rejected the other's.
+fn is_tls_version_error(err: &(dyn std::error::Error + 'static)) -> bool {
+    use rustls::PeerIncompatible;
+
+    matches!(
+        rustls_error(err),
+        Some(
+            rustls::Error::AlertReceived(rustls::AlertDescription::ProtocolVersion)
+                | rustls::Error::PeerIncompatible(
+                    PeerIncompatible::ServerDoesNotSupportTls12Or13
+                        | PeerIncompatible::ServerTlsVersionIsDisabledByOurConfig
+                        | PeerIncompatible::SupportedVersionsExtensionRequired
+                        | PeerIncompatible::Tls12NotOffered
+                        | PeerIncompatible::Tls12NotOfferedOrEnabled
+                )
+        )
+    )
+}
+
+/// Builds the feed URL for a page of one of the enabled [`KnownSite`]s.
+fn known_site_feed_url(url: &str, sites: &[KnownSite]) -> Option<String> {
+    let parsed = Url::parse(url).ok()?;
+    let host = parsed.host_str()?.to_ascii_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    let host = host.strip_prefix("m.").unwrap_or(host);
+    let segments: Vec<&str> = parsed
+        .path_segments()
+        .map(|segments| segments.filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default();
+
+    if sites.contains(&KnownSite::YouTube) && host == "youtube.com" {
+        if let ["channel", channel_id, ..] = segments.as_slice() {
+            return Some(format!(
+                "https://www.youtube.com/feeds/videos.xml?channel_id={}",
+                channel_id
+            ));
+        }
+        if let Some((_, playlist_id)) = parsed.query_pairs().find(|(key, _)| key == "list") {
+            return Some(format!(
+                "https://www.youtube.com/feeds/videos.xml?playlist_id={}",
+                playlist_id
+            ));
+        }
+    }
+
+    if sites.contains(&KnownSite::Reddit)
+        && (host == "reddit.com" || host == "old.reddit.com")
+        && let [kind @ ("r" | "user" | "u"), name, ..] = segments.as_slice()
+    {
+        let kind = if *kind == "u" { "user" } else { kind };
+        return Some(format!("https://www.reddit.com/{}/{}/.rss", kind, name));
+    }
+
+    None
+}
+
+/// Returns the Mastodon-style `.rss` feed URL for a likely fediverse actor
+/// URL: `https://host/@user` or the ActivityPub `https://host/users/user`.
+fn fediverse_actor_feed_url(url: &str) -> Option<String> {
+    let parsed = Url::parse(url).ok()?;
+    let segments: Vec<&str> = parsed.path_segments()?.filter(|s| !s.is_empty()).collect();
+
+    let handle = match segments.as_slice() {
+        [handle] if handle.len() > 1 && handle.starts_with('@') => &handle[1..],
+        ["users", handle] => handle,
+        _ => return None,
+    };
+    if handle.contains(['@', '.']) {
+        return None;
+    }
+
+    let mut feed_url = parsed.clone();
+    feed_url.set_path(&format!("/@{}.rss", handle));
+    feed_url.set_query(None);
+    feed_url.set_fragment(None);
+    Some(feed_url.to_string())
+}
+
+/// Most feed URLs taken from a page's JSON-LD.
+const MAX_JSON_LD_CANDIDATES: usize = 5;
+
+/// Collects the distinct, resolved http(s) URLs that a page's
+/// `<script type="application/ld+json">` blocks declare as feeds, up to
+/// [`MAX_JSON_LD_CANDIDATES`]. See [`collect_json_ld_feeds`] for which
+/// values count.
+fn json_ld_feed_urls(document: &Html, base_url: &str) -> Vec<String> {
+    let script_selector = Selector::parse("script[type='application/ld+json']")
+        .expect("Failed to parse CSS selector");
+    let mut found = Vec::new();
+    for script in document.select(&script_selector) {
+        let json: String = script.text().collect();
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(&json) {
+            collect_json_ld_feeds(&value, false, &mut found);
+        }
+    }
+
+    let mut candidates: Vec<String> = Vec::new();
+    for href in found {
+        let Ok(feed_url) = resolve_url(base_url, href.trim()) else {
+            continue;
+        };
+        if feed_url.starts_with("http") && !candidates.contains(&feed_url) {
+            candidates.push(feed_url);
+        }
+        if candidates.len() >= MAX_JSON_LD_CANDIDATES {
+            break;
+        }
+    }
+    candidates
+}
+
+/// Walks a JSON-LD value for feed URLs: strings under keys naming a feed
+/// (schema.org's `webFeed`, or custom ones like `rssFeed`), and the `url`,
+/// `@id`, `target`, or `urlTemplate` of `SubscribeAction` and `DataFeed`
+/// nodes, or of nodes under such keys. `in_feed` is set for values under a
+/// feed key.
+fn collect_json_ld_feeds(value: &serde_json::Value, in_feed: bool, found: &mut Vec<String>) {
+    const FEED_KEY_MARKERS: [&str; 3] = ["feed", "rss", "atom"];
+    const FEED_TYPES: [&str; 2] = ["SubscribeAction", "DataFeed"];
+    const URL_KEYS: [&str; 4] = ["url", "@id", "target", "urlTemplate"];
+
+    match value {
+        serde_json::Value::String(url) if in_feed => found.push(url.clone()),
+        serde_json::Value::Array(items) => {
+            for item in items {
+                collect_json_ld_feeds(item, in_feed, found);
+            }
+        }
+        serde_json::Value::Object(node) => {
+            let feed_node = in_feed
+                || match node.get("@type") {
+                    Some(serde_json::Value::String(ty)) => FEED_TYPES.contains(&ty.as_str()),
+                    Some(serde_json::Value::Array(types)) => types
+                        .iter()
+                        .any(|ty| ty.as_str().is_some_and(|ty| FEED_TYPES.contains(&ty))),
+                    _ => false,
+                };
+            for (key, value) in node {
+                let lower = key.to_ascii_lowercase();
+                let feed_key = FEED_KEY_MARKERS.iter().any(|marker| lower.contains(marker))
+                    || (feed_node && URL_KEYS.contains(&key.as_str()));
+                collect_json_ld_feeds(value, feed_key, found);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Collects up to `limit` distinct, resolved URLs of `<a>` elements whose
+/// text or href suggests a feed.
+fn feed_like_anchors(document: &Html, base_url: &str, limit: usize) -> Vec<String> {
+    const TEXT_MARKERS: [&str; 4] = ["rss", "atom", "feed", "subscribe"];
+    const HREF_MARKERS: [&str; 6] = [".rss", ".xml", ".atom", "/feed", "/rss", "/atom"];
+
+    let anchor_selector = Selector::parse("a[href]").expect("Failed to parse CSS selector");
+    let mut candidates = Vec::new();
+
+    for element in document.select(&anchor_selector) {
+        if candidates.len() >= limit {
+            break;
+        }
+
+        let Some(href) = element.value().attr("href") else {
+            continue;
+        };
+        let text = element.text().collect::<String>().to_ascii_lowercase();
+        let href_lower = href.to_ascii_lowercase();
+
+        let text_matches = TEXT_MARKERS.iter().any(|marker| text.contains(marker));
+        let href_matches = HREF_MARKERS
+            .iter()
+            .any(|marker| href_lower.contains(marker));
+        if !text_matches && !href_matches {
+            continue;
+        }
+
+        if let Ok(feed_url) = resolve_url(base_url, href)
+            && feed_url.starts_with("http")
+            && !candidates.contains(&feed_url)
+        {
+            candidates.push(feed_url);
+        }
+    }
+
+    candidates
+}
+
+/// Largest sitemap read, after decompression, so a gzip bomb can't exhaust
+/// memory.
+const MAX_SITEMAP_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Most sitemap entries validated per site.
+const MAX_SITEMAP_PROBES: usize = 5;
+
+/// Returns feed-like URLs listed in the site's sitemap, trying
+/// `/sitemap.xml` and then `/sitemap.xml.gz`.
+fn sitemap_feed_candidates(
+    url: &str,
+    client: &Client,
+    config: &MinerConfig,
+    stats: &RunStats,
+) -> Vec<String> {
+    for path in ["/sitemap.xml", "/sitemap.xml.gz"] {
+        let Ok(sitemap_url) = resolve_url(url, path) else {
+            continue;
+        };
+        let Some(body) = fetch_sitemap(&sitemap_url, client, config, stats) else {
+            continue;
+        };
+        return sitemap_locations(&body)
+            .into_iter()
+            .filter(|loc| looks_like_feed_url(loc))
+            .take(MAX_SITEMAP_PROBES)
+            .collect();
+    }
+    Vec::new()
+}
+
+/// Fetches a sitemap, gunzipping it if the body is gzip data (a `.gz` file,
+/// or a gzip `Content-Encoding` the client did not decode).
+fn fetch_sitemap(
+    sitemap_url: &str,
+    client: &Client,
+    config: &MinerConfig,
+    stats: &RunStats,
+) -> Option<String> {
+    let _permit = stats.request_permit();
+    let response = send_with_retries(get_request(client, sitemap_url, config), config).ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    let body = response.bytes().ok()?;
+    stats.record_download(sitemap_url, body.len());
+
+    let mut text = String::new();
+    if body.starts_with(&[0x1f, 0x8b]) {
+        flate2::read::GzDecoder::new(body.as_ref())
+            .take(MAX_SITEMAP_BYTES)
+            .read_to_string(&mut text)
+            .ok()?;
+    } else {
+        body.as_ref()
+            .take(MAX_SITEMAP_BYTES)
+            .read_to_string(&mut text)
+            .ok()?;
+    }
+    Some(text)
+}
+
+/// Extracts the `<loc>` URLs of a sitemap, in document order.
+fn sitemap_locations(xml: &str) -> Vec<String> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut locations = Vec::new();
+    let mut in_loc = false;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(start)) => in_loc = start.local_name().as_ref() == b"loc",
+            Ok(Event::Text(text)) if in_loc => {
+                if let Ok(loc) = text.xml_content() {
+                    locations.push(loc.trim().to_string());
+                }
+            }
+            Ok(Event::End(_)) => in_loc = false,
+            Ok(Event::Eof) | Err(_) => break,
+            Ok(_) => {}
+        }
+    }
+
+    locations
+}
+
+/// Whether a URL's path looks like a feed rather than an ordinary page.
+fn looks_like_feed_url(url: &str) -> bool {
+    const MARKERS: [&str; 8] = [
+        "/feed", "/rss", "/atom", ".rss", ".atom", "feed.xml", "rss.xml", "atom.xml",
+    ];
+    let url = url.to_ascii_lowercase();
+    url.starts_with("http") && MARKERS.iter().any(|marker| url.contains(marker))
+}
+
+fn discovered_feed(
+    title: &str,
+    feed_url: String,
+    html_url: &str,
+    info: FeedInfo,
+    method: DiscoveryMethod,
+) -> RssFeed {
+    let url = info
+        .self_link
+        .or(info.moved_to)
+        .unwrap_or_else(|| feed_url.clone());
+    RssFeed {
+        title: title.to_string(),
+        mixed_content: is_mixed_content(html_url, &url),
+        url,
+        raw_url: feed_url,
+        html_url: html_url.to_string(),
+        feed_type: info.feed_type,
+        discovery_method: Some(method),
+        etag: info.etag,
+        last_modified: info.last_modified,
+        categories: info.categories,
+        language: info.language,
+        author: info.author,
+        folder: None,
+        format_version: info.format_version,
+        source_title: None,
+    }
+}
+
+/// Returns true if an `http` feed is linked from an `https` page.
+fn is_mixed_content(page_url: &str, feed_url: &str) -> bool {
+    let scheme = |url: &str| Url::parse(url).map(|url| url.scheme().to_string());
+    matches!(
+        (scheme(page_url).as_deref(), scheme(feed_url).as_deref()),
+        (Ok("https"), Ok("http"))
+    )
+}
+
+pub fn find_rss_feeds_parallel(urls: &[String], client: &Client, verbose: bool) -> Vec<RssFeed> {
+    find_rss_feeds_parallel_with_config(urls, client, &MinerConfig::default(), verbose)
+}
+
+pub fn find_rss_feeds_parallel_with_config(
+    urls: &[String],
+    client: &Client,
+    config: &MinerConfig,
+    verbose: bool,
+) -> Vec<RssFeed> {
+    let stats = RunStats::new(config);
+    let feeds = discover_urls(urls, client, config, &stats, verbose);
+    stats.save_validation_cache();
+    feeds
+}
+
+/// Runs discovery on `urls` in parallel, collecting every feed found.
+fn discover_urls(
+    urls: &[String],
+    client: &Client,
+    config: &MinerConfig,
+    stats: &RunStats,
+    verbose: bool,
+) -> Vec<RssFeed> {
+    urls.par_iter()
+        .flat_map_iter(|url| {
+            discover_url(url, client, config, stats, verbose)
+                .map(|discovery| discovery.feeds)
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Summary of a discovery run: the feeds found plus per-URL outcome counts
+/// and fetch latency distributions.
+#[derive(Debug, Clone, Default)]
+pub struct DiscoveryReport {
+    pub feeds: Vec<RssFeed>,
+    /// Number of input URLs processed.
+    pub urls_processed: usize,
+    /// URLs whose page could not be fetched or processed.
+    pub urls_failed: usize,
+    /// URLs processed successfully that yielded no feeds.
+    pub urls_without_feeds: usize,
+    /// Why each of the `urls_without_feeds` URLs came up empty, in input
+    /// order.
+    pub no_feeds_reasons: Vec<(String, NoFeedsReason)>,
+    /// URLs not fetched because [`MinerConfig::max_page_fetches`] was
+    /// reached; non-zero means the crawl was truncated.
+    pub urls_skipped: usize,
+    /// URLs not fetched because a [`Checkpoint`] recorded them as already
+    /// processed; their feeds are included in `feeds`.
+    pub urls_resumed: usize,
+    /// Candidate feed URLs that could not be validated because of a TLS
+    /// error, such as an expired or self-signed certificate, with the
+    /// error. Unlike a 404, these may well be feeds.
+    pub tls_failures: Vec<(String, String)>,
+    /// Total size of the page, feed, and sitemap bodies downloaded.
+    pub bytes_downloaded: u64,
+    /// Latency of each page fetch, including failed ones.
+    pub page_latency: LatencyHistogram,
+    /// Latency of each feed validation fetch, including failed ones.
+    pub validation_latency: LatencyHistogram,
+    /// One entry per processed URL in completion order, when
+    /// [`MinerConfig::event_log`] is set; empty otherwise.
+    pub log: Vec<LogEntry>,
+    /// Counters by host (with any port), when [`MinerConfig::host_stats`] is
+    /// set; empty otherwise.
+    pub host_stats: BTreeMap<String, HostStats>,
+}
+
+/// A timestamped record of how one URL was processed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    /// When processing of the URL finished.
+    pub timestamp: SystemTime,
+    pub url: String,
+    /// Time spent discovering feeds for the URL.
+    pub elapsed: Duration,
+    pub event: LogEvent,
+}
+
+/// The outcome recorded in a [`LogEntry`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogEvent {
+    /// The page yielded this many feeds.
+    FeedsFound(usize),
+    /// The page was processed but yielded no feeds, for this reason.
+    NoFeeds(NoFeedsReason),
+    /// The page could not be fetched or processed.
+    Failed(String),
+}
+
+/// Like [`find_rss_feeds_parallel_with_config`], but returns a
+/// [`DiscoveryReport`] with outcome counts and latency histograms.
+pub fn find_rss_feeds_parallel_report(
+    urls: &[String],
+    client: &Client,
+    config: &MinerConfig,
+    verbose: bool,
+) -> DiscoveryReport {
+    find_rss_feeds_parallel_report_with_progress(urls, client, config, verbose, |_| {})
+}
+
+/// A snapshot of how far a run has got, passed to progress callbacks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// URLs finished so far, successfully or not.
+    pub processed: usize,
+    /// Total number of URLs in the run.
+    pub total: usize,
+    /// Feeds found so far, before deduplication.
+    pub feeds_found: usize,
+}
+
+/// Like [`find_rss_feeds_parallel_report`], calling `on_progress` from the
+/// worker threads after each URL is finished.
+pub fn find_rss_feeds_parallel_report_with_progress<F>(
+    urls: &[String],
+    client: &Client,
+    config: &MinerConfig,
+    verbose: bool,
+    on_progress: F,
+) -> DiscoveryReport
+where
+    F: Fn(Progress) + Sync,
+{
+    discovery_report(urls, client, config, verbose, on_progress, |_, _| {})
+}
+
+/// Like [`find_rss_feeds_parallel_report_with_progress`], but skips URLs
+/// that `checkpoint` already recorded and records each newly processed URL
+/// as soon as it finishes.
+///
+/// The returned report includes the feeds restored from the checkpoint.
+/// URLs that failed or were skipped are not recorded, so a resumed run
+/// retries them.
+pub fn find_rss_feeds_parallel_report_with_checkpoint<F>(
+    urls: &[String],
+    client: &Client,
+    config: &MinerConfig,
+    verbose: bool,
+    checkpoint: &Checkpoint,
+    on_progress: F,
+) -> Result<DiscoveryReport>
+where
+    F: Fn(Progress) + Sync,
+{
+    let pending: Vec<String> = urls
+        .iter()
+        .filter(|url| !checkpoint.is_processed(url))
+        .cloned()
+        .collect();
+    let write_error = Mutex::new(None);
+
+    let mut report = discovery_report(
+        &pending,
+        client,
+        config,
+        verbose,
+        on_progress,
+        |url, discovery| {
+            if let Err(e) = checkpoint.record(url, &discovery.feeds)
+                && let Ok(mut slot) = write_error.lock()
+            {
+                slot.get_or_insert(e);
+            }
+        },
+    );
+    if let Some(e) = write_error.into_inner().ok().flatten() {
+        return Err(e);
+    }
+
+    report.urls_resumed = urls.len() - pending.len();
+    report
+        .feeds
+        .splice(0..0, checkpoint.feeds().iter().cloned());
+    Ok(report)
+}
+
+/// Runs discovery over `urls`, calling `on_progress` after each URL and
+/// `on_done` for each URL that was processed without error.
+fn discovery_report<F, G>(
+    urls: &[String],
+    client: &Client,
+    config: &MinerConfig,
+    verbose: bool,
+    on_progress: F,
+    on_done: G,
+) -> DiscoveryReport
+where
+    F: Fn(Progress) + Sync,
+    G: Fn(&str, &UrlDiscovery) + Sync,
+{
+    let stats = RunStats::new(config);
+    let processed = AtomicUsize::new(0);
+    let feeds_found = AtomicUsize::new(0);
+    let outcomes: Vec<(Result<UrlDiscovery>, Option<LogEntry>)> = urls
+        .par_iter()
+        .map(|url| {
+            let started = Instant::now();
+            let outcome = discover_url(url, client, config, &stats, verbose);
+            if let Ok(discovery) = &outcome {
+                on_done(url, discovery);
+            }
+            let found = outcome
+                .as_ref()
+                .map_or(0, |discovery| discovery.feeds.len());
+            on_progress(Progress {
+                processed: processed.fetch_add(1, Ordering::SeqCst) + 1,
+                total: urls.len(),
+                feeds_found: feeds_found.fetch_add(found, Ordering::SeqCst) + found,
+            });
+
+            let entry = config.event_log.then(|| LogEntry {
+                timestamp: SystemTime::now(),
+                url: url.clone(),
+                elapsed: started.elapsed(),
+                event: match &outcome {
+                    Ok(UrlDiscovery {
+                        no_feeds_reason: Some(reason),
+                        ..
+                    }) => LogEvent::NoFeeds(*reason),
+                    Ok(discovery) => LogEvent::FeedsFound(discovery.feeds.len()),
+                    Err(e) => LogEvent::Failed(e.to_string()),
+                },
+            });
+            (outcome, entry)
+        })
+        .collect();
+
+    let mut report = DiscoveryReport {
+        urls_processed: outcomes.len(),
+        ..Default::default()
+    };
+    stats.save_validation_cache();
+    let mut hosts = stats
+        .hosts
+        .map(|hosts| hosts.into_inner().unwrap_or_default());
+    for (url, (outcome, entry)) in urls.iter().zip(outcomes) {
+        report.log.extend(entry);
+        if let Some(hosts) = &mut hosts {
+            let host = hosts.entry(host_key(url).unwrap_or_default()).or_default();
+            match &outcome {
+                Ok(discovery) => host.feeds_found += discovery.feeds.len(),
+                Err(e) if !e.is::<PageBudgetExhausted>() => host.errors += 1,
+                Err(_) => {}
+            }
+        }
+        match outcome {
+            Ok(UrlDiscovery {
+                no_feeds_reason: Some(reason),
+                ..
+            }) => {
+                report.urls_without_feeds += 1;
+                report.no_feeds_reasons.push((url.clone(), reason));
+            }
+            Ok(discovery) => report.feeds.extend(discovery.feeds),
+            Err(e) if e.is::<PageBudgetExhausted>() => report.urls_skipped += 1,
+            Err(_) => report.urls_failed += 1,
+        }
+    }
+    if let Some(hosts) = hosts {
+        report.host_stats = hosts.into_iter().collect();
+    }
+    report.tls_failures = stats.tls_failures.into_inner().unwrap_or_default();
+    report.tls_failures.sort();
+    report.tls_failures.dedup();
+    report.bytes_downloaded = stats.bytes_downloaded.into_inner();
+    report.page_latency = stats.page_latency.into_inner().unwrap_or_default();
+    report.validation_latency = stats.validation_latency.into_inner().unwrap_or_default();
+    report.log.sort_by_key(|entry| entry.timestamp);
+
+    report
+}
+
+/// The progress of a long run, persisted so an interrupted run can resume
+/// where it stopped.
+///
+/// The file holds one JSON line per processed input URL with the feeds it
+/// yielded, appended as soon as the URL finishes, so a crash loses at most
+/// the URLs still in flight.
+#[derive(Debug)]
+pub struct Checkpoint {
+    file: Mutex<fs::File>,
+    processed: HashSet<String>,
+    feeds: Vec<RssFeed>,
+}
+
+/// One line of a [`Checkpoint`] file.
+#[derive(Debug, Serialize, Deserialize)]
+struct CheckpointRecord {
+    url: String,
+    feeds: Vec<RssFeed>,
+}
+
+impl Checkpoint {
+    /// Starts an empty checkpoint at `path`, replacing any existing file.
+    pub fn create(path: &Path) -> Result<Self> {
+        Self::write(path, Vec::new())
+    }
+
+    /// Opens the checkpoint at `path` to resume from it, or starts an empty
+    /// one if the file does not exist.
+    ///
+    /// A line cut short by a crash is dropped, so its URL is processed again.
+    pub fn resume(path: &Path) -> Result<Self> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to read checkpoint: {}", path.display()));
+            }
+        };
+        let records = content
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        Self::write(path, records)
+    }
+
+    /// Rewrites the file with `records` and keeps it open for appending.
+    ///
+    /// The rewrite goes through a temporary file, so a crash while resuming
+    /// leaves the previous checkpoint intact.
+    fn write(path: &Path, records: Vec<CheckpointRecord>) -> Result<Self> {
+        let mut lines = String::new();
+        let mut processed = HashSet::new();
+        let mut feeds = Vec::new();
+        for record in records {
+            lines.push_str(&serde_json::to_string(&record)?);
+            lines.push('\n');
+            if processed.insert(record.url) {
+                feeds.extend(record.feeds);
+            }
+        }
+        write_file_atomically(path, "checkpoint", |file| file.write_all(lines.as_bytes()))?;
+
+        let file = fs::OpenOptions::new()
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open checkpoint: {}", path.display()))?;
+        Ok(Checkpoint {
+            file: Mutex::new(file),
+            processed,
+            feeds,
+        })
+    }
+
+    /// Returns whether `url` was processed by an earlier run.
+    pub fn is_processed(&self, url: &str) -> bool {
+        self.processed.contains(url)
+    }
+
+    /// Number of URLs processed by earlier runs.
+    pub fn processed_count(&self) -> usize {
+        self.processed.len()
+    }
+
+    /// Feeds found by earlier runs, in the order they were recorded.
+    pub fn feeds(&self) -> &[RssFeed] {
+        &self.feeds
+    }
+
+    /// Appends `url` and the feeds it yielded to the file.
+    pub fn record(&self, url: &str, feeds: &[RssFeed]) -> Result<()> {
+        let record = CheckpointRecord {
+            url: url.to_string(),
+            feeds: feeds.to_vec(),
+        };
+        let line = format!("{}\n", serde_json::to_string(&record)?);
+        let mut file = self
+            .file
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Checkpoint file lock poisoned"))?;
+        file.write_all(line.as_bytes())
+            .context("Failed to write checkpoint")
+    }
+}
+
+/// Instrumentation and caches shared by all workers during a run.
+#[derive(Debug, Default)]
+struct RunStats {
+    page_latency: Mutex<LatencyHistogram>,
+    validation_latency: Mutex<LatencyHistogram>,
+    /// Parsed feeds keyed by `(url, ETag)`, so a feed linked from several
+    /// pages is only parsed once while its ETag is unchanged.
+    parsed_feeds: Mutex<HashMap<(String, String), FeedInfo>>,
+    /// Number of feed bodies actually parsed.
+    feeds_parsed: AtomicUsize,
+    /// Validations currently running, by feed URL, so workers that find the
+    /// same feed at the same time wait for one result instead of refetching.
+    in_flight: Mutex<HashMap<String, Arc<OnceLock<Option<FeedInfo>>>>>,
+    /// Page fetches started, checked against
+    /// [`MinerConfig::max_page_fetches`].
+    pages_fetched: AtomicUsize,
+    /// Response body bytes read from pages, feeds, and sitemaps.
+    bytes_downloaded: AtomicU64,
+    /// Fetch counters by [`host_key`]; only kept when
+    /// [`MinerConfig::host_stats`] is set, to keep the lock off the hot path.
+    hosts: Option<Mutex<HashMap<String, HostStats>>>,
+    /// Validations persisted under [`MinerConfig::cache_dir`].
+    validation_cache: Option<ValidationCache>,
+    /// Feed URLs whose fetch failed with a TLS error, with the error.
+    tls_failures: Mutex<Vec<(String, String)>>,
+    /// [`FeedInfo::related_feeds`] by validated feed URL, kept under
+    /// [`MinerConfig::follow_related_feeds`].
+    related_feeds: Mutex<HashMap<String, Vec<String>>>,
+    /// Caps concurrent requests under [`MinerConfig::max_inflight_requests`].
+    request_limiter: Option<RequestLimiter>,
+}
+
+impl RunStats {
+    fn new(config: &MinerConfig) -> Self {
+        RunStats {
+            validation_cache: config
+                .cache_dir
+                .as_deref()
+                .filter(|_| config.custom_validators.is_empty())
+                .map(|dir| ValidationCache::load(dir, config)),
+            request_limiter: config.max_inflight_requests.map(RequestLimiter::new),
+            hosts: config.host_stats.then(Mutex::default),
+            ..Default::default()
+        }
+    }
+
+    /// Writes the validation cache back to disk, if there is one and it
+    /// changed. Failing to write it only costs refetches next run.
+    fn save_validation_cache(&self) {
+        if let Some(cache) = &self.validation_cache
+            && let Err(e) = cache.save()
+        {
+            eprintln!("Warning: {:#}", e);
+        }
+    }
+
+    fn cached_feed(&self, feed_url: &str, etag: &str) -> Option<FeedInfo> {
+        let cache = self.parsed_feeds.lock().ok()?;
+        cache
+            .get(&(feed_url.to_string(), etag.to_string()))
+            .cloned()
+    }
+
+    fn cache_feed(&self, feed_url: &str, etag: &str, info: &FeedInfo) {
+        if let Ok(mut cache) = self.parsed_feeds.lock() {
+            cache.insert((feed_url.to_string(), etag.to_string()), info.clone());
+        }
+    }
+
+    /// Waits until another request may be in flight. The request counts
+    /// against [`MinerConfig::max_inflight_requests`] until the permit is
+    /// dropped, so hold it while the response body is read.
+    fn request_permit(&self) -> Option<RequestPermit<'_>> {
+        self.request_limiter.as_ref().map(RequestLimiter::acquire)
+    }
+
+    fn record_page(&self, url: &str, elapsed: Duration) {
+        if let Ok(mut histogram) = self.page_latency.lock() {
+            histogram.record(elapsed);
+        }
+        self.update_host(url, |host| {
+            host.pages_fetched += 1;
+            host.record_fetch(elapsed);
+        });
+    }
+
+    fn record_validation(&self, feed_url: &str, elapsed: Duration) {
+        if let Ok(mut histogram) = self.validation_latency.lock() {
+            histogram.record(elapsed);
+        }
+        self.update_host(feed_url, |host| host.record_fetch(elapsed));
+    }
+
+    fn record_download(&self, url: &str, bytes: usize) {
+        self.bytes_downloaded
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+        self.update_host(url, |host| host.bytes_downloaded += bytes as u64);
+    }
+
+    fn record_tls_failure(&self, feed_url: &str, error: String) {
+        if let Ok(mut failures) = self.tls_failures.lock() {
+            failures.push((feed_url.to_string(), error));
+        }
+    }
+
+    fn record_related_feeds(&self, feed_url: &str, info: &FeedInfo) {
+        if !info.related_feeds.is_empty()
+            && let Ok(mut related) = self.related_feeds.lock()
+        {
+            related.insert(feed_url.to_string(), info.related_feeds.clone());
+        }
+    }
+
+    fn related_feeds(&self, feed_url: &str) -> Vec<String> {
+        self.related_feeds
+            .lock()
+            .ok()
+            .and_then(|related| related.get(feed_url).cloned())
+            .unwrap_or_default()
+    }
+
+    fn update_host(&self, url: &str, update: impl FnOnce(&mut HostStats)) {
+        if let Some(hosts) = &self.hosts
+            && let Ok(mut hosts) = hosts.lock()
+        {
+            update(hosts.entry(host_key(url).unwrap_or_default()).or_default());
+        }
+    }
+}
+
+/// A counting semaphore over in-flight requests.
+#[derive(Debug)]
+struct RequestLimiter {
+    limit: usize,
+    in_flight: Mutex<usize>,
+    released: Condvar,
+}
+
+impl RequestLimiter {
+    fn new(limit: usize) -> Self {
+        RequestLimiter {
+            limit: limit.max(1),
+            in_flight: Mutex::new(0),
+            released: Condvar::new(),
+        }
+    }
+
+    fn acquire(&self) -> RequestPermit<'_> {
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        while *in_flight >= self.limit {
+            in_flight = self
+                .released
+                .wait(in_flight)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        *in_flight += 1;
+        RequestPermit { limiter: self }
+    }
+}
+
+/// One request's slot in a [`RequestLimiter`], freed on drop.
+struct RequestPermit<'a> {
+    limiter: &'a RequestLimiter,
+}
+
+impl Drop for RequestPermit<'_> {
+    fn drop(&mut self) {
+        let mut in_flight = self
+            .limiter
+            .in_flight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        *in_flight -= 1;
+        self.limiter.released.notify_one();
+    }
+}
+
+const VALIDATION_CACHE_FILE_NAME: &str = "validation-cache.json";
+
+/// Successful feed validations shared between runs through a JSON file in
+/// [`MinerConfig::cache_dir`].
+#[derive(Debug)]
+struct ValidationCache {
+    path: PathBuf,
+    ttl: Duration,
+    /// The settings of this run; entries validated under others are stale.
+    settings: ValidationSettings,
+    entries: Mutex<HashMap<String, CachedValidation>>,
+    changed: AtomicBool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedValidation {
+    info: FeedInfo,
+    /// When the feed was validated, in seconds since the Unix epoch.
+    validated_at: u64,
+    settings: ValidationSettings,
+}
+
+/// The [`MinerConfig`] fields that shape a [`FeedInfo`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct ValidationSettings {
+    validation_mode: ValidationMode,
+    sample_titles: usize,
+    keep_unknown: bool,
+}
+
+impl ValidationSettings {
+    fn of(config: &MinerConfig) -> Self {
+        ValidationSettings {
+            validation_mode: config.validation_mode,
+            sample_titles: config.sample_titles,
+            keep_unknown: config.keep_unknown,
+        }
+    }
+}
+
+impl ValidationCache {
+    /// Loads the cache in `dir` for a run with `config`, starting empty if it
+    /// is missing or unreadable.
+    fn load(dir: &Path, config: &MinerConfig) -> Self {
+        let path = dir.join(VALIDATION_CACHE_FILE_NAME);
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        ValidationCache {
+            path,
+            ttl: config.cache_ttl,
+            settings: ValidationSettings::of(config),
+            entries: Mutex::new(entries),
+            changed: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the cached validation of `feed_url` if it is younger than the
+    /// TTL and was made with this run's settings.
+    fn get(&self, feed_url: &str) -> Option<FeedInfo> {
+        let entries = self.entries.lock().ok()?;
+        let entry = entries.get(feed_url)?;
+        let age = unix_time().saturating_sub(entry.validated_at);
+        (age < self.ttl.as_secs() && entry.settings == self.settings).then(|| entry.info.clone())
+    }
+
+    fn insert(&self, feed_url: &str, info: &FeedInfo) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(
+                feed_url.to_string(),
+                CachedValidation {
+                    info: info.clone(),
+                    validated_at: unix_time(),
+                    settings: self.settings,
+                },
+            );
+            self.changed.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn save(&self) -> Result<()> {
+        if !self.changed.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let entries = self
+            .entries
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Validation cache lock poisoned"))?;
+        let json = serde_json::to_string(&*entries).context("Failed to serialize cache")?;
+        create_parent_dirs(&self.path)?;
+        write_file_atomically(&self.path, "validation cache", |file| {
+            file.write_all(json.as_bytes())
+        })
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Per-host counters in [`DiscoveryReport::host_stats`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HostStats {
+    /// Pages fetched from the host.
+    pub pages_fetched: usize,
+    /// Feeds found for input URLs on the host, before deduplication.
+    pub feeds_found: usize,
+    /// Input URLs on the host that could not be fetched or processed.
+    pub errors: usize,
+    /// Response body bytes downloaded from the host.
+    pub bytes_downloaded: u64,
+    /// Page and feed fetches sent to the host, successful or not.
+    pub fetches: usize,
+    /// Time spent on those fetches.
+    pub total_latency: Duration,
+}
+
+impl HostStats {
+    fn record_fetch(&mut self, elapsed: Duration) {
+        self.fetches += 1;
+        self.total_latency += elapsed;
+    }
+
+    /// Mean latency of the host's fetches, or `None` if there were none.
+    pub fn average_latency(&self) -> Option<Duration> {
+        (self.fetches > 0).then(|| self.total_latency / self.fetches as u32)
+    }
+}
+
+const LATENCY_BUCKETS: usize = 32;
+
+/// Latency histogram with exponentially sized buckets.
+///
+/// Bucket 0 holds samples up to 1ms and bucket `i` holds samples in
+/// `(2^(i-1), 2^i]` milliseconds, so recording is a constant-time increment
+/// and percentiles are reported as the upper bound of the containing bucket.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatencyHistogram {
+    buckets: [u64; LATENCY_BUCKETS],
+    count: u64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        LatencyHistogram {
+            buckets: [0; LATENCY_BUCKETS],
+            count: 0,
+        }
+    }
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, latency: Duration) {
+        let millis = latency.as_millis().min(u64::MAX as u128) as u64;
+        let bucket = if millis <= 1 {
+            0
+        } else {
+            (u64::BITS - (millis - 1).leading_zeros()) as usize
+        };
+        self.buckets[bucket.min(LATENCY_BUCKETS - 1)] += 1;
+        self.count += 1;
+    }
+
+    /// Number of recorded samples.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the upper bound of the bucket containing the `percentile`th
+    /// sample (0-100), or `None` if nothing was recorded.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let rank = ((percentile.clamp(0.0, 100.0) / 100.0) * self.count as f64).ceil() as u64;
+        let rank = rank.max(1);
+        let mut seen = 0;
+        for (bucket, &samples) in self.buckets.iter().enumerate() {
+            seen += samples;
+            if seen >= rank {
+                return Some(Duration::from_millis(1 << bucket));
+            }
+        }
+        None
+    }
+
+    /// Formats p50/p90/p99 as a single line, e.g. `p50 <= 64ms, ...`.
+    pub fn summary(&self) -> String {
+        let format = |p| match self.percentile(p) {
+            Some(latency) => format!("<= {}ms", latency.as_millis()),
+            None => "n/a".to_string(),
+        };
+        format!(
+            "p50 {}, p90 {}, p99 {} ({} samples)",
+            format(50.0),
+            format(90.0),
+            format(99.0),
+            self.count
+        )
+    }
+}
+
+/// Processes `urls` in chunks of `chunk_size`, calling `on_chunk` with the
+/// feeds found in each chunk as soon as it completes.
+///
+/// URLs within a chunk are processed in parallel; the union of all chunks
+/// equals the result of [`find_rss_feeds_parallel`]. Chunks share one run,
+/// so limits such as [`MinerConfig::max_page_fetches`] apply to the whole
+/// of `urls`, and a feed found again in a later chunk is not passed on a
+/// second time. The callback fires once per chunk, even when a chunk yields
+/// no feeds.
+pub fn find_rss_feeds_chunked(
+    urls: &[String],
+    client: &Client,
+    chunk_size: usize,
+    on_chunk: impl FnMut(Vec<RssFeed>),
+) {
+    find_rss_feeds_chunked_with_config(urls, client, &MinerConfig::default(), chunk_size, on_chunk)
+}
+
+pub fn find_rss_feeds_chunked_with_config(
+    urls: &[String],
+    client: &Client,
+    config: &MinerConfig,
+    chunk_size: usize,
+    mut on_chunk: impl FnMut(Vec<RssFeed>),
+) {
+    let stats = RunStats::new(config);
+    let mut seen = HashSet::new();
+    for chunk in urls.chunks(chunk_size.max(1)) {
+        let feeds = discover_urls(chunk, client, config, &stats, false);
+        on_chunk(
+            feeds
+                .into_iter()
+                .filter(|feed| seen.insert(feed.url.clone()))
+                .collect(),
+        );
+    }
+    stats.save_validation_cache();
+}
+
+/// Discovers feeds on a background thread, sending each feed over the returned
+/// channel as soon as its page has been processed.
+///
+/// Pair with [`OpmlStreamWriter`] to keep memory flat on very large scans. The
+/// channel closes once every URL has been processed.
+pub fn find_rss_feeds_stream(
+    urls: Vec<String>,
+    client: Client,
+    config: MinerConfig,
+    verbose: bool,
+) -> Receiver<RssFeed> {
+    find_rss_feeds_stream_with_failures(urls, client, config, verbose).0
+}
+
+/// [`find_rss_feeds_stream`], also returning a handle to the background
+/// thread that yields, once the channel has closed, the number of URLs whose
+/// page could not be fetched or processed.
+pub fn find_rss_feeds_stream_with_failures(
+    urls: Vec<String>,
+    client: Client,
+    config: MinerConfig,
+    verbose: bool,
+) -> (Receiver<RssFeed>, JoinHandle<usize>) {
+    let (sender, receiver) = mpsc::channel();
+
+    let handle = thread::spawn(move || {
+        let stats = RunStats::new(&config);
+        let failed = AtomicUsize::new(0);
+        urls.par_iter().for_each_with(sender, |sender, url| {
+            let Ok(discovery) = discover_url(url, &client, &config, &stats, verbose) else {
+                failed.fetch_add(1, Ordering::Relaxed);
+                return;
+            };
+            for feed in discovery.feeds {
+                // The receiver hung up; nobody is listening for more feeds.
+                if sender.send(feed).is_err() {
+                    return;
+                }
+            }
+        });
+        stats.save_validation_cache();
+        failed.into_inner()
+    });
+
+    (receiver, handle)
+}
+
+/// A [`find_rss_feeds_stream`] receiver that threads can share, as the
+/// Python bindings' iterator must be.
+#[cfg(any(test, feature = "python"))]
+struct FeedStream {
+    receiver: Mutex<Receiver<RssFeed>>,
+}
+
+#[cfg(any(test, feature = "python"))]
+impl FeedStream {
+    fn new(receiver: Receiver<RssFeed>) -> Self {
+        FeedStream {
+            receiver: Mutex::new(receiver),
+        }
+    }
+
+    /// Blocks until the next feed is found; `None` once the channel has
+    /// closed because every URL has been processed.
+    fn next_feed(&self) -> Option<RssFeed> {
+        self.receiver.lock().ok()?.recv().ok()
+    }
+}
+
+fn discover_url(
+    url: &str,
+    client: &Client,
+    config: &MinerConfig,
+    stats: &RunStats,
+    verbose: bool,
+) -> Result<UrlDiscovery> {
+    if verbose {
+        println!("Processing: {}", url);
+    }
+    let result = find_rss_feeds_recorded(url, client, config, stats);
+    if verbose {
+        match &result {
+            Ok(UrlDiscovery {
+                no_feeds_reason: Some(reason),
+                ..
+            }) => println!("  No feeds found for {} ({})", url, reason),
+            Ok(discovery) => {
+                println!("  Found {} feed(s) for {}", discovery.feeds.len(), url)
+            }
+            Err(e) => eprintln!("  Error processing {}: {}", url, e),
+        }
+    }
+    result
+}
+
+fn resolve_url(base: &str, href: &str) -> Result<String> {
+    let base_url = Url::parse(base)?;
+    let resolved = base_url.join(&normalize_feed_scheme(href))?;
+    Ok(resolved.to_string())
+}
+
+/// Rewrites browser "subscribe" links to plain web URLs: `feed://host/rss`
+/// becomes `https://host/rss`, and `feed:https://host/rss` unwraps to
+/// `https://host/rss`. Other URLs are returned unchanged.
+pub fn normalize_feed_scheme(url: &str) -> String {
+    let Some(scheme) = url.get(..5).filter(|s| s.eq_ignore_ascii_case("feed:")) else {
+        return url.to_string();
+    };
+    let rest = &url[scheme.len()..];
+
+    if let Some(authority) = rest.strip_prefix("//") {
+        return format!("https://{}", authority);
+    }
+    rest.to_string()
+}
+
+/// Prefixes `https://` to input written without a scheme, such as
+/// `example.com/blog`. An explicit scheme, including plain `http://` for
+/// hosts without TLS, is kept as written.
+fn with_default_scheme(url: &str) -> String {
+    let has_scheme = url.split_once("://").is_some_and(|(scheme, _)| {
+        !scheme.is_empty()
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    });
+    if has_scheme {
+        url.to_string()
+    } else {
+        format!("https://{}", url)
+    }
+}
+
+/// Recognizes a feed format beyond the built-in RSS and Atom support.
+///
+/// Register implementations in [`MinerConfig::custom_validators`]. Feeds they
+/// accept are kept like any other; note that OPML output drops
+/// [`FeedType::Unknown`] feeds unless `include_unknown` is set.
+pub trait FeedValidator: std::fmt::Debug + Send + Sync {
+    /// Returns metadata if `body` is a feed in this validator's format.
+    fn try_validate(&self, body: &[u8]) -> Option<FeedInfo>;
+}
+
+/// Metadata gathered while validating a feed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeedInfo {
+    pub feed_type: FeedType,
+    /// The feed's own title; empty for [`FeedType::Unknown`] feeds.
+    pub title: String,
+    /// The RSS channel `description` or Atom `subtitle`, if non-empty.
+    pub description: Option<String>,
+    /// Number of RSS items or Atom entries.
+    pub item_count: usize,
+    /// `lastBuildDate` (falling back to `pubDate`) for RSS, `updated` for Atom.
+    pub last_updated: Option<String>,
+    /// The response's `ETag` header, if any.
+    pub etag: Option<String>,
+    /// The response's `Last-Modified` header, if any.
+    pub last_modified: Option<String>,
+    pub categories: Vec<String>,
+    /// The feed's canonical `rel="self"` link, if it declares a valid
+    /// absolute http(s) URL.
+    pub self_link: Option<String>,
+    /// `<language>` for RSS or `xml:lang` for Atom, normalized to BCP-47.
+    pub language: Option<String>,
+    /// The first `<author><name>` for Atom; for RSS, the first Dublin Core
+    /// `<dc:creator>`, falling back to `<managingEditor>`.
+    pub author: Option<String>,
+    /// Titles of the first items or entries, in document order, when
+    /// [`MinerConfig::sample_titles`] asks for them.
+    pub sample_titles: Vec<String>,
+    /// Where the feed was fetched from after an unfollowed cross-host
+    /// redirect, under [`MinerConfig::follow_feed_redirects`].
+    pub moved_to: Option<String>,
+    /// Other feeds an Atom feed points to: feed-level `rel="related"` links
+    /// and feed-typed `rel="alternate"` links, then the `rel="self"` links
+    /// of entries' `<source>` elements. Absolute http(s) URLs only, without
+    /// duplicates or the feed's own URL.
+    #[serde(default)]
+    pub related_feeds: Vec<String>,
+    /// The format and version the document declares, e.g. `RSS 2.0`,
+    /// `RSS 1.0` (RDF), `Atom 1.0`, or `JSON Feed 1.1`.
+    #[serde(default)]
+    pub format_version: Option<String>,
+}
+
+/// Upper bound on [`MinerConfig::sample_titles`].
+pub const MAX_SAMPLE_TITLES: usize = 20;
+
+impl FeedInfo {
+    /// Creates metadata of the given type with every other field empty.
+    pub fn new(feed_type: FeedType) -> Self {
+        FeedInfo {
+            feed_type,
+            title: String::new(),
+            description: None,
+            item_count: 0,
+            last_updated: None,
+            etag: None,
+            last_modified: None,
+            categories: Vec::new(),
+            self_link: None,
+            language: None,
+            author: None,
+            sample_titles: Vec::new(),
+            moved_to: None,
+            related_feeds: Vec::new(),
+            format_version: None,
+        }
+    }
+}
+
+/// Fetches `feed_url` and returns its metadata if it is a valid feed.
+pub fn validate_feed(feed_url: &str, client: &Client) -> Option<FeedInfo> {
+    validate_feed_with_config(feed_url, client, &MinerConfig::default())
+}
+
+pub fn validate_feed_with_config(
+    feed_url: &str,
+    client: &Client,
+    config: &MinerConfig,
+) -> Option<FeedInfo> {
+    let stats = RunStats::new(config);
+    let info = validate_rss_feed(feed_url, None, client, config, &stats);
+    stats.save_validation_cache();
+    info
+}
+
+/// Formats a human-readable, multi-line health report for a validated feed.
+pub fn format_feed_report(feed_url: &str, info: &FeedInfo) -> String {
+    let mut report = format!("{}\n", feed_url);
+    report.push_str(&format!("  Type: {}\n", info.feed_type.as_str()));
+    report.push_str(&format!("  Title: {}\n", info.title));
+    report.push_str(&format!("  Items: {}\n", info.item_count));
+    report.push_str(&format!(
+        "  Last updated: {}\n",
+        info.last_updated.as_deref().unwrap_or("unknown")
+    ));
+    report.push_str(&format!(
+        "  Categories: {}\n",
+        if info.categories.is_empty() {
+            "none".to_string()
+        } else {
+            info.categories.join(", ")
+        }
+    ));
+    report.push_str(&format!(
+        "  Language: {}\n",
+        info.language.as_deref().unwrap_or("unknown")
+    ));
+    if !info.sample_titles.is_empty() {
+        report.push_str("  Recent items:\n");
+        for title in &info.sample_titles {
+            report.push_str(&format!("    - {}\n", title));
+        }
+    }
+    report
+}
+
+/// Fetches and parses a candidate feed. `page_url` is the page it was found
+/// on, sent as the Referer when [`MinerConfig::send_referer`] is set.
+///
+/// Concurrent calls for the same feed URL share one validation: the first
+/// caller fetches the feed while the others wait for and reuse its result.
+fn validate_rss_feed(
+    feed_url: &str,
+    page_url: Option<&str>,
+    client: &Client,
+    config: &MinerConfig,
+    stats: &RunStats,
+) -> Option<FeedInfo> {
+    let info = validate_rss_feed_once(feed_url, page_url, client, config, stats);
+    if config.follow_related_feeds
+        && let Some(info) = &info
+    {
+        stats.record_related_feeds(feed_url, info);
+    }
+    info
+}
+
+fn validate_rss_feed_once(
+    feed_url: &str,
+    page_url: Option<&str>,
+    client: &Client,
+    config: &MinerConfig,
+    stats: &RunStats,
+) -> Option<FeedInfo> {
+    if let Some(info) = stats
+        .validation_cache
+        .as_ref()
+        .and_then(|cache| cache.get(feed_url))
+    {
+        return Some(info);
+    }
+
+    let Ok(mut in_flight) = stats.in_flight.lock() else {
+        return fetch_and_validate_feed(feed_url, page_url, client, config, stats);
+    };
+    let cell = Arc::clone(in_flight.entry(feed_url.to_string()).or_default());
+    drop(in_flight);
+
+    let info = cell
+        .get_or_init(|| {
+            let info = fetch_and_validate_feed(feed_url, page_url, client, config, stats);
+            if let (Some(cache), Some(info)) = (&stats.validation_cache, &info) {
+                cache.insert(feed_url, info);
+            }
+            info
+        })
+        .clone();
+
+    // Later validations fetch afresh; only the ETag cache outlives this one
+    if let Ok(mut in_flight) = stats.in_flight.lock()
+        && in_flight
+            .get(feed_url)
+            .is_some_and(|current| Arc::ptr_eq(current, &cell))
+    {
+        in_flight.remove(feed_url);
+    }
+
+    info
+}
+
+fn fetch_and_validate_feed(
+    feed_url: &str,
+    page_url: Option<&str>,
+    client: &Client,
+    config: &MinerConfig,
+    stats: &RunStats,
+) -> Option<FeedInfo> {
+    // Try to fetch and parse the feed
+    let started = Instant::now();
+    let referer = page_url.filter(|_| config.send_referer);
+    let fetched = fetch_feed_body(feed_url, referer, client, config, stats);
+    stats.record_validation(feed_url, started.elapsed());
+    validate_fetched_feed(feed_url, fetched?, config, stats)
+}
+
+/// [`parse_fetched_feed`], noting the response's cache validators on the
+/// result.
+fn validate_fetched_feed(
+    feed_url: &str,
+    fetched: FetchedFeed,
+    config: &MinerConfig,
+    stats: &RunStats,
+) -> Option<FeedInfo> {
+    let etag = fetched.etag.clone();
+    let last_modified = fetched.last_modified.clone();
+    let info = parse_fetched_feed(feed_url, fetched, config, stats)?;
+    Some(FeedInfo {
+        etag,
+        last_modified,
+        ..info
+    })
+}
+
+/// Parses a fetched candidate feed as [`MinerConfig::validation_mode`] and
+/// the custom validators direct.
+fn parse_fetched_feed(
+    feed_url: &str,
+    fetched: FetchedFeed,
+    config: &MinerConfig,
+    stats: &RunStats,
+) -> Option<FeedInfo> {
+    if let Some(etag) = &fetched.etag
+        && let Some(info) = stats.cached_feed(feed_url, etag)
+    {
+        return Some(info);
+    }
+
+    let content_type = fetched.content_type.as_deref();
+    if config.validation_mode == ValidationMode::ContentType {
+        return sniff_feed_type(content_type, &fetched.body).map(|feed_type| FeedInfo {
+            moved_to: fetched.moved_to,
+            ..FeedInfo::new(feed_type)
+        });
+    }
+
+    stats.feeds_parsed.fetch_add(1, Ordering::Relaxed);
+    let body = fetched.body.as_bytes();
+    let parsed = parse_feed_body(body, config.sample_titles).or_else(|| {
+        config
+            .custom_validators
+            .iter()
+            .find_map(|validator| validator.try_validate(body))
+    });
+    if let Some(mut info) = parsed {
+        info.moved_to = fetched.moved_to;
+        if let Some(etag) = &fetched.etag {
+            stats.cache_feed(feed_url, etag, &info);
+        }
+        return Some(info);
+    }
+
+    if config.keep_unknown && content_type.is_some_and(is_feed_content_type) {
+        return Some(FeedInfo {
+            moved_to: fetched.moved_to,
+            ..FeedInfo::new(FeedType::Unknown)
+        });
+    }
+
+    None
+}
+
+/// Starts a GET request to `url`, with its host's timeout override if it
+/// has one.
+fn get_request(client: &Client, url: &str, config: &MinerConfig) -> RequestBuilder {
+    client.get(url).timeout(config.timeout_for(url))
+}
+
+/// Sends `request`, retrying 429 and 503 responses up to
+/// [`MinerConfig::max_retries`] times. Each retry waits as long as the
+/// response's `Retry-After` asks (seconds or an HTTP date), or backs off
+/// exponentially from one second without it, capped at
+/// [`MinerConfig::max_retry_wait`], plus up to
+/// [`MinerConfig::retry_jitter`] chosen at random.
+fn send_with_retries(request: RequestBuilder, config: &MinerConfig) -> reqwest::Result<Response> {
+    let mut request = request;
+    let mut attempt = 0;
+    let mut rng = None;
+    loop {
+        let next = request.try_clone();
+        let response = request.send()?;
+
+        let retryable = matches!(
+            response.status(),
+            StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+        );
+        let Some(next) = next.filter(|_| retryable && attempt < config.max_retries) else {
+            return Ok(response);
+        };
+
+        let wait = retry_after(&response).unwrap_or(Duration::from_secs(1 << attempt.min(5)));
+        let rng = rng.get_or_insert_with(JitterRng::from_clock);
+        thread::sleep(retry_delay(wait, config, rng));
+        request = next;
+        attempt += 1;
+    }
+}
+
+/// Reads `response`'s body as text, recording the bytes it took to download
+/// against `url`: its Content-Length, or the length read without one. The
+/// text is decoded from the response's charset, so its own length can differ.
+fn read_text(response: Response, url: &str, stats: &RunStats) -> reqwest::Result<String> {
+    let content_length = response.content_length();
+    let text = response.text()?;
+    stats.record_download(
+        url,
+        content_length.map_or(text.len(), |bytes| bytes as usize),
+    );
+    Ok(text)
+}
+
+/// The wait before a retry: `wait` capped at
+/// [`MinerConfig::max_retry_wait`], plus random jitter.
+fn retry_delay(wait: Duration, config: &MinerConfig, rng: &mut JitterRng) -> Duration {
+    wait.min(config.max_retry_wait) + rng.jitter(config.retry_jitter)
+}
+
+/// SplitMix64, seedable so tests can reproduce delays and samples. Only fit
+/// for spreading out retries and picking inputs, not for anything
+/// security-sensitive.
+struct JitterRng(u64);
+
+impl JitterRng {
+    fn new(seed: u64) -> Self {
+        JitterRng(seed)
+    }
+
+    /// Seeds from the clock and the current thread, so workers retrying at
+    /// the same moment still draw different delays.
+    fn from_clock() -> Self {
+        use std::hash::{BuildHasher, Hash, Hasher};
+
+        let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+        thread::current().id().hash(&mut hasher);
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos()
+            .hash(&mut hasher);
+        JitterRng::new(hasher.finish())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A duration drawn uniformly from zero to `max`, inclusive.
+    fn jitter(&mut self, max: Duration) -> Duration {
+        let max_nanos = u64::try_from(max.as_nanos()).unwrap_or(u64::MAX);
+        if max_nanos == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_nanos(self.next_u64() % max_nanos.saturating_add(1))
+    }
+}
+
+/// Parses a `Retry-After` header given as delay-seconds or an HTTP date.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = httpdate::parse_http_date(value).ok()?;
+    Some(at.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+/// A successfully fetched candidate feed.
+struct FetchedFeed {
+    body: String,
+    /// The response's Content-Type, if any.
+    content_type: Option<String>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// The target of a blocked redirect the body was fetched from instead.
+    moved_to: Option<String>,
+}
+
+/// Why [`try_fetch_feed_body`] got no feed body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FeedFetchError {
+    /// The server answered with this unsuccessful status.
+    Status(StatusCode),
+    /// No answer: a timeout, DNS, TLS, or connection failure, or a body
+    /// cut off mid-read.
+    Unreachable,
+}
+
+/// Fetches a candidate feed. Unsuccessful responses yield `None`.
+fn fetch_feed_body(
+    feed_url: &str,
+    referer: Option<&str>,
+    client: &Client,
+    config: &MinerConfig,
+    stats: &RunStats,
+) -> Option<FetchedFeed> {
+    try_fetch_feed_body(feed_url, referer, None, client, config, stats).ok()
+}
+
+/// [`fetch_feed_body`], telling apart why no body was fetched. With
+/// `validators` the request is conditional, and an unchanged feed fails
+/// with a 304 status.
+fn try_fetch_feed_body(
+    feed_url: &str,
+    referer: Option<&str>,
+    validators: Option<&FeedValidators>,
+    client: &Client,
+    config: &MinerConfig,
+    stats: &RunStats,
+) -> Result<FetchedFeed, FeedFetchError> {
+    let _permit = stats.request_permit();
+    let send = |url: &str, request: RequestBuilder| match send_with_retries(request, config) {
+        Ok(response) => Ok(response),
+        Err(e) => {
+            if let Some(tls) = tls_error(&e) {
+                stats.record_tls_failure(url, tls);
+            }
+            Err(FeedFetchError::Unreachable)
+        }
+    };
+    // A followed redirect is sent the same Referer and validators
+    let request = |url: &str| {
+        let mut request = get_request(client, url, config);
+        if let Some(referer) = referer {
+            request = request.header(REFERER, referer);
+        }
+        if let Some(validators) = validators {
+            if let Some(etag) = &validators.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &validators.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        request
+    };
+    let mut response = send(feed_url, request(feed_url))?;
+    let mut moved_to = None;
+    if config.follow_feed_redirects
+        && response.status().is_redirection()
+        && let Some(target) = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|location| resolve_url(feed_url, location).ok())
+    {
+        response = send(&target, request(&target))?;
+        moved_to = Some(target);
+    }
+    if !response.status().is_success() {
+        return Err(FeedFetchError::Status(response.status()));
+    }
+
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let content_type = header(CONTENT_TYPE);
+    let etag = header(ETAG);
+    let last_modified = header(LAST_MODIFIED);
+    let body = read_text(response, feed_url, stats).map_err(|_| FeedFetchError::Unreachable)?;
+
+    Ok(FetchedFeed {
+        body,
+        content_type,
+        etag,
+        last_modified,
+        moved_to,
+    })
+}
+
+/// Parses a feed body with the built-in `rss` and `atom_syndication`
+/// parsers.
+#[cfg(not(feature = "feed-rs"))]
+fn parse_feed_body(content: &[u8], sample: usize) -> Option<FeedInfo> {
+    parse_feed(content, sample)
+}
+
+/// Parses a feed body with `feed-rs`, which also understands JSON Feed.
+#[cfg(feature = "feed-rs")]
+fn parse_feed_body(content: &[u8], sample: usize) -> Option<FeedInfo> {
+    parse_feed_with_feed_rs(content, sample)
+}
+
+/// Parses RSS, Atom, or JSON Feed with the `feed-rs` crate, mapping its
+/// unified model onto [`FeedInfo`].
+#[cfg(feature = "feed-rs")]
+pub fn parse_feed_with_feed_rs(content: &[u8], sample: usize) -> Option<FeedInfo> {
+    use feed_rs::model;
+
+    let feed = feed_rs::parser::parse(content).ok()?;
+    let feed_type = match feed.feed_type {
+        model::FeedType::Atom => FeedType::Atom,
+        model::FeedType::JSON => FeedType::Json,
+        model::FeedType::RSS0 | model::FeedType::RSS1 | model::FeedType::RSS2 => FeedType::Rss,
+    };
+    let self_link = feed
+        .links
+        .iter()
+        .filter(|link| link.rel.as_deref() == Some("self"))
+        .filter_map(|link| Url::parse(&link.href).ok())
+        .find(|url| matches!(url.scheme(), "http" | "https"))
+        .map(|url| url.to_string());
+    // feed-rs drops entries' <source>, so only feed-level links are seen
+    let related = feed.links.iter().filter(|link| match link.rel.as_deref() {
+        Some("related") => true,
+        Some("alternate") => link.media_type.as_deref().is_some_and(is_feed_content_type),
+        _ => false,
+    });
+
+    Some(FeedInfo {
+        title: feed
+            .title
+            .as_ref()
+            .map(|title| title.content.trim().to_string())
+            .unwrap_or_default(),
+        description: feed
+            .description
+            .as_ref()
+            .and_then(|description| non_empty(&description.content)),
+        item_count: feed.entries.len(),
+        last_updated: feed.updated.map(|updated| updated.to_rfc3339()),
+        categories: feed
+            .categories
+            .iter()
+            .map(|category| category.term.clone())
+            .collect(),
+        related_feeds: absolute_feed_urls(
+            related.map(|link| link.href.as_str()),
+            self_link.clone(),
+        ),
+        self_link,
+        language: feed.language.as_deref().and_then(normalize_language_tag),
+        author: feed
+            .authors
+            .iter()
+            .find_map(|person| non_empty(&person.name)),
+        sample_titles: feed
+            .entries
+            .iter()
+            .filter_map(|entry| entry.title.as_ref())
+            .filter_map(|title| non_empty(&title.content))
+            .take(sample.min(MAX_SAMPLE_TITLES))
+            .collect(),
+        format_version: feed_format_version(content),
+        ..FeedInfo::new(feed_type)
+    })
+}
+
+/// Parses `content` as RSS or Atom, recording up to `sample` item titles.
+#[cfg_attr(feature = "feed-rs", allow(dead_code))]
+fn parse_feed(content: &[u8], sample: usize) -> Option<FeedInfo> {
+    let sample = sample.min(MAX_SAMPLE_TITLES);
+
+    // Try to parse as RSS
+    if let Ok(channel) = rss::Channel::read_from(content) {
+        return Some(FeedInfo {
+            title: channel.title().to_string(),
+            description: non_empty(channel.description()),
+            item_count: channel.items().len(),
+            last_updated: channel
+                .last_build_date()
+                .or(channel.pub_date())
+                .map(str::to_string),
+            categories: channel
+                .categories()
+                .iter()
+                .map(|category| category.name().to_string())
+                .collect(),
+            self_link: channel
+                .atom_ext()
+                .and_then(|atom| find_self_link(atom.links())),
+            language: channel.language().and_then(normalize_language_tag),
+            author: channel
+                .dublin_core_ext()
+                .and_then(|dc| dc.creators().iter().find_map(|creator| non_empty(creator)))
+                .or_else(|| channel.managing_editor().and_then(non_empty)),
+            sample_titles: channel
+                .items()
+                .iter()
+                .filter_map(|item| item.title().and_then(non_empty))
+                .take(sample)
+                .collect(),
+            format_version: feed_format_version(content),
+            ..FeedInfo::new(FeedType::Rss)
+        });
+    }
+
+    // Try to parse as Atom
+    if let Ok(feed) = atom_syndication::Feed::read_from(content) {
+        return Some(FeedInfo {
+            title: feed.title().as_str().to_string(),
+            description: feed
+                .subtitle()
+                .and_then(|subtitle| non_empty(subtitle.as_str())),
+            item_count: feed.entries().len(),
+            last_updated: Some(feed.updated().to_rfc3339()),
+            categories: feed
+                .categories()
+                .iter()
+                .map(|category| category.term().to_string())
+                .collect(),
+            self_link: find_self_link(feed.links()),
+            related_feeds: find_related_feeds(&feed),
+            language: feed.lang().and_then(normalize_language_tag),
+            author: feed
+                .authors()
+                .iter()
+                .find_map(|person| non_empty(person.name())),
+            sample_titles: feed
+                .entries()
+                .iter()
+                .filter_map(|entry| non_empty(entry.title().as_str()))
+                .take(sample)
+                .collect(),
+            format_version: feed_format_version(content),
+            ..FeedInfo::new(FeedType::Atom)
+        });
+    }
+
+    None
+}
+
+/// Reads the format and version a feed document declares from its root
+/// element: `<rss version>`, `<rdf:RDF>` for RSS 1.0, or `<feed>` with the
+/// Atom 1.0 or 0.3 namespace. JSON Feed's top-level `version` URL is
+/// recognized too.
+fn feed_format_version(content: &[u8]) -> Option<String> {
+    if content.trim_ascii_start().starts_with(b"{") {
+        let feed: serde_json::Value = serde_json::from_slice(content).ok()?;
+        let version = feed.get("version")?.as_str()?;
+        let number = version.strip_prefix("https://jsonfeed.org/version/")?;
+        return Some(format!("JSON Feed {}", number));
+    }
+
+    let mut reader = Reader::from_reader(content);
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(element) | Event::Empty(element)) => {
+                let attribute = |name: &[u8]| {
+                    element
+                        .try_get_attribute(name)
+                        .ok()
+                        .flatten()
+                        .and_then(|attr| attr.unescape_value().ok())
+                        .map(|value| value.trim().to_string())
+                };
+                return match element.local_name().as_ref() {
+                    b"rss" => attribute(b"version")
+                        .filter(|version| !version.is_empty())
+                        .map(|version| format!("RSS {}", version)),
+                    b"RDF" => Some("RSS 1.0".to_string()),
+                    b"feed" => match attribute(b"xmlns").as_deref() {
+                        Some("http://purl.org/atom/ns#") => Some("Atom 0.3".to_string()),
+                        _ => Some("Atom 1.0".to_string()),
+                    },
+                    _ => None,
+                };
+            }
+            Ok(Event::Eof) | Err(_) => return None,
+            Ok(_) => buf.clear(),
+        }
+    }
+}
+
+fn non_empty(text: &str) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Normalizes a declared language such as `EN_us` to BCP-47 casing (`en-US`).
+///
+/// Tags that don't look like BCP-47 are kept as declared, minus surrounding
+/// whitespace; empty tags yield `None`.
+fn normalize_language_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return None;
+    }
+
+    let subtags: Vec<&str> = tag.split(['-', '_']).collect();
+    let well_formed = subtags.iter().all(|subtag| {
+        (1..=8).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+    }) && subtags[0].chars().all(|c| c.is_ascii_alphabetic());
+    if !well_formed {
+        return Some(tag.to_string());
+    }
+
+    let normalized: Vec<String> = subtags
+        .iter()
+        .enumerate()
+        .map(|(i, subtag)| match subtag.len() {
+            // Region, e.g. "US"
+            2 if i > 0 && subtag.chars().all(|c| c.is_ascii_alphabetic()) => {
+                subtag.to_ascii_uppercase()
+            }
+            // Script, e.g. "Hant"
+            4 if i > 0 && subtag.chars().all(|c| c.is_ascii_alphabetic()) => {
+                let lower = subtag.to_ascii_lowercase();
+                lower[..1].to_ascii_uppercase() + &lower[1..]
+            }
+            _ => subtag.to_ascii_lowercase(),
+        })
+        .collect();
+    Some(normalized.join("-"))
+}
+
+/// Returns the first `rel="self"` link that is an absolute http(s) URL.
+#[cfg_attr(feature = "feed-rs", allow(dead_code))]
+fn find_self_link(links: &[atom_syndication::Link]) -> Option<String> {
+    links
+        .iter()
+        .filter(|link| link.rel() == "self")
+        .filter_map(|link| Url::parse(link.href()).ok())
+        .find(|url| matches!(url.scheme(), "http" | "https"))
+        .map(|url| url.to_string())
+}
+
+/// Collects [`FeedInfo::related_feeds`] from an Atom document.
+#[cfg_attr(feature = "feed-rs", allow(dead_code))]
+fn find_related_feeds(feed: &atom_syndication::Feed) -> Vec<String> {
+    let linked = feed.links().iter().filter(|link| {
+        link.rel() == "related"
+            || (link.rel() == "alternate" && link.mime_type().is_some_and(is_feed_content_type))
+    });
+    let sources = feed
+        .entries()
+        .iter()
+        .filter_map(|entry| entry.source())
+        .flat_map(|source| source.links())
+        .filter(|link| link.rel() == "self");
+    let own_url = find_self_link(feed.links());
+    absolute_feed_urls(linked.chain(sources).map(|link| link.href()), own_url)
+}
+
+/// Keeps the absolute http(s) URLs in `hrefs`, normalized and deduplicated
+/// in order, leaving out `own_url`.
+fn absolute_feed_urls<'a>(
+    hrefs: impl Iterator<Item = &'a str>,
+    own_url: Option<String>,
+) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    for href in hrefs {
+        let Ok(url) = Url::parse(href.trim()) else {
+            continue;
+        };
+        let url = url.to_string();
+        if url.starts_with("http") && Some(&url) != own_url.as_ref() && !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+    urls
+}
+
+/// Issues a HEAD request and reports whether a full GET is worthwhile.
+///
+/// Servers that reject HEAD (405/501) or fail to answer it are given the
+/// benefit of the doubt, as are responses without a Content-Type.
+fn head_probe_allows(
+    feed_url: &str,
+    client: &Client,
+    config: &MinerConfig,
+    stats: &RunStats,
+) -> bool {
+    let _permit = stats.request_permit();
+    let request = client.head(feed_url).timeout(config.timeout_for(feed_url));
+    let response = match request.send() {
+        Ok(response) => response,
+        Err(_) => return true,
+    };
+    stats.record_download(feed_url, response.content_length().unwrap_or(0) as usize);
+
+    let status = response.status();
+    if status == StatusCode::METHOD_NOT_ALLOWED || status == StatusCode::NOT_IMPLEMENTED {
+        return true;
+    }
+    if !status.is_success() {
+        return false;
+    }
+
+    match response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(content_type) => {
+            let content_type = content_type.to_ascii_lowercase();
+            ["xml", "rss", "atom", "json"]
+                .iter()
+                .any(|marker| content_type.contains(marker))
+        }
+        None => true,
+    }
+}
+
+/// Classifies a feed by its Content-Type and root element alone, for
+/// [`ValidationMode::ContentType`]. The Content-Type must be a feed or XML
+/// type, and the first element must be `<rss>` or `<rdf:RDF>` (RSS) or
+/// `<feed>` (Atom); nothing past it is parsed.
+fn sniff_feed_type(content_type: Option<&str>, body: &str) -> Option<FeedType> {
+    let mime = content_type?
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    if !matches!(
+        mime.as_str(),
+        "application/rss+xml"
+            | "application/atom+xml"
+            | "application/rdf+xml"
+            | "application/xml"
+            | "text/xml"
+    ) {
+        return None;
+    }
+
+    let mut reader = Reader::from_str(body);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(element) | Event::Empty(element)) => {
+                return match element.local_name().as_ref() {
+                    b"rss" | b"RDF" => Some(FeedType::Rss),
+                    b"feed" => Some(FeedType::Atom),
+                    _ => None,
+                };
+            }
+            Ok(Event::Eof) | Err(_) => return None,
+            Ok(_) => {}
+        }
+    }
+}
+
+/// Returns true for Content-Types that unambiguously identify a feed.
+fn is_feed_content_type(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    matches!(
+        mime.as_str(),
+        "application/rss+xml" | "application/atom+xml" | "application/feed+json"
+    )
+}
+
+fn extract_head_html(html: &str) -> Option<&str> {
+    let lower = html.to_ascii_lowercase();
+    let head_start = lower.find("<head")?;
+    let tag_end = lower[head_start..].find('>')? + head_start + 1;
+    let head_end = lower[tag_end..].find("</head")? + tag_end;
+    Some(&html[tag_end..head_end])
+}
+
+/// The placeholder title for a feed found at `url`: its host name, or
+/// "Feed at <ip>" when the host is an IP address.
+fn extract_title_from_url(url: &str) -> String {
+    match Url::parse(url).ok().as_ref().and_then(Url::host) {
+        Some(url::Host::Domain(host)) => host.to_string(),
+        Some(ip) => format!("Feed at {}", ip),
+        None => "Unknown".to_string(),
+    }
+}
+
+/// Renames feeds still titled "Feed at <ip>" after their site's
+/// reverse-DNS name, under [`MinerConfig::reverse_dns_titles`]. Feeds
+/// whose address has no name keep their title.
+fn name_ip_feeds(feeds: &mut [RssFeed]) {
+    let mut names: HashMap<IpAddr, Option<String>> = HashMap::new();
+    for feed in feeds {
+        let Some(ip) = Url::parse(&feed.html_url)
+            .ok()
+            .and_then(|url| match url.host()? {
+                url::Host::Ipv4(ip) => Some(IpAddr::V4(ip)),
+                url::Host::Ipv6(ip) => Some(IpAddr::V6(ip)),
+                url::Host::Domain(_) => None,
+            })
+        else {
+            continue;
+        };
+        if feed.title != extract_title_from_url(&feed.html_url) {
+            continue;
+        }
+        let name = names.entry(ip).or_insert_with(|| {
+            dns_lookup::lookup_addr(&ip)
+                .ok()
+                .filter(|name| name.parse::<IpAddr>().is_err())
+        });
+        if let Some(name) = name {
+            feed.title = name.clone();
+        }
+    }
+}
+
+/// Reduces an input URL to its domain, unless it is a fediverse actor URL
+/// that `options` asks to keep.
+fn normalize_input_url(input: &str, options: &InputOptions) -> String {
+    if (options.keep_actor_paths && fediverse_actor_feed_url(input).is_some())
+        || known_site_feed_url(input, &options.known_sites).is_some()
+    {
+        return input.trim_end_matches('/').to_string();
+    }
+    normalize_to_domain_url(input)
+}
+
+fn normalize_to_domain_url(input: &str) -> String {
+    if let Ok(url) = Url::parse(input)
+        && let Some(host) = url.host_str()
+    {
+        let mut base = format!("{}://{}", url.scheme(), host);
+        if let Some(port) = url.port() {
+            base.push(':');
+            base.push_str(&port.to_string());
+        }
+        return base;
+    }
+
+    input.to_string()
+}
+
+/// Formats XML string with indentation for better readability.
+///
+/// Takes compact XML and reformats it with proper line breaks and 2-space indentation.
+/// This makes the XML output more human-readable while maintaining semantic equivalence.
+///
+/// # Arguments
+///
+/// * `xml` - A string slice containing the XML to format
+///
+/// # Returns
+///
+/// * `Result<String>` - The formatted XML string, or an error if parsing/formatting fails
+///
+/// # Errors
+///
+/// Returns an error if:
+/// * The input XML is malformed
+/// * XML event writing fails
+/// * UTF-8 conversion fails
+fn pretty_print_xml(xml: &str) -> Result<String> {
+    const INDENT_CHAR: u8 = b' ';
+    const INDENT_SIZE: usize = 2;
+
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut writer = Writer::new_with_indent(Cursor::new(Vec::new()), INDENT_CHAR, INDENT_SIZE);
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Eof) => break,
+            Ok(event) => {
+                writer
+                    .write_event(event)
+                    .context("Failed to write XML event")?;
+            }
+            Err(e) => return Err(anyhow::anyhow!("Error parsing XML: {}", e)),
+        }
+    }
+
+    let result = writer.into_inner().into_inner();
+    String::from_utf8(result).context("Failed to convert XML to UTF-8")
+}
+
+/// A sed-style `s/pattern/replacement/` rewrite of feed titles, as accepted
+/// by `--transform-title`.
+///
+/// The pattern uses [`regex`] syntax and every match is replaced;
+/// `$1` or `${name}` in the replacement refer to capture groups. Any
+/// character may stand in for `/` as the delimiter, and a backslash escapes
+/// it within the pattern or replacement.
+#[derive(Debug, Clone)]
+pub struct TitleTransform {
+    pattern: regex::Regex,
+    replacement: String,
+}
+
+impl TitleTransform {
+    /// Parses an `s/pattern/replacement/` expression.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let invalid = || {
+            format!(
+                "Invalid title transform (expected s/pattern/replacement/): {}",
+                spec
+            )
+        };
+        let rest = spec.strip_prefix('s').with_context(invalid)?;
+        let delimiter = rest.chars().next().with_context(invalid)?;
+        if delimiter.is_alphanumeric() || delimiter == '\\' {
+            bail!(invalid());
+        }
+
+        // Split on unescaped delimiters, unescaping them as we go
+        let mut parts = vec![String::new()];
+        let mut chars = rest[delimiter.len_utf8()..].chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some(next) if next == delimiter => parts.last_mut().unwrap().push(next),
+                    Some(next) => parts.last_mut().unwrap().extend(['\\', next]),
+                    None => parts.last_mut().unwrap().push('\\'),
+                },
+                c if c == delimiter => parts.push(String::new()),
+                c => parts.last_mut().unwrap().push(c),
+            }
+        }
+        let [pattern, replacement, trailing] =
+            <[String; 3]>::try_from(parts).map_err(|_| anyhow::anyhow!(invalid()))?;
+        if !trailing.is_empty() {
+            bail!(invalid());
+        }
+
+        let pattern = regex::Regex::new(&pattern)
+            .with_context(|| format!("Invalid title transform pattern: {}", spec))?;
+        Ok(TitleTransform {
+            pattern,
+            replacement,
+        })
+    }
+
+    /// Applies the transform to `title`, trimming the result.
+    pub fn apply(&self, title: &str) -> String {
+        self.pattern
+            .replace_all(title, self.replacement.as_str())
+            .trim()
+            .to_string()
+    }
+}
+
+/// Rewrites every feed title with `transforms`, in order.
+pub fn transform_titles(feeds: &mut [RssFeed], transforms: &[TitleTransform]) {
+    for feed in feeds {
+        for transform in transforms {
+            feed.title = transform.apply(&feed.title);
+        }
+    }
+}
+
+/// Default longest title, in characters, kept by [`normalize_title`].
+pub const DEFAULT_MAX_TITLE_LENGTH: usize = 200;
+
+/// Trims `title` and collapses each run of internal whitespace, newlines
+/// included, into one space. Titles longer than `max_len` characters are
+/// cut to fit with a trailing `…`; a `max_len` of 0 never truncates.
+pub fn normalize_title(title: &str, max_len: usize) -> String {
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    if max_len == 0 || title.chars().count() <= max_len {
+        return title;
+    }
+    let truncated: String = title.chars().take(max_len - 1).collect();
+    format!("{}…", truncated.trim_end())
+}
+
+/// Normalizes every feed title with [`normalize_title`].
+pub fn normalize_titles(feeds: &mut [RssFeed], max_len: usize) {
+    for feed in feeds {
+        feed.title = normalize_title(&feed.title, max_len);
+    }
+}
+
+/// Removes feeds with duplicate URLs, keeping the first occurrence's position.
+///
+/// Metadata from later duplicates is merged in rather than discarded: a real
+/// title replaces a placeholder or host-derived one, and categories are
+/// unioned in order of first appearance.
+pub fn dedupe_feeds(feeds: Vec<RssFeed>) -> Vec<RssFeed> {
+    dedupe_feeds_with_report(feeds).0
+}
+
+/// Like [`dedupe_feeds`], also returning the duplicates that were folded into
+/// an earlier feed, as they were before merging and in input order.
+pub fn dedupe_feeds_with_report(feeds: Vec<RssFeed>) -> (Vec<RssFeed>, Vec<RssFeed>) {
+    let mut positions: HashMap<String, usize> = HashMap::with_capacity(feeds.len());
+    let mut unique: Vec<RssFeed> = Vec::with_capacity(feeds.len());
+    let mut removed = Vec::new();
+
+    for feed in feeds {
+        match positions.get(&feed.url) {
+            Some(&index) => {
+                removed.push(feed.clone());
+                unique[index].merge(feed);
+            }
+            None => {
+                positions.insert(feed.url.clone(), unique.len());
+                unique.push(feed);
+            }
+        }
+    }
+
+    (unique, removed)
+}
+
+/// Which host variant [`collapse_www_duplicates`] keeps.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WwwPreference {
+    /// Keep `example.com`.
+    #[default]
+    Bare,
+    /// Keep `www.example.com`.
+    Www,
+}
+
+/// Merges feeds whose URLs differ only by a leading `www.` on the host,
+/// such as `https://www.example.com/feed` and `https://example.com/feed`.
+///
+/// The merged feed keeps the first occurrence's position and the URL of
+/// the `prefer`red variant, if that variant was found. Returns the feeds
+/// kept and the ones merged away, as in [`dedupe_feeds_with_report`].
+pub fn collapse_www_duplicates(
+    feeds: Vec<RssFeed>,
+    prefer: WwwPreference,
+) -> (Vec<RssFeed>, Vec<RssFeed>) {
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut unique: Vec<RssFeed> = Vec::with_capacity(feeds.len());
+    let mut removed = Vec::new();
+
+    for feed in feeds {
+        let Some((key, is_www)) = www_collapse_key(&feed.url) else {
+            unique.push(feed);
+            continue;
+        };
+        match positions.get(&key) {
+            Some(&index) => {
+                removed.push(feed.clone());
+                let kept = &mut unique[index];
+                let preferred = is_www == (prefer == WwwPreference::Www);
+                if preferred && kept.url != feed.url {
+                    kept.url = feed.url.clone();
+                    kept.raw_url = feed.raw_url.clone();
+                }
+                kept.merge(feed);
+            }
+            None => {
+                positions.insert(key, unique.len());
+                unique.push(feed);
+            }
+        }
+    }
+
+    (unique, removed)
+}
+
+/// Returns `url` with any leading `www.` removed from its host, and whether
+/// there was one. URLs without a host name yield `None`.
+fn www_collapse_key(url: &str) -> Option<(String, bool)> {
+    let mut parsed = Url::parse(url).ok()?;
+    let host = parsed.host_str()?.to_ascii_lowercase();
+    match host.strip_prefix("www.") {
+        Some(bare) if bare.contains('.') => {
+            parsed.set_host(Some(bare)).ok()?;
+            Some((parsed.to_string(), true))
+        }
+        _ => Some((parsed.to_string(), false)),
+    }
+}
+
+/// Merges feeds of the same site whose titles differ only by case,
+/// punctuation, or a trailing feed marker such as `" - RSS"` or
+/// `" Atom Feed"`, keeping the first occurrence's position and URL.
+///
+/// Feeds from different sites, and feeds with a missing or placeholder
+/// title, are never merged. Returns the feeds kept and the ones merged away,
+/// as in [`dedupe_feeds_with_report`].
+pub fn dedupe_similar_titles(feeds: Vec<RssFeed>) -> (Vec<RssFeed>, Vec<RssFeed>) {
+    let mut positions: HashMap<(String, String), usize> = HashMap::new();
+    let mut unique: Vec<RssFeed> = Vec::with_capacity(feeds.len());
+    let mut removed = Vec::new();
+
+    for feed in feeds {
+        let key = (!feed.has_fallback_title())
+            .then(|| similar_title_key(&feed.title))
+            .filter(|title| !title.is_empty())
+            .map(|title| (host_key(&feed.html_url).unwrap_or_default(), title));
+        match key.as_ref().and_then(|key| positions.get(key)) {
+            Some(&index) => {
+                removed.push(feed.clone());
+                unique[index].merge(feed);
+            }
+            None => {
+                if let Some(key) = key {
+                    positions.insert(key, unique.len());
+                }
+                unique.push(feed);
+            }
+        }
+    }
+
+    (unique, removed)
+}
+
+/// Reduces a title to its lowercase words, dropping trailing words that
+/// only say it is a feed: "My Blog - RSS Feed" becomes "my blog".
+fn similar_title_key(title: &str) -> String {
+    const FEED_MARKERS: [&str; 5] = ["rss", "atom", "feed", "xml", "syndication"];
+    let lower = title.to_lowercase();
+    let mut words: Vec<&str> = lower
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+    while words.len() > 1 && words.last().is_some_and(|word| FEED_MARKERS.contains(word)) {
+        words.pop();
+    }
+    words.join(" ")
+}
+
+/// Combines the results of several scans into one deduplicated list.
+///
+/// Feeds keep the order of their first appearance across `sets`, and
+/// duplicates are merged as in [`dedupe_feeds`].
+pub fn merge_feeds(sets: Vec<Vec<RssFeed>>) -> Vec<RssFeed> {
+    dedupe_feeds(sets.into_iter().flatten().collect())
+}
+
+pub fn create_opml_file(feeds: &[RssFeed], output_path: &Path) -> Result<()> {
+    create_opml_file_filtered(feeds, output_path, None)
+}
+
+pub fn create_opml_file_filtered(
+    feeds: &[RssFeed],
+    output_path: &Path,
+    feed_type_filter: Option<FeedType>,
+) -> Result<()> {
+    let options = OpmlOptions {
+        feed_type_filter,
+        ..Default::default()
+    };
+    create_opml_file_with_options(feeds, output_path, &options)
+}
+
+/// Options controlling which feeds end up in a generated OPML file.
+#[derive(Debug, Clone)]
+pub struct OpmlOptions {
+    /// Keep only feeds of this type; `None` keeps RSS and Atom feeds.
+    pub feed_type_filter: Option<FeedType>,
+    /// Also write [`FeedType::Unknown`] feeds when no type filter is set.
+    pub include_unknown: bool,
+    /// Nest feeds under one parent outline per host.
+    pub group_by_host: bool,
+    /// With `group_by_host`, write hosts that have a single feed as a plain
+    /// top-level outline instead of a one-child group.
+    pub flatten_single_group: bool,
+    /// With `group_by_host`, order each group's feeds by title
+    /// (case-insensitively) instead of discovery order.
+    pub sort_group_children: bool,
+    /// Indent the XML for human editing; when `false`, write it without any
+    /// whitespace between elements. Defaults to `true`.
+    pub pretty: bool,
+    /// Adjust outline attributes for a feed reader with import quirks.
+    pub compat: ReaderCompat,
+}
+
+/// Feed readers whose OPML import needs attributes the spec leaves optional.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReaderCompat {
+    /// Write attributes as discovered.
+    #[default]
+    Standard,
+    /// Older NetNewsWire releases skip outlines whose `type` is not `rss`
+    /// and need an `htmlUrl`: every feed is typed `rss`, and a missing
+    /// `htmlUrl` falls back to the feed's site root.
+    NetNewsWire,
+}
+
+impl Default for OpmlOptions {
+    fn default() -> Self {
+        OpmlOptions {
+            feed_type_filter: None,
+            include_unknown: false,
+            group_by_host: false,
+            flatten_single_group: false,
+            sort_group_children: false,
+            pretty: true,
+            compat: ReaderCompat::Standard,
+        }
+    }
+}
+
+pub fn create_opml_file_with_options(
+    feeds: &[RssFeed],
+    output_path: &Path,
+    options: &OpmlOptions,
+) -> Result<()> {
+    create_opml_file_with(feeds, output_path, options, |feed| {
+        feed_outline(feed, options.compat)
+    })
+}
+
+/// Like [`create_opml_file_with_options`], but builds each feed's outline
+/// with `outline` instead of [`feed_outline`], e.g. to fill in extra
+/// attributes. Filtering, deduplication, and folder and host grouping are
+/// unchanged.
+pub fn create_opml_file_with<F>(
+    feeds: &[RssFeed],
+    output_path: &Path,
+    options: &OpmlOptions,
+    outline: F,
+) -> Result<()>
+where
+    F: Fn(&RssFeed) -> opml::Outline,
+{
+    let mut opml = opml::OPML::default();
+
+    opml.head = Some(opml::Head {
+        title: Some(opml_title(options).to_string()),
+        ..Default::default()
+    });
+
+    // Skip feeds that don't match the filter, then merge duplicate URLs
+    let accepted = feeds
+        .iter()
+        .filter(|feed| opml_accepts(feed, options))
+        .cloned()
+        .collect();
+    let feeds = dedupe_feeds(accepted);
+
+    // Input categories become folders, in order of first appearance, each
+    // laid out like the top level; unfiled feeds follow them
+    let mut folders: Vec<(&str, Vec<RssFeed>)> = Vec::new();
+    let mut unfiled = Vec::new();
+    for feed in &feeds {
+        match feed.folder.as_deref() {
+            Some(name) => match folders.iter_mut().find(|(folder, _)| *folder == name) {
+                Some((_, members)) => members.push(feed.clone()),
+                None => folders.push((name, vec![feed.clone()])),
+            },
+            None => unfiled.push(feed.clone()),
+        }
+    }
+
+    let mut outlines: Vec<opml::Outline> = folders
+        .into_iter()
+        .map(|(name, members)| opml::Outline {
+            text: name.to_string(),
+            title: Some(name.to_string()),
+            outlines: body_outlines(&members, options, &outline),
+            ..Default::default()
+        })
+        .collect();
+    outlines.extend(body_outlines(&unfiled, options, &outline));
+
+    opml.body = opml::Body { outlines };
+
+    let output_path = &resolve_output_path(output_path, DEFAULT_OPML_FILE_NAME);
+    write_opml_file_with_validators(&opml, &feed_validators(&feeds), output_path, options.pretty)
+}
+
+/// Lays out feeds flat or, with `group_by_host`, grouped by host.
+fn body_outlines(
+    feeds: &[RssFeed],
+    options: &OpmlOptions,
+    outline: &dyn Fn(&RssFeed) -> opml::Outline,
+) -> Vec<opml::Outline> {
+    if options.group_by_host {
+        host_group_outlines(feeds, options, outline)
+    } else {
+        feeds.iter().map(outline).collect()
+    }
+}
+
+fn opml_title(options: &OpmlOptions) -> &'static str {
+    match options.feed_type_filter {
+        Some(FeedType::Rss) => "RSS Feeds",
+        Some(FeedType::Atom) => "Atom Feeds",
+        Some(FeedType::Json) => "JSON Feeds",
+        Some(FeedType::Unknown) => "Unknown Feeds",
+        None => "RSS and Atom Feeds",
+    }
+}
+
+fn opml_accepts(feed: &RssFeed, options: &OpmlOptions) -> bool {
+    match options.feed_type_filter {
+        Some(filter_type) => filter_type == feed.feed_type,
+        None => feed.feed_type != FeedType::Unknown || options.include_unknown,
+    }
+}
+
+/// Builds one parent outline per host, in host order, holding that host's feeds.
+fn host_group_outlines(
+    feeds: &[RssFeed],
+    options: &OpmlOptions,
+    outline: &dyn Fn(&RssFeed) -> opml::Outline,
+) -> Vec<opml::Outline> {
+    let mut groups: BTreeMap<String, Vec<&RssFeed>> = BTreeMap::new();
+    for feed in feeds {
+        groups
+            .entry(host_key(&feed.html_url).unwrap_or_else(|| "unknown".to_string()))
+            .or_default()
+            .push(feed);
+    }
+
+    groups
+        .into_iter()
+        .map(|(host, mut group)| {
+            if options.flatten_single_group && group.len() == 1 {
+                return outline(group[0]);
+            }
+            if options.sort_group_children {
+                group.sort_by(|a, b| {
+                    a.title
+                        .to_lowercase()
+                        .cmp(&b.title.to_lowercase())
+                        .then_with(|| a.url.cmp(&b.url))
+                });
+            }
+            opml::Outline {
+                text: host.clone(),
+                title: Some(host),
+                outlines: group.into_iter().map(outline).collect(),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// Builds the OPML outline written for `feed` by default.
+pub fn feed_outline(feed: &RssFeed, compat: ReaderCompat) -> opml::Outline {
+    let mut outline = opml::Outline {
+        text: feed.title.clone(),
+        r#type: Some(feed.feed_type.as_str().to_string()),
+        category: (!feed.categories.is_empty()).then(|| feed.categories.join(",")),
+        xml_url: Some(feed.url.clone()),
+        html_url: Some(feed.html_url.clone()),
+        language: feed.language.clone(),
+        version: feed.format_version.clone(),
+        ..Default::default()
+    };
+
+    if compat == ReaderCompat::NetNewsWire {
+        outline.r#type = Some(FeedType::Rss.as_str().to_string());
+        if feed.html_url.is_empty() {
+            outline.html_url = Some(normalize_to_domain_url(&feed.url));
+        }
+    }
+
+    outline
+}
+
+/// Incremental OPML writer for result sets too large to hold in memory.
+///
+/// The header is written on creation, each accepted feed is written as soon
+/// as it is passed to [`write_feed`](Self::write_feed), and the closing tags
+/// are written by [`finish`](Self::finish). Filtering, deduplication, and
+/// formatting match [`create_opml_file_with_options`] byte for byte, except
+/// that duplicates are dropped rather than merged since the first occurrence
+/// has already been written, and feeds are never nested under their
+/// [`folder`](RssFeed::folder).
+pub struct OpmlStreamWriter<W: Write> {
+    writer: Writer<W>,
+    options: OpmlOptions,
+    seen_urls: HashSet<String>,
+    body_open: bool,
+    written: usize,
+    /// Set by [`create`](OpmlStreamWriter::create), which streams into a
+    /// temporary file that only replaces the output once finished.
+    pending: Option<PendingOutput>,
+}
+
+impl OpmlStreamWriter<BufWriter<fs::File>> {
+    /// Writes the OPML header to a temporary file next to `output_path`.
+    /// [`finish`](Self::finish) renames it over `output_path`, so an
+    /// interrupted run leaves any previous file there untouched.
+    pub fn create(output_path: &Path, options: &OpmlOptions) -> Result<Self> {
+        let output_path = resolve_output_path(output_path, DEFAULT_OPML_FILE_NAME);
+        let temp_path = temp_path_for(&output_path);
+        let file = fs::File::create(&temp_path).context(format!(
+            "Failed to create OPML file: {}",
+            output_path.display()
+        ))?;
+        let pending = PendingOutput {
+            file: file.try_clone()?,
+            temp_path,
+            path: output_path,
+            done: false,
+        };
+        let mut writer = Self::new(BufWriter::new(file), options)?;
+        writer.pending = Some(pending);
+        Ok(writer)
+    }
+}
+
+/// A temporary file to be renamed over `path` once fully written; removed
+/// instead if dropped before then.
+struct PendingOutput {
+    file: fs::File,
+    temp_path: PathBuf,
+    path: PathBuf,
+    done: bool,
+}
+
+impl PendingOutput {
+    fn persist(mut self) -> Result<()> {
+        self.file
+            .sync_all()
+            .and_then(|()| fs::rename(&self.temp_path, &self.path))
+            .context(format!(
+                "Failed to write OPML file: {} (the existing file was left unchanged)",
+                self.path.display()
+            ))?;
+        self.done = true;
+        Ok(())
+    }
+}
+
+impl Drop for PendingOutput {
+    fn drop(&mut self) {
+        if !self.done {
+            let _ = fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+impl<W: Write> OpmlStreamWriter<W> {
+    pub fn new(inner: W, options: &OpmlOptions) -> Result<Self> {
+        if options.group_by_host {
+            bail!("Grouping by host is not supported when streaming OPML");
+        }
+
+        let mut writer = if options.pretty {
+            Writer::new_with_indent(inner, b' ', 2)
+        } else {
+            Writer::new(inner)
+        };
+
+        writer.write_event(Event::Start(
+            BytesStart::new("opml").with_attributes([("version", "2.0")]),
+        ))?;
+        writer.write_event(Event::Start(BytesStart::new("head")))?;
+        writer.write_event(Event::Start(BytesStart::new("title")))?;
+        writer.write_event(Event::Text(BytesText::new(opml_title(options))))?;
+        writer.write_event(Event::End(BytesEnd::new("title")))?;
+        writer.write_event(Event::End(BytesEnd::new("head")))?;
+
+        Ok(OpmlStreamWriter {
+            writer,
+            options: options.clone(),
+            seen_urls: HashSet::new(),
+            body_open: false,
+            written: 0,
+            pending: None,
+        })
+    }
+
+    /// Writes one feed, returning `false` if it was filtered out or a duplicate.
+    pub fn write_feed(&mut self, feed: &RssFeed) -> Result<bool> {
+        if !opml_accepts(feed, &self.options) || !self.seen_urls.insert(feed.url.clone()) {
+            return Ok(false);
+        }
+
+        if !self.body_open {
+            self.writer
+                .write_event(Event::Start(BytesStart::new("body")))?;
+            self.body_open = true;
+        }
+        let validators = FeedValidators::of(feed);
+        write_outline(
+            &mut self.writer,
+            &feed_outline(feed, self.options.compat),
+            validators.as_ref(),
+        )?;
+        self.written += 1;
+
+        Ok(true)
+    }
+
+    /// Number of feeds written so far.
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    /// Writes the closing tags and flushes, returning the underlying writer.
+    /// A writer from [`create`](OpmlStreamWriter::create) then replaces its
+    /// output file.
+    pub fn finish(mut self) -> Result<W> {
+        if self.body_open {
+            self.writer.write_event(Event::End(BytesEnd::new("body")))?;
+        } else {
+            self.writer
+                .write_event(Event::Empty(BytesStart::new("body")))?;
+        }
+        self.writer.write_event(Event::End(BytesEnd::new("opml")))?;
+
+        let mut inner = self.writer.into_inner();
+        inner.flush().context("Failed to flush OPML output")?;
+        if let Some(pending) = self.pending {
+            pending.persist()?;
+        }
+        Ok(inner)
+    }
+}
+
+/// Writes an outline with attributes in the same order as the `opml` crate,
+/// followed by `validators`.
+fn write_outline<W: Write>(
+    writer: &mut Writer<W>,
+    outline: &opml::Outline,
+    validators: Option<&FeedValidators>,
+) -> Result<()> {
+    let is_comment = outline.is_comment.map(|v| v.to_string());
+    let is_breakpoint = outline.is_breakpoint.map(|v| v.to_string());
+    let attributes = [
+        ("text", Some(&outline.text)),
+        ("type", outline.r#type.as_ref()),
+        ("isComment", is_comment.as_ref()),
+        ("isBreakpoint", is_breakpoint.as_ref()),
+        ("created", outline.created.as_ref()),
+        ("category", outline.category.as_ref()),
+        ("xmlUrl", outline.xml_url.as_ref()),
+        ("description", outline.description.as_ref()),
+        ("htmlUrl", outline.html_url.as_ref()),
+        ("language", outline.language.as_ref()),
+        ("title", outline.title.as_ref()),
+        ("version", outline.version.as_ref()),
+        ("url", outline.url.as_ref()),
+    ];
+
+    let mut start = BytesStart::new("outline");
+    for (name, value) in attributes {
+        if let Some(value) = value {
+            start.push_attribute((name, value.as_str()));
+        }
+    }
+    if let Some(validators) = validators {
+        validators.push_attributes(&mut start);
+    }
+
+    if outline.outlines.is_empty() {
+        writer.write_event(Event::Empty(start))?;
+    } else {
+        writer.write_event(Event::Start(start))?;
+        for child in &outline.outlines {
+            write_outline(writer, child, None)?;
+        }
+        writer.write_event(Event::End(BytesEnd::new("outline")))?;
+    }
+
+    Ok(())
+}
+
+/// A feed's HTTP cache validators, stored in OPML output as the `etag` and
+/// `lastModified` outline attributes. The `opml` crate drops attributes it
+/// doesn't know, so these are added and read back in separate XML passes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct FeedValidators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl FeedValidators {
+    /// The validators `feed` was served with, if any.
+    fn of(feed: &RssFeed) -> Option<Self> {
+        (feed.etag.is_some() || feed.last_modified.is_some()).then(|| FeedValidators {
+            etag: feed.etag.clone(),
+            last_modified: feed.last_modified.clone(),
+        })
+    }
+
+    fn push_attributes(&self, start: &mut BytesStart) {
+        if let Some(etag) = &self.etag {
+            start.push_attribute(("etag", etag.as_str()));
+        }
+        if let Some(last_modified) = &self.last_modified {
+            start.push_attribute(("lastModified", last_modified.as_str()));
+        }
+    }
+}
+
+/// Maps the URL of each feed served with cache validators to them.
+fn feed_validators(feeds: &[RssFeed]) -> HashMap<String, FeedValidators> {
+    feeds
+        .iter()
+        .filter_map(|feed| Some((feed.url.clone(), FeedValidators::of(feed)?)))
+        .collect()
+}
+
+/// Reads the unescaped value of an element's attribute.
+fn xml_attribute(element: &BytesStart, name: &[u8]) -> Option<String> {
+    element
+        .try_get_attribute(name)
+        .ok()
+        .flatten()
+        .and_then(|attr| attr.unescape_value().ok())
+        .map(|value| value.into_owned())
+}
+
+/// Reads the [`FeedValidators`] stored in the OPML file at `path`, keyed by
+/// `xmlUrl`, relative ones resolved as by [`read_opml_file`].
+fn read_feed_validators(path: &Path) -> Result<HashMap<String, FeedValidators>> {
+    let xml = fs::read_to_string(path)
+        .context(format!("Failed to read OPML file: {}", path.display()))?;
+    let mut reader = Reader::from_str(&xml);
+    let mut validators = HashMap::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(element) | Event::Empty(element))
+                if element.name().as_ref() == b"outline" =>
+            {
+                let found = FeedValidators {
+                    etag: xml_attribute(&element, b"etag"),
+                    last_modified: xml_attribute(&element, b"lastModified"),
+                };
+                if found == FeedValidators::default() {
+                    continue;
+                }
+                if let Some(xml_url) = xml_attribute(&element, b"xmlUrl") {
+                    let html_url = xml_attribute(&element, b"htmlUrl");
+                    let xml_url = absolute_xml_url(xml_url.trim(), html_url.as_deref())
+                        .unwrap_or_else(|| xml_url.trim().to_string());
+                    validators.insert(xml_url, found);
+                }
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(e) => bail!("Invalid OPML file: {}: {}", path.display(), e),
+        }
+    }
+    Ok(validators)
+}
+
+/// Adds the `etag` and `lastModified` attributes of `validators` to the
+/// outlines in `xml` whose `xmlUrl` they are keyed by.
+fn add_validator_attributes(
+    xml: &str,
+    validators: &HashMap<String, FeedValidators>,
+) -> Result<String> {
+    if validators.is_empty() {
+        return Ok(xml.to_string());
+    }
+    let with_validators = |mut element: BytesStart<'static>| {
+        if element.name().as_ref() == b"outline"
+            && let Some(found) = xml_attribute(&element, b"xmlUrl")
+                .and_then(|xml_url| validators.get(xml_url.trim()))
+        {
+            found.push_attributes(&mut element);
+        }
+        element
+    };
+
+    let mut reader = Reader::from_str(xml);
+    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    loop {
+        let event = match reader.read_event() {
+            Ok(Event::Eof) => break,
+            Ok(Event::Start(element)) => Event::Start(with_validators(element.into_owned())),
+            Ok(Event::Empty(element)) => Event::Empty(with_validators(element.into_owned())),
+            Ok(event) => event,
+            Err(e) => return Err(anyhow::anyhow!("Error parsing XML: {}", e)),
+        };
+        writer
+            .write_event(event)
+            .context("Failed to write XML event")?;
+    }
+
+    let result = writer.into_inner().into_inner();
+    String::from_utf8(result).context("Failed to convert XML to UTF-8")
+}
+
+/// How a feed fared when revalidated by [`check_opml_feeds`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedHealth {
+    /// Fetched and parsed as a feed.
+    Live,
+    /// Answered a conditional request with 304 Not Modified.
+    Unchanged,
+    /// Answered 404 or 410, or was fetched fine but did not parse.
+    Dead,
+    /// Could not be checked: a timeout, DNS or TLS failure, or an error
+    /// status other than 404 and 410.
+    Unreachable,
+}
+
+/// Revalidates every feed in the OPML file at `path`, returning each
+/// distinct `xmlUrl` with how it fared, in no particular order.
+///
+/// Feeds with `etag` or `lastModified` attributes, as written for feeds
+/// discovered with an `ETag` or `Last-Modified` header, are fetched with
+/// `If-None-Match` or `If-Modified-Since`, so unchanged feeds answer with a
+/// quick 304 and count as [`FeedHealth::Unchanged`].
+pub fn check_opml_feeds(path: &Path, client: &Client) -> Result<Vec<(String, FeedHealth)>> {
+    check_opml_feeds_with_config(path, client, &MinerConfig::default())
+}
+
+pub fn check_opml_feeds_with_config(
+    path: &Path,
+    client: &Client,
+    config: &MinerConfig,
+) -> Result<Vec<(String, FeedHealth)>> {
+    let document = read_opml_file(path)?;
+    let mut validators = read_feed_validators(path)?;
+
+    let mut urls = HashSet::new();
+    collect_xml_urls(&document.body.outlines, &mut urls);
+    Ok(check_feeds(urls, &mut validators, client, config)
+        .into_iter()
+        .collect())
+}
+
+/// Adds feeds to an existing OPML file, leaving its outlines untouched.
+///
+/// Feeds whose URL already appears anywhere in the file are skipped. A new
+/// feed with a [`folder`](RssFeed::folder) goes into the top-level outline
+/// of that name, which is created at the end if missing; other new feeds
+/// are appended at the top level. If `path` does not exist it is created as
+/// by [`create_opml_file_with_options`]. Returns the number of feeds added.
+pub fn append_to_opml_file(feeds: &[RssFeed], path: &Path, options: &OpmlOptions) -> Result<usize> {
+    let accepted: Vec<RssFeed> = feeds
+        .iter()
+        .filter(|feed| opml_accepts(feed, options))
+        .cloned()
+        .collect();
+    let accepted = dedupe_feeds(accepted);
+    if !path.exists() {
+        create_opml_file_with_options(&accepted, path, options)?;
+        return Ok(accepted.len());
+    }
+
+    let mut document = read_opml_file(path)?;
+    let mut validators = read_feed_validators(path)?;
+
+    let mut existing = HashSet::new();
+    collect_xml_urls(&document.body.outlines, &mut existing);
+
+    let mut added = 0;
+    for feed in accepted {
+        if !existing.insert(feed.url.clone()) {
+            continue;
+        }
+        if let Some(found) = FeedValidators::of(&feed) {
+            validators.insert(feed.url.clone(), found);
+        }
+        let outline = feed_outline(&feed, options.compat);
+        let outlines = &mut document.body.outlines;
+        match feed.folder.as_deref() {
+            Some(name) => {
+                let index = match outlines
+                    .iter()
+                    .position(|o| o.xml_url.is_none() && o.text == name)
+                {
+                    Some(index) => index,
+                    None => {
+                        outlines.push(opml::Outline {
+                            text: name.to_string(),
+                            title: Some(name.to_string()),
+                            ..Default::default()
+                        });
+                        outlines.len() - 1
+                    }
+                };
+                outlines[index].outlines.push(outline);
+            }
+            None => outlines.push(outline),
+        }
+        added += 1;
+    }
+
+    write_opml_file_with_validators(&document, &validators, path, options.pretty)?;
+    Ok(added)
+}
+
+/// Drops the feeds already listed in the OPML file at `baseline`, e.g. the
+/// output of a previous run, leaving only newly discovered ones.
+///
+/// A feed counts as listed when its canonical or discovered URL equals an
+/// `xmlUrl` anywhere in the file, folders included.
+pub fn exclude_baseline_feeds(feeds: Vec<RssFeed>, baseline: &Path) -> Result<Vec<RssFeed>> {
+    let document = read_opml_file(baseline)?;
+
+    let mut known = HashSet::new();
+    collect_xml_urls(&document.body.outlines, &mut known);
+    Ok(feeds
+        .into_iter()
+        .filter(|feed| !known.contains(&feed.url) && !known.contains(&feed.raw_url))
+        .collect())
+}
+
+fn collect_xml_urls(outlines: &[opml::Outline], urls: &mut HashSet<String>) {
+    for outline in outlines {
+        if let Some(xml_url) = &outline.xml_url
+            && Url::parse(xml_url.trim()).is_ok()
+        {
+            urls.insert(xml_url.trim().to_string());
+        }
+        collect_xml_urls(&outline.outlines, urls);
+    }
+}
+
+/// Revalidates `urls` in parallel, conditionally for those with
+/// `validators`. The validators of each feed refetched are replaced by the
+/// response's, and dropped if it sent none.
+fn check_feeds(
+    urls: HashSet<String>,
+    validators: &mut HashMap<String, FeedValidators>,
+    client: &Client,
+    config: &MinerConfig,
+) -> HashMap<String, FeedHealth> {
+    let known = &*validators;
+    let stats = RunStats::new(config);
+    let checked: Vec<(String, FeedHealth, Option<FeedValidators>)> = urls
+        .into_par_iter()
+        .map(|url| {
+            let (health, refetched) =
+                check_feed_health(&url, known.get(&url), client, config, &stats);
+            (url, health, refetched)
+        })
+        .collect();
+
+    let mut health = HashMap::new();
+    for (url, feed_health, refetched) in checked {
+        if feed_health == FeedHealth::Live {
+            match refetched.filter(|found| *found != FeedValidators::default()) {
+                Some(found) => validators.insert(url.clone(), found),
+                None => validators.remove(&url),
+            };
+        }
+        health.insert(url, feed_health);
+    }
+    health
+}
+
+/// Revalidates one feed, conditionally if it has `validators`. A live feed
+/// comes with the validators it was just served with.
+fn check_feed_health(
+    feed_url: &str,
+    validators: Option<&FeedValidators>,
+    client: &Client,
+    config: &MinerConfig,
+    stats: &RunStats,
+) -> (FeedHealth, Option<FeedValidators>) {
+    match try_fetch_feed_body(feed_url, None, validators, client, config, stats) {
+        Ok(fetched) => match validate_fetched_feed(feed_url, fetched, config, stats) {
+            Some(info) => (
+                FeedHealth::Live,
+                Some(FeedValidators {
+                    etag: info.etag,
+                    last_modified: info.last_modified,
+                }),
+            ),
+            None => (FeedHealth::Dead, None),
+        },
+        Err(FeedFetchError::Status(StatusCode::NOT_MODIFIED)) => (FeedHealth::Unchanged, None),
+        Err(FeedFetchError::Status(StatusCode::NOT_FOUND | StatusCode::GONE)) => {
+            (FeedHealth::Dead, None)
+        }
+        Err(_) => (FeedHealth::Unreachable, None),
+    }
+}
+
+/// Reads an existing OPML file and cleans it up.
+///
+/// Feed outlines (those with an `xmlUrl`) are deduplicated across the whole
+/// tree, keeping the first occurrence; folders left empty by this are
+/// dropped, as are outlines whose `xmlUrl` [`read_opml_file`] could not
+/// make absolute. A missing `type` is inferred from the `xmlUrl` (`atom`
+/// if it looks like an Atom URL, otherwise `rss`), types are lowercased,
+/// and `text` and `title` fill in for each other. Write the result back
+/// with [`write_opml_file`].
+pub fn normalize_opml(path: &Path) -> Result<opml::OPML> {
+    let mut document = read_opml_file(path)?;
+
+    let mut seen = HashSet::new();
+    let outlines = std::mem::take(&mut document.body.outlines);
+    document.body.outlines = normalize_outlines(outlines, &mut seen);
+    Ok(document)
+}
+
+fn normalize_outlines(
+    outlines: Vec<opml::Outline>,
+    seen: &mut HashSet<String>,
+) -> Vec<opml::Outline> {
+    let mut normalized = Vec::new();
+    for mut outline in outlines {
+        let is_folder = !outline.outlines.is_empty();
+        outline.outlines = normalize_outlines(std::mem::take(&mut outline.outlines), seen);
+
+        outline.text = outline.text.trim().to_string();
+        outline.title = outline.title.as_deref().and_then(non_empty);
+        if outline.text.is_empty() {
+            outline.text = outline.title.clone().unwrap_or_default();
+        }
+
+        if let Some(xml_url) = outline.xml_url.as_deref().and_then(non_empty) {
+            if Url::parse(&xml_url).is_err() {
+                continue;
+            }
+            if !seen.insert(xml_url.clone()) {
+                continue;
+            }
+            let feed_type = match outline.r#type.as_deref().and_then(non_empty) {
+                Some(feed_type) => feed_type.to_ascii_lowercase(),
+                None => infer_feed_type_from_url(&xml_url).as_str().to_string(),
+            };
+            if outline.text.is_empty() {
+                outline.text = xml_url.clone();
+            }
+            outline.r#type = Some(feed_type);
+            outline.xml_url = Some(xml_url);
+            outline.html_url = outline.html_url.as_deref().and_then(non_empty);
+        } else if is_folder && outline.outlines.is_empty() {
+            continue;
+        }
+
+        if outline.title.is_none() && !outline.text.is_empty() {
+            outline.title = Some(outline.text.clone());
+        }
+        normalized.push(outline);
+    }
+    normalized
+}
+
+/// Reads and parses the OPML file at `path`.
+///
+/// A relative `xmlUrl`, as hand-edited files sometimes have, is resolved
+/// against its outline's `htmlUrl`. One with no absolute `htmlUrl` to
+/// resolve against is left as written; callers skip it.
+pub fn read_opml_file(path: &Path) -> Result<opml::OPML> {
+    let mut file =
+        fs::File::open(path).context(format!("Failed to read OPML file: {}", path.display()))?;
+    let mut document = opml::OPML::from_reader(&mut file)
+        .context(format!("Invalid OPML file: {}", path.display()))?;
+    resolve_xml_urls(&mut document.body.outlines);
+    Ok(document)
+}
+
+fn resolve_xml_urls(outlines: &mut [opml::Outline]) {
+    for outline in outlines {
+        if let Some(xml_url) = &outline.xml_url
+            && Url::parse(xml_url.trim()).is_err()
+            && let Some(resolved) = absolute_xml_url(xml_url.trim(), outline.html_url.as_deref())
+        {
+            outline.xml_url = Some(resolved);
+        }
+        resolve_xml_urls(&mut outline.outlines);
+    }
+}
+
+/// Returns an outline's `xmlUrl` as an absolute URL, resolving a relative
+/// one against its `htmlUrl`; `None` if there is no absolute URL to
+/// resolve against.
+fn absolute_xml_url(xml_url: &str, html_url: Option<&str>) -> Option<String> {
+    if Url::parse(xml_url).is_ok() {
+        return Some(xml_url.to_string());
+    }
+    let base = Url::parse(html_url?.trim()).ok()?;
+    base.join(xml_url).ok().map(String::from)
+}
+
+/// Outcome of [`prune_opml`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PruneReport {
+    /// Feeds that validated and were kept.
+    pub kept: usize,
+    /// Of the kept feeds, those that answered a conditional request with
+    /// 304 Not Modified.
+    pub unchanged: usize,
+    /// `xmlUrl`s of the feeds removed, in document order.
+    pub removed: Vec<String>,
+    /// `xmlUrl`s of the feeds kept because they could not be checked (a
+    /// timeout, DNS or TLS failure, or an error status other than 404 and
+    /// 410), in document order.
+    pub unreachable: Vec<String>,
+}
+
+/// Revalidates every feed in the OPML file at `path` and rewrites it
+/// without the dead ones: feeds answering 404 or 410, and feeds fetched
+/// fine that don't parse. A feed that can't be reached stays, so a network
+/// outage never empties the file. Folders left empty are dropped; every
+/// other outline, and the file's head, is kept as is.
+///
+/// Feeds are revalidated as by [`check_opml_feeds`], and the `etag` and
+/// `lastModified` attributes are updated from each feed refetched.
+pub fn prune_opml(path: &Path, client: &Client) -> Result<PruneReport> {
+    prune_opml_with_config(path, client, &MinerConfig::default())
+}
+
+pub fn prune_opml_with_config(
+    path: &Path,
+    client: &Client,
+    config: &MinerConfig,
+) -> Result<PruneReport> {
+    let mut document = read_opml_file(path)?;
+    let mut validators = read_feed_validators(path)?;
+
+    let mut urls = HashSet::new();
+    collect_xml_urls(&document.body.outlines, &mut urls);
+    let health = check_feeds(urls, &mut validators, client, config);
+
+    let mut report = PruneReport::default();
+    let outlines = std::mem::take(&mut document.body.outlines);
+    document.body.outlines = prune_outlines(outlines, &health, &mut report);
+    write_opml_file_with_validators(&document, &validators, path, true)?;
+    Ok(report)
+}
+
+fn prune_outlines(
+    outlines: Vec<opml::Outline>,
+    health: &HashMap<String, FeedHealth>,
+    report: &mut PruneReport,
+) -> Vec<opml::Outline> {
+    let mut kept = Vec::new();
+    for mut outline in outlines {
+        let is_folder = !outline.outlines.is_empty();
+        outline.outlines = prune_outlines(std::mem::take(&mut outline.outlines), health, report);
+        if let Some(xml_url) = &outline.xml_url
+            && let Some(health) = health.get(xml_url.trim())
+        {
+            match health {
+                FeedHealth::Live => report.kept += 1,
+                FeedHealth::Unchanged => {
+                    report.kept += 1;
+                    report.unchanged += 1;
+                }
+                FeedHealth::Dead => {
+                    report.removed.push(xml_url.clone());
+                    continue;
+                }
+                FeedHealth::Unreachable => report.unreachable.push(xml_url.clone()),
+            }
+        } else if is_folder && outline.outlines.is_empty() {
+            continue;
+        }
+        kept.push(outline);
+    }
+    kept
+}
+
+/// Problems found by [`validate_opml_file`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpmlReport {
+    /// Every outline in the file, folders included.
+    pub outline_count: usize,
+    /// Feed outlines in the file (those with an `xmlUrl`).
+    pub feed_count: usize,
+    /// `text` of leaf outlines that have no `xmlUrl`.
+    pub missing_xml_url: Vec<String>,
+    /// `xmlUrl`s listed more than once, each reported once.
+    pub duplicate_urls: Vec<String>,
+    /// `(xmlUrl, type)` of feeds whose `type` is neither `rss` nor `atom`.
+    pub unknown_types: Vec<(String, String)>,
+}
+
+impl OpmlReport {
+    /// Whether no problems were found.
+    pub fn is_clean(&self) -> bool {
+        self.missing_xml_url.is_empty()
+            && self.duplicate_urls.is_empty()
+            && self.unknown_types.is_empty()
+    }
+}
+
+/// Lints an OPML file without touching the network: fails if it is not
+/// well-formed OPML, and otherwise reports feeds missing an `xmlUrl`,
+/// duplicate URLs, and unrecognized `type` attributes.
+pub fn validate_opml_file(path: &Path) -> Result<OpmlReport> {
+    let document = read_opml_file(path)?;
+
+    let mut report = OpmlReport::default();
+    let mut seen = HashSet::new();
+    lint_outlines(&document.body.outlines, &mut report, &mut seen);
+    Ok(report)
+}
+
+fn lint_outlines(outlines: &[opml::Outline], report: &mut OpmlReport, seen: &mut HashSet<String>) {
+    for outline in outlines {
+        report.outline_count += 1;
+        match outline.xml_url.as_deref().and_then(non_empty) {
+            Some(xml_url) => {
+                report.feed_count += 1;
+                if let Some(feed_type) = outline.r#type.as_deref().and_then(non_empty)
+                    && !["rss", "atom"].contains(&feed_type.to_ascii_lowercase().as_str())
+                {
+                    report.unknown_types.push((xml_url.clone(), feed_type));
+                }
+                if !seen.insert(xml_url.clone()) && !report.duplicate_urls.contains(&xml_url) {
+                    report.duplicate_urls.push(xml_url);
+                }
+            }
+            None if outline.outlines.is_empty() => {
+                report.missing_xml_url.push(outline.text.clone());
+            }
+            None => {}
+        }
+        lint_outlines(&outline.outlines, report, seen);
+    }
+}
+
+/// Guesses a feed's type from its URL alone.
+fn infer_feed_type_from_url(url: &str) -> FeedType {
+    let url = url.to_ascii_lowercase();
+    if url.ends_with(".atom") || url.contains("atom.xml") || url.contains("/atom") {
+        FeedType::Atom
+    } else {
+        FeedType::Rss
+    }
+}
+
+/// Writes an OPML document to `output_path`, formatted like
+/// [`create_opml_file_with_options`] output.
+pub fn write_opml_file(document: &opml::OPML, output_path: &Path, pretty: bool) -> Result<()> {
+    write_opml_file_with_validators(document, &HashMap::new(), output_path, pretty)
+}
+
+/// [`write_opml_file`], storing `validators` on the outlines whose `xmlUrl`
+/// they are keyed by.
+fn write_opml_file_with_validators(
+    document: &opml::OPML,
+    validators: &HashMap<String, FeedValidators>,
+    output_path: &Path,
+    pretty: bool,
+) -> Result<()> {
+    let mut xml = add_validator_attributes(&document.to_string()?, validators)?;
+    if pretty {
+        xml = pretty_print_xml(&xml)?;
+    }
+    write_file_atomically(output_path, "OPML", |file| file.write_all(xml.as_bytes()))
+}
+
+/// Writes one OPML file per site into `output_dir`, in parallel.
+///
+/// Feeds are grouped by the host (and port, if any) of their `html_url`, and
+/// each group is written to `<host>.opml` with the host sanitized into a safe
+/// file name. The directory is created if missing. Returns the written paths
+/// sorted by host.
+pub fn create_opml_files_by_host(
+    feeds: &[RssFeed],
+    output_dir: &Path,
+    options: &OpmlOptions,
+) -> Result<Vec<PathBuf>> {
+    fs::create_dir_all(output_dir).context(format!(
+        "Failed to create output directory: {}",
+        output_dir.display()
+    ))?;
+
+    let mut groups: BTreeMap<String, Vec<RssFeed>> = BTreeMap::new();
+    for feed in feeds.iter().filter(|feed| opml_accepts(feed, options)) {
+        groups
+            .entry(host_key(&feed.html_url).unwrap_or_else(|| "unknown".to_string()))
+            .or_default()
+            .push(feed.clone());
+    }
+
+    groups
+        .par_iter()
+        .map(|(host, group)| {
+            let path = output_dir.join(format!("{}.opml", sanitize_file_name(host)));
+            create_opml_file_with_options(group, &path, options)?;
+            Ok(path)
+        })
+        .collect()
+}
+
+/// Writes feeds to OPML files whose paths come from `template`, e.g.
+/// `out/{host}/{id}.opml`.
+///
+/// Supported placeholders are `{host}` (of the feed's `html_url`), `{id}`
+/// ([`RssFeed::id`]), `{type}`, `{title}`, and `{folder}`. Each substituted
+/// value is sanitized into a single safe path component; the template's own
+/// text is used as written. Feeds whose paths coincide share one file, so
+/// `{host}.opml` behaves like [`create_opml_files_by_host`]. Missing parent
+/// directories are created. Returns the written paths in sorted order.
+pub fn create_opml_files_from_template(
+    feeds: &[RssFeed],
+    template: &str,
+    options: &OpmlOptions,
+) -> Result<Vec<PathBuf>> {
+    let mut groups: BTreeMap<PathBuf, Vec<RssFeed>> = BTreeMap::new();
+    for feed in feeds.iter().filter(|feed| opml_accepts(feed, options)) {
+        groups
+            .entry(expand_output_template(template, feed)?)
+            .or_default()
+            .push(feed.clone());
+    }
+
+    groups
+        .par_iter()
+        .map(|(path, group)| {
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                fs::create_dir_all(parent).context(format!(
+                    "Failed to create output directory: {}",
+                    parent.display()
+                ))?;
+            }
+            create_opml_file_with_options(group, path, options)?;
+            Ok(path.clone())
+        })
+        .collect()
+}
+
+/// Fills the placeholders of an output path template for one feed.
+/// Unknown or unterminated placeholders are an error.
+pub fn expand_output_template(template: &str, feed: &RssFeed) -> Result<PathBuf> {
+    let mut expanded = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else {
+            bail!("Unterminated placeholder in output template: {}", template);
+        };
+        let value = match &rest[start + 1..start + len] {
+            "host" => host_key(&feed.html_url).unwrap_or_default(),
+            "id" => feed.id(),
+            "type" => feed.feed_type.as_str().to_string(),
+            "title" => feed.title.clone(),
+            "folder" => feed.folder.clone().unwrap_or_default(),
+            other => bail!("Unknown placeholder {{{}}} in output template", other),
+        };
+        expanded.push_str(&sanitize_file_name(&value));
+        rest = &rest[start + len + 1..];
+    }
+    expanded.push_str(rest);
+
+    Ok(PathBuf::from(expanded))
+}
+
+/// Returns `host[:port]` for a URL, used to group feeds by site.
+fn host_key(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    let host = url.host_str()?;
+    Some(match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    })
+}
+
+/// Turns an arbitrary string into a single safe path component.
+fn sanitize_file_name(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let sanitized = sanitized.trim_matches('.');
+
+    if sanitized.is_empty() {
+        "unknown".to_string()
+    } else {
+        sanitized.to_string()
+    }
+}
+
+/// File name used when an OPML output path turns out to be a directory.
+pub const DEFAULT_OPML_FILE_NAME: &str = "feeds.opml";
+
+/// File name used when a JSON output path turns out to be a directory.
+pub const DEFAULT_JSON_FILE_NAME: &str = "feeds.json";
+
+/// File name used when a URL list output path turns out to be a directory.
+pub const DEFAULT_URLS_FILE_NAME: &str = "feeds.txt";
+
+/// File name used when a bookmarks output path turns out to be a directory.
+pub const DEFAULT_BOOKMARKS_FILE_NAME: &str = "feeds.html";
+
+/// File name used when a reading list output path turns out to be a
+/// directory.
+pub const DEFAULT_READING_LIST_FILE_NAME: &str = "reading-list.xml";
+
+/// File name used when a SQLite output path turns out to be a directory.
+#[cfg(feature = "sqlite")]
+pub const DEFAULT_SQLITE_FILE_NAME: &str = "feeds.db";
+
+/// Maps an output path that names an existing directory to `default_name`
+/// inside it; any other path is returned unchanged.
+///
+/// All file writers apply this, so `--output some/dir` writes
+/// `some/dir/feeds.opml` instead of failing.
+pub fn resolve_output_path(output_path: &Path, default_name: &str) -> PathBuf {
+    if output_path.is_dir() {
+        output_path.join(default_name)
+    } else {
+        output_path.to_path_buf()
+    }
+}
+
+/// Creates any missing parent directories of `output_path`.
+///
+/// Used by the CLI's `--mkdir` flag before writing output files.
+pub fn create_parent_dirs(output_path: &Path) -> Result<()> {
+    if let Some(parent) = output_path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent).context(format!(
+            "Failed to create output directory: {}",
+            parent.display()
+        ))?;
+    }
+    Ok(())
+}
+
+/// The hidden temporary file that `path` is written to before being
+/// renamed into place.
+fn temp_path_for(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy();
+    path.with_file_name(format!(".{}.tmp", file_name))
+}
+
+/// Writes `path` via a temporary file in the same directory that is renamed
+/// over it only once `write` and a sync succeed, so a failed write (e.g. a
+/// full disk) never leaves a truncated file in place of a good one.
+fn write_file_atomically<F>(path: &Path, kind: &str, write: F) -> Result<()>
+where
+    F: FnOnce(&mut fs::File) -> io::Result<()>,
+{
+    let temp_path = temp_path_for(path);
+
+    let result = fs::File::create(&temp_path).and_then(|mut file| {
+        write(&mut file)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    });
+
+    let Err(err) = result else {
+        return Ok(());
+    };
+    let _ = fs::remove_file(&temp_path);
+
+    let outcome = if path.exists() {
+        "the existing file was left unchanged"
+    } else {
+        "no file was written"
+    };
+    if err.kind() == io::ErrorKind::StorageFull {
+        bail!(
+            "Disk full while writing {} file {}; {}. Free up space or choose another --output",
+            kind,
+            path.display(),
+            outcome
+        );
+    }
+    Err(err).context(format!(
+        "Failed to write {} file: {} ({})",
+        kind,
+        path.display(),
+        outcome
+    ))
+}
+
+/// Writes feeds as a pretty-printed JSON array, merging duplicate URLs.
+///
+/// Unlike OPML output, JSON includes [`FeedType::Unknown`] feeds so they can
+/// be inspected.
+pub fn create_json_file(feeds: &[RssFeed], output_path: &Path) -> Result<()> {
+    #[derive(Serialize)]
+    struct JsonFeed<'a> {
+        id: String,
+        #[serde(flatten)]
+        feed: &'a RssFeed,
+    }
+
+    let unique = dedupe_feeds(feeds.to_vec());
+    let entries: Vec<JsonFeed> = unique
+        .iter()
+        .map(|feed| JsonFeed {
+            id: feed.id(),
+            feed,
+        })
+        .collect();
+
+    let json = serde_json::to_string_pretty(&entries).context("Failed to serialize feeds")?;
+    let output_path = &resolve_output_path(output_path, DEFAULT_JSON_FILE_NAME);
+    write_file_atomically(output_path, "JSON", |file| file.write_all(json.as_bytes()))
+}
+
+/// Writes feeds into the `feeds` table of a SQLite database, creating the
+/// database and table as needed. Rows are keyed by feed URL, so writing a
+/// feed that is already there updates its row in place.
+///
+/// Categories are stored as a JSON array; enum columns hold the names used
+/// in JSON output.
+#[cfg(feature = "sqlite")]
+pub fn create_sqlite_db(feeds: &[RssFeed], output_path: &Path) -> Result<()> {
+    use rusqlite::params;
+
+    fn name<T: Serialize>(value: &T) -> Option<String> {
+        serde_json::to_value(value)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string))
+    }
+
+    let output_path = &resolve_output_path(output_path, DEFAULT_SQLITE_FILE_NAME);
+    let mut connection = rusqlite::Connection::open(output_path).context(format!(
+        "Failed to open database: {}",
+        output_path.display()
+    ))?;
+    connection
+        .execute_batch(
+            "CREATE TABLE IF NOT EXISTS feeds (
+                url TEXT PRIMARY KEY NOT NULL,
+                id TEXT NOT NULL,
+                raw_url TEXT NOT NULL,
+                title TEXT NOT NULL,
+                html_url TEXT NOT NULL,
+                feed_type TEXT NOT NULL,
+                discovery_method TEXT,
+                categories TEXT NOT NULL,
+                language TEXT,
+                author TEXT,
+                folder TEXT,
+                mixed_content INTEGER NOT NULL,
+                format_version TEXT,
+                source_title TEXT
+            )",
+        )
+        .context("Failed to create feeds table")?;
+
+    let transaction = connection.transaction()?;
+    {
+        let mut upsert = transaction.prepare(
+            "INSERT INTO feeds (url, id, raw_url, title, html_url, feed_type, discovery_method,
+                categories, language, author, folder, mixed_content, format_version, source_title)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+            ON CONFLICT(url) DO UPDATE SET
+                id = excluded.id,
+                raw_url = excluded.raw_url,
+                title = excluded.title,
+                html_url = excluded.html_url,
+                feed_type = excluded.feed_type,
+                discovery_method = excluded.discovery_method,
+                categories = excluded.categories,
+                language = excluded.language,
+                author = excluded.author,
+                folder = excluded.folder,
+                mixed_content = excluded.mixed_content,
+                format_version = excluded.format_version,
+                source_title = excluded.source_title",
+        )?;
+        for feed in dedupe_feeds(feeds.to_vec()) {
+            upsert
+                .execute(params![
+                    feed.url,
+                    feed.id(),
+                    feed.raw_url,
+                    feed.title,
+                    feed.html_url,
+                    feed.feed_type.as_str(),
+                    feed.discovery_method.as_ref().and_then(name),
+                    serde_json::to_string(&feed.categories)?,
+                    feed.language,
+                    feed.author,
+                    feed.folder,
+                    feed.mixed_content,
+                    feed.format_version,
+                    feed.source_title,
+                ])
+                .context(format!("Failed to write feed: {}", feed.url))?;
+        }
+    }
+    transaction.commit().context(format!(
+        "Failed to write database: {}",
+        output_path.display()
+    ))
+}
+
+/// Formats the distinct feed URLs, sorted, one per line with a trailing
+/// newline, for shell pipelines.
+pub fn feeds_to_url_lines(feeds: &[RssFeed]) -> String {
+    let urls: BTreeSet<&str> = feeds.iter().map(|feed| feed.url.as_str()).collect();
+    urls.into_iter().map(|url| format!("{}\n", url)).collect()
+}
+
+/// Writes [`feeds_to_url_lines`] to `output_path`.
+pub fn create_url_list_file(feeds: &[RssFeed], output_path: &Path) -> Result<()> {
+    let lines = feeds_to_url_lines(feeds);
+    let output_path = &resolve_output_path(output_path, DEFAULT_URLS_FILE_NAME);
+    write_file_atomically(output_path, "URL list", |file| {
+        file.write_all(lines.as_bytes())
+    })
+}
+
+/// Formats feeds as a Netscape bookmark file, the HTML format browsers and
+/// some feed readers import bookmarks from, with one bookmark per feed URL.
+///
+/// Duplicate URLs are merged, and feeds with a [`folder`](RssFeed::folder)
+/// are nested under a bookmark folder of that name, ahead of unfiled feeds.
+pub fn feeds_to_bookmarks_html(feeds: &[RssFeed]) -> String {
+    let feeds = dedupe_feeds(feeds.to_vec());
+    let mut folders: Vec<(&str, Vec<&RssFeed>)> = Vec::new();
+    let mut unfiled = Vec::new();
+    for feed in &feeds {
+        match feed.folder.as_deref() {
+            Some(name) => match folders.iter_mut().find(|(folder, _)| *folder == name) {
+                Some((_, members)) => members.push(feed),
+                None => folders.push((name, vec![feed])),
+            },
+            None => unfiled.push(feed),
+        }
+    }
+
+    let bookmark = |feed: &RssFeed, indent: &str| {
+        format!(
+            "{}<DT><A HREF=\"{}\">{}</A>\n",
+            indent,
+            quick_xml::escape::escape(feed.url.as_str()),
+            quick_xml::escape::escape(feed.title.as_str()),
+        )
+    };
+
+    let mut html = String::from(
+        "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+         <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n\
+         <TITLE>RSS Feeds</TITLE>\n\
+         <H1>RSS Feeds</H1>\n\
+         <DL><p>\n",
+    );
+    for (name, members) in folders {
+        html.push_str(&format!(
+            "    <DT><H3>{}</H3>\n    <DL><p>\n",
+            quick_xml::escape::escape(name)
+        ));
+        for feed in members {
+            html.push_str(&bookmark(feed, "        "));
+        }
+        html.push_str("    </DL><p>\n");
+    }
+    for feed in unfiled {
+        html.push_str(&bookmark(feed, "    "));
+    }
+    html.push_str("</DL><p>\n");
+    html
+}
+
+/// Writes [`feeds_to_bookmarks_html`] to `output_path`.
+pub fn create_bookmarks_html(feeds: &[RssFeed], output_path: &Path) -> Result<()> {
+    let html = feeds_to_bookmarks_html(feeds);
+    let output_path = &resolve_output_path(output_path, DEFAULT_BOOKMARKS_FILE_NAME);
+    write_file_atomically(output_path, "bookmarks", |file| {
+        file.write_all(html.as_bytes())
+    })
+}
+
+/// Formats feeds as an RSS 2.0 "reading list" whose items are the feeds
+/// themselves: each item's title and link are the feed's title and URL, its
+/// description is the site URL, and its categories are the feed's.
+///
+/// Duplicate URLs are merged as in [`dedupe_feeds`].
+pub fn feeds_to_reading_list_rss(feeds: &[RssFeed]) -> String {
+    let items: Vec<rss::Item> = dedupe_feeds(feeds.to_vec())
+        .into_iter()
+        .map(|feed| {
+            rss::ItemBuilder::default()
+                .title(Some(feed.title))
+                .link(Some(feed.url.clone()))
+                .description(Some(feed.html_url))
+                .guid(Some(rss::Guid {
+                    value: feed.url,
+                    permalink: false,
+                }))
+                .categories(
+                    feed.categories
+                        .into_iter()
+                        .map(|name| rss::Category { name, domain: None })
+                        .collect::<Vec<_>>(),
+                )
+                .build()
+        })
+        .collect();
+
+    rss::ChannelBuilder::default()
+        .title("RSS Feeds")
+        .description("Feeds discovered by rss-miner")
+        .items(items)
+        .build()
+        .to_string()
+}
+
+/// Writes [`feeds_to_reading_list_rss`] to `output_path`.
+pub fn create_reading_list_rss(feeds: &[RssFeed], output_path: &Path) -> Result<()> {
+    let rss = feeds_to_reading_list_rss(feeds);
+    let output_path = &resolve_output_path(output_path, DEFAULT_READING_LIST_FILE_NAME);
+    write_file_atomically(output_path, "reading list", |file| {
+        file.write_all(rss.as_bytes())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{TcpListener, TcpStream};
+    use tempfile::NamedTempFile;
+
+    const RSS_SAMPLE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0">
+  <channel>
+    <title>Example RSS</title>
+    <link>https://example.com</link>
+    <description>Example feed</description>
+    <item><title>First post</title></item>
+  </channel>
+</rss>"#;
+
+    const ATOM_SAMPLE: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Example Atom</title>
+  <id>urn:uuid:60a76c80-d399-11d9-b93C-0003939e0af6</id>
+  <updated>2024-01-01T00:00:00Z</updated>
+  <entry>
+    <title>First entry</title>
+    <id>urn:uuid:1225c695-cfb8-4ebb-aaaa-80da344efa6a</id>
+    <updated>2024-01-01T00:00:00Z</updated>
+  </entry>
+</feed>"#;
+
+    /// A request captured by [`MockServer`].
+    #[derive(Debug, Clone)]
+    struct MockRequest {
+        method: String,
+        path: String,
+        headers: Vec<(String, String)>,
+    }
+
+    impl MockRequest {
+        fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        }
+    }
+
+    struct MockResponse {
+        status: u16,
+        headers: Vec<(String, String)>,
+        body: Vec<u8>,
+    }
+
+    impl MockResponse {
+        fn new(status: u16, content_type: &str, body: &str) -> Self {
+            MockResponse {
+                status,
+                headers: vec![("Content-Type".to_string(), content_type.to_string())],
+                body: body.as_bytes().to_vec(),
+            }
+        }
+
+        fn not_found() -> Self {
+            MockResponse::new(404, "text/plain", "not found")
+        }
+    }
+
+    /// Minimal HTTP/1.1 server for exercising discovery without the network.
+    struct MockServer {
+        base: String,
+        requests: Arc<Mutex<Vec<MockRequest>>>,
+    }
+
+    impl MockServer {
+        fn start<F>(handler: F) -> Self
+        where
+            F: Fn(&MockRequest) -> MockResponse + Send + Sync + 'static,
+        {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let base = format!("http://{}", listener.local_addr().unwrap());
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let handler = Arc::new(handler);
+
+            let log = Arc::clone(&requests);
+            thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let log = Arc::clone(&log);
+                    let handler = Arc::clone(&handler);
+                    thread::spawn(move || Self::serve(stream, &*handler, &log));
+                }
+            });
+
+            MockServer { base, requests }
+        }
+
+        /// Serves fixed `(path, content_type, body)` routes and 404s everything else.
+        fn with_routes(routes: Vec<(&str, &str, String)>) -> Self {
+            let routes: Vec<(String, String, String)> = routes
+                .into_iter()
+                .map(|(p, t, b)| (p.to_string(), t.to_string(), b))
+                .collect();
+            Self::start(move |req| {
+                routes
+                    .iter()
+                    .find(|(path, _, _)| *path == req.path)
+                    .map(|(_, content_type, body)| MockResponse::new(200, content_type, body))
+                    .unwrap_or_else(MockResponse::not_found)
+            })
+        }
+
+        fn url(&self, path: &str) -> String {
+            format!("{}{}", self.base, path)
+        }
+
+        fn requests(&self) -> Vec<MockRequest> {
+            self.requests.lock().unwrap().clone()
+        }
+
+        fn serve(
+            stream: TcpStream,
+            handler: &dyn Fn(&MockRequest) -> MockResponse,
+            log: &Mutex<Vec<MockRequest>>,
+        ) {
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            if reader.read_line(&mut request_line).is_err() {
+                return;
+            }
+            let mut parts = request_line.split_whitespace();
+            let method = parts.next().unwrap_or_default().to_string();
+            let path = parts.next().unwrap_or_default().to_string();
+
+            let mut headers = Vec::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 || line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    let (name, value) = (name.trim().to_string(), value.trim().to_string());
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.parse().unwrap_or(0);
+                    }
+                    headers.push((name, value));
+                }
+            }
+            let mut body = vec![0; content_length];
+            let _ = reader.read_exact(&mut body);
+
+            let request = MockRequest {
+                method,
+                path,
+                headers,
+            };
+            log.lock().unwrap().push(request.clone());
+            let response = handler(&request);
+
+            let mut out = format!("HTTP/1.1 {} Mock\r\n", response.status);
+            for (name, value) in &response.headers {
+                out.push_str(&format!("{}: {}\r\n", name, value));
+            }
+            out.push_str(&format!(
+                "Content-Length: {}\r\nConnection: close\r\n\r\n",
+                response.body.len()
+            ));
+            let mut stream = stream;
+            let _ = stream.write_all(out.as_bytes());
+            if request.method != "HEAD" {
+                let _ = stream.write_all(&response.body);
+            }
+        }
+    }
+
+    #[test]
+    fn test_read_urls_from_file() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "# Comment line").unwrap();
+        writeln!(temp_file, "https://example.com/path?query=1").unwrap();
+        writeln!(temp_file).unwrap();
+        writeln!(temp_file, "https://test.com/another/path").unwrap();
+        writeln!(temp_file, "https://example.com/dup/path").unwrap();
+        writeln!(temp_file, "  https://trimmed.com/page  ").unwrap();
+
+        let urls = read_urls_from_file(temp_file.path()).unwrap();
+        assert_eq!(urls.len(), 3);
+        assert_eq!(urls[0], "https://example.com");
+        assert_eq!(urls[1], "https://test.com");
+        assert_eq!(urls[2], "https://trimmed.com");
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let (name, value) = parse_header("X-Api-Key:  secret ").unwrap();
+        assert_eq!(name, "X-Api-Key");
+        assert_eq!(value, "secret");
+
+        assert!(parse_header("missing-colon").is_err());
+        assert!(parse_header("Bad Name: value").is_err());
+    }
+
+    #[test]
+    fn test_extra_headers_sent_on_page_and_feed_requests() {
+        let server = MockServer::with_routes(vec![
+            (
+                "/",
+                "text/html",
+                r#"<html><head><link rel="alternate" type="application/rss+xml" href="/feed.xml"></head></html>"#
+                    .to_string(),
+            ),
+            ("/feed.xml", "application/rss+xml", RSS_SAMPLE.to_string()),
+        ]);
+        let config = MinerConfig {
+            extra_headers: vec![("X-Api-Key".to_string(), "secret".to_string())],
+            ..Default::default()
+        };
+        let client = build_client(&config).unwrap();
+
+        let feeds = find_rss_feeds(&server.url("/"), &client).unwrap();
+        assert_eq!(feeds.len(), 1);
+
+        let requests = server.requests();
+        assert!(requests.iter().any(|r| r.path == "/"));
+        assert!(requests.iter().any(|r| r.path == "/feed.xml"));
+        for request in requests {
+            assert_eq!(request.header("X-Api-Key"), Some("secret"));
+        }
+    }
+
+    #[test]
+    fn test_build_client_rejects_invalid_header() {
+        let config = MinerConfig {
+            extra_headers: vec![("X-Bad".to_string(), "line\nbreak".to_string())],
+            ..Default::default()
+        };
+        assert!(build_client(&config).is_err());
+    }
+
+    #[test]
+    fn test_keep_unknown_captures_feed_typed_content() {
+        let server = MockServer::with_routes(vec![
+            (
+                "/",
+                "text/html",
+                r#"<html><head><link rel="alternate" type="application/rss+xml" href="/broken.xml"></head></html>"#
+                    .to_string(),
+            ),
+            ("/broken.xml", "application/rss+xml", "<rss><oops".to_string()),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        let feeds = find_rss_feeds(&server.url("/"), &client).unwrap();
+        assert!(feeds.is_empty());
+
+        let config = MinerConfig {
+            keep_unknown: true,
+            ..Default::default()
+        };
+        let feeds = find_rss_feeds_with_config(&server.url("/"), &client, &config).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].feed_type, FeedType::Unknown);
+        assert_eq!(feeds[0].url, server.url("/broken.xml"));
+    }
+
+    #[test]
+    fn test_unknown_feeds_only_in_json_by_default() {
+        let feeds = vec![
+            RssFeed::new(
+                "Good Feed",
+                "https://example.com/feed.xml",
+                "https://example.com",
+                FeedType::Rss,
+            ),
+            RssFeed::new(
+                "Broken Feed",
+                "https://example.com/broken.xml",
+                "https://example.com",
+                FeedType::Unknown,
+            ),
+        ];
+
+        let opml_file = NamedTempFile::new().unwrap();
+        create_opml_file(&feeds, opml_file.path()).unwrap();
+        let content = fs::read_to_string(opml_file.path()).unwrap();
+        assert!(content.contains("Good Feed"));
+        assert!(!content.contains("Broken Feed"));
+
+        let options = OpmlOptions {
+            include_unknown: true,
+            ..Default::default()
+        };
+        create_opml_file_with_options(&feeds, opml_file.path(), &options).unwrap();
+        let content = fs::read_to_string(opml_file.path()).unwrap();
+        assert!(content.contains("Broken Feed"));
+        assert!(content.contains("type=\"unknown\""));
+
+        let json_file = NamedTempFile::new().unwrap();
+        create_json_file(&feeds, json_file.path()).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(json_file.path()).unwrap()).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 2);
+        assert_eq!(json[1]["feed_type"], "unknown");
+    }
+
+    #[test]
+    fn test_streamed_opml_matches_batch_output() {
+        let feeds = vec![
+            RssFeed::new(
+                "Tom & Jerry's <Feed>",
+                "https://example.com/feed.xml?a=1&b=2",
+                "https://example.com",
+                FeedType::Rss,
+            ),
+            RssFeed::new(
+                "Atom \"Quoted\"",
+                "https://example.org/atom.xml",
+                "https://example.org",
+                FeedType::Atom,
+            ),
+            RssFeed::new(
+                "Duplicate",
+                "https://example.com/feed.xml?a=1&b=2",
+                "https://example.com",
+                FeedType::Rss,
+            ),
+        ];
+
+        for options in [
+            OpmlOptions::default(),
+            OpmlOptions {
+                feed_type_filter: Some(FeedType::Atom),
+                ..Default::default()
+            },
+            OpmlOptions {
+                feed_type_filter: Some(FeedType::Unknown),
+                ..Default::default()
+            },
+        ] {
+            let batch_file = NamedTempFile::new().unwrap();
+            create_opml_file_with_options(&feeds, batch_file.path(), &options).unwrap();
+
+            let stream_file = NamedTempFile::new().unwrap();
+            let mut writer = OpmlStreamWriter::create(stream_file.path(), &options).unwrap();
+            for feed in &feeds {
+                writer.write_feed(feed).unwrap();
+            }
+            writer.finish().unwrap();
+
+            assert_eq!(
+                fs::read(batch_file.path()).unwrap(),
+                fs::read(stream_file.path()).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_interrupted_stream_keeps_previous_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("feeds.opml");
+        fs::write(&path, "previous run").unwrap();
+        let feed = RssFeed::new(
+            "Example",
+            "https://example.com/feed.xml",
+            "https://example.com",
+            FeedType::Rss,
+        );
+
+        let mut writer = OpmlStreamWriter::create(&path, &OpmlOptions::default()).unwrap();
+        writer.write_feed(&feed).unwrap();
+        drop(writer);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "previous run");
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+
+        let mut writer = OpmlStreamWriter::create(&path, &OpmlOptions::default()).unwrap();
+        writer.write_feed(&feed).unwrap();
+        writer.finish().unwrap();
+        let document = opml::OPML::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(document.body.outlines.len(), 1);
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_find_rss_feeds_stream_sends_every_feed() {
+        let server = MockServer::with_routes(vec![
+            ("/", "text/html", "<html><head></head></html>".to_string()),
+            ("/feed", "application/rss+xml", RSS_SAMPLE.to_string()),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let urls = vec![server.url("/"), "http://127.0.0.1:1/".to_string()];
+
+        let (receiver, done) =
+            find_rss_feeds_stream_with_failures(urls, client, MinerConfig::default(), false);
+        let feeds: Vec<RssFeed> = receiver.into_iter().collect();
+
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].url, server.url("/feed"));
+        assert_eq!(done.join().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_find_rss_feeds_stream_delivers_feeds_as_found() {
+        // The slow site holds its page until the fast site's feed arrives
+        let (release, released) = mpsc::channel::<()>();
+        let released = Mutex::new(released);
+        let slow = MockServer::start(move |req| match req.path.as_str() {
+            "/" => {
+                let _ = released
+                    .lock()
+                    .unwrap()
+                    .recv_timeout(Duration::from_secs(10));
+                MockResponse::new(200, "text/html", "<html><head></head></html>")
+            }
+            "/feed" => MockResponse::new(200, "application/rss+xml", RSS_SAMPLE),
+            _ => MockResponse::not_found(),
+        });
+        let fast = MockServer::with_routes(vec![
+            ("/", "text/html", "<html><head></head></html>".to_string()),
+            ("/rss", "application/rss+xml", RSS_SAMPLE.to_string()),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let urls = vec![fast.url("/"), slow.url("/")];
+
+        let receiver = find_rss_feeds_stream(urls, client, MinerConfig::default(), false);
+        let first = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(first.url, fast.url("/rss"));
+
+        release.send(()).unwrap();
+        let rest: Vec<RssFeed> = receiver.into_iter().collect();
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].url, slow.url("/feed"));
+    }
+
+    #[test]
+    fn test_feed_stream_yields_early_then_closes() {
+        let (release, released) = mpsc::channel::<()>();
+        let released = Mutex::new(released);
+        let slow = MockServer::start(move |req| match req.path.as_str() {
+            "/" => {
+                let _ = released
+                    .lock()
+                    .unwrap()
+                    .recv_timeout(Duration::from_secs(10));
+                MockResponse::new(200, "text/html", "<html><head></head></html>")
+            }
+            "/feed" => MockResponse::new(200, "application/rss+xml", RSS_SAMPLE),
+            _ => MockResponse::not_found(),
+        });
+        let fast = MockServer::with_routes(vec![
+            ("/", "text/html", "<html><head></head></html>".to_string()),
+            ("/rss", "application/rss+xml", RSS_SAMPLE.to_string()),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let urls = vec![fast.url("/"), slow.url("/")];
+        let stream = FeedStream::new(find_rss_feeds_stream(
+            urls,
+            client,
+            MinerConfig::default(),
+            false,
+        ));
+
+        // The fast site's feed arrives while the slow page is still held
+        let first = stream.next_feed().unwrap();
+        assert_eq!(first.url, fast.url("/rss"));
+        assert!(slow.requests().iter().all(|req| req.path == "/"));
+
+        release.send(()).unwrap();
+        assert_eq!(stream.next_feed().unwrap().url, slow.url("/feed"));
+        assert!(stream.next_feed().is_none());
+        assert!(stream.next_feed().is_none());
+    }
+
+    #[test]
+    fn test_discovery_method_recorded() {
+        let link_server = MockServer::with_routes(vec![
+            (
+                "/",
+                "text/html",
+                r#"<html><head><link rel="alternate" type="application/rss+xml" href="/posts.xml"></head></html>"#
+                    .to_string(),
+            ),
+            ("/posts.xml", "application/rss+xml", RSS_SAMPLE.to_string()),
+        ]);
+        let path_server = MockServer::with_routes(vec![
+            ("/", "text/html", "<html><head></head></html>".to_string()),
+            ("/rss.xml", "application/rss+xml", RSS_SAMPLE.to_string()),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        let feeds = find_rss_feeds(&link_server.url("/"), &client).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].discovery_method, Some(DiscoveryMethod::Link));
+
+        let feeds = find_rss_feeds(&path_server.url("/"), &client).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].url, path_server.url("/rss.xml"));
+        assert_eq!(feeds[0].discovery_method, Some(DiscoveryMethod::CommonPath));
+
+        let json = serde_json::to_value(&feeds[0]).unwrap();
+        assert_eq!(json["discovery_method"], "common_path");
+    }
+
+    #[test]
+    fn test_head_probe_skips_html_paths() {
+        let server = MockServer::start(|req| match req.path.as_str() {
+            "/" => MockResponse::new(200, "text/html", "<html><head></head></html>"),
+            "/feed" => MockResponse::new(200, "text/html", "<html>not a feed</html>"),
+            "/rss" => MockResponse::new(200, "application/rss+xml", RSS_SAMPLE),
+            _ => MockResponse::not_found(),
+        });
+        let config = MinerConfig {
+            use_head_probe: true,
+            ..Default::default()
+        };
+        let client = build_client(&config).unwrap();
+
+        let feeds = find_rss_feeds_with_config(&server.url("/"), &client, &config).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].url, server.url("/rss"));
+
+        let requests = server.requests();
+        let sent = |method: &str, path: &str| {
+            requests
+                .iter()
+                .any(|r| r.method == method && r.path == path)
+        };
+        assert!(sent("HEAD", "/feed"));
+        assert!(!sent("GET", "/feed"));
+        assert!(sent("HEAD", "/rss"));
+        assert!(sent("GET", "/rss"));
+    }
+
+    #[test]
+    fn test_head_probe_falls_back_to_get_on_405() {
+        let server = MockServer::start(|req| match (req.method.as_str(), req.path.as_str()) {
+            (_, "/") => MockResponse::new(200, "text/html", "<html><head></head></html>"),
+            ("HEAD", _) => MockResponse::new(405, "text/plain", ""),
+            ("GET", "/feed") => MockResponse::new(200, "application/rss+xml", RSS_SAMPLE),
+            _ => MockResponse::not_found(),
+        });
+        let config = MinerConfig {
+            use_head_probe: true,
+            ..Default::default()
+        };
+        let client = build_client(&config).unwrap();
+
+        let feeds = find_rss_feeds_with_config(&server.url("/"), &client, &config).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].url, server.url("/feed"));
+    }
+
+    #[test]
+    fn test_create_parent_dirs_before_write() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_path = temp_dir.path().join("nested/deeper/feeds.opml");
+        let feeds = vec![RssFeed::new(
+            "Test Feed",
+            "https://example.com/feed.xml",
+            "https://example.com",
+            FeedType::Rss,
+        )];
+
+        assert!(create_opml_file(&feeds, &output_path).is_err());
+
+        create_parent_dirs(&output_path).unwrap();
+        create_opml_file(&feeds, &output_path).unwrap();
+
+        assert!(temp_dir.path().join("nested/deeper").is_dir());
+        assert!(
+            fs::read_to_string(&output_path)
+                .unwrap()
+                .contains("Test Feed")
+        );
+
+        // Bare file names have no parent to create.
+        create_parent_dirs(Path::new("feeds.opml")).unwrap();
+    }
+
+    #[test]
+    fn test_find_rss_feeds_chunked_matches_full_run() {
+        let servers: Vec<MockServer> = (0..3)
+            .map(|_| {
+                MockServer::with_routes(vec![
+                    ("/", "text/html", "<html><head></head></html>".to_string()),
+                    ("/feed", "application/rss+xml", RSS_SAMPLE.to_string()),
+                ])
+            })
+            .collect();
+        let mut urls: Vec<String> = servers.iter().map(|server| server.url("/")).collect();
+        urls.push("http://127.0.0.1:1/".to_string());
+        urls.push("http://127.0.0.1:2/".to_string());
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        let mut calls = 0;
+        let mut chunked = Vec::new();
+        find_rss_feeds_chunked(&urls, &client, 2, |feeds| {
+            calls += 1;
+            chunked.extend(feeds.into_iter().map(|feed| feed.url));
+        });
+        assert_eq!(calls, 3);
+
+        let mut full: Vec<String> = find_rss_feeds_parallel(&urls, &client, false)
+            .into_iter()
+            .map(|feed| feed.url)
+            .collect();
+        chunked.sort();
+        full.sort();
+        assert_eq!(chunked.len(), 3);
+        assert_eq!(chunked, full);
+    }
+
+    #[test]
+    fn test_find_rss_feeds_chunked_shares_one_run() {
+        let routes = || {
+            vec![
+                ("/", "text/html", "<html></html>".to_string()),
+                ("/feed", "application/rss+xml", RSS_SAMPLE.to_string()),
+            ]
+        };
+        let page_gets = |server: &MockServer| {
+            server
+                .requests()
+                .iter()
+                .filter(|request| request.path.starts_with("/?page="))
+                .count()
+        };
+        let config = MinerConfig {
+            max_page_fetches: Some(3),
+            ..Default::default()
+        };
+        let client = build_client(&config).unwrap();
+
+        let server = MockServer::with_routes(routes());
+        let urls: Vec<String> = (0..6)
+            .map(|n| server.url(&format!("/?page={}", n)))
+            .collect();
+        find_rss_feeds_parallel_with_config(&urls, &client, &config, false);
+        let full_gets = page_gets(&server);
+
+        let server = MockServer::with_routes(routes());
+        let urls: Vec<String> = (0..6)
+            .map(|n| server.url(&format!("/?page={}", n)))
+            .collect();
+        let mut chunked = Vec::new();
+        find_rss_feeds_chunked_with_config(&urls, &client, &config, 2, |feeds| {
+            chunked.extend(feeds.into_iter().map(|feed| feed.url));
+        });
+
+        assert_eq!(full_gets, 3);
+        assert_eq!(page_gets(&server), full_gets);
+        // Every page probes the same /feed, passed on only once
+        assert_eq!(chunked, vec![server.url("/feed")]);
+    }
+
+    #[test]
+    fn test_build_client_accepting_invalid_certs() {
+        let config = MinerConfig {
+            danger_accept_invalid_certs: true,
+            ..Default::default()
+        };
+        let client = build_client(&config).unwrap();
+
+        let server = MockServer::with_routes(vec![(
+            "/feed.xml",
+            "application/rss+xml",
+            RSS_SAMPLE.to_string(),
+        )]);
+        let response = client.get(server.url("/feed.xml")).send().unwrap();
+        assert!(response.status().is_success());
+    }
+
+    #[test]
+    fn test_dedupe_feeds_merges_metadata() {
+        let mut fallback = RssFeed::new(
+            "example.com",
+            "https://example.com/feed.xml",
+            "https://example.com",
+            FeedType::Rss,
+        );
+        fallback.categories = vec!["tech".to_string()];
+        let mut titled = RssFeed::new(
+            "Example Blog",
+            "https://example.com/feed.xml",
+            "https://example.com/blog",
+            FeedType::Rss,
+        );
+        titled.categories = vec!["rust".to_string(), "tech".to_string()];
+        let other = RssFeed::new(
+            "Other",
+            "https://example.com/other.xml",
+            "https://example.com",
+            FeedType::Atom,
+        );
+        let untitled = RssFeed::new(
+            UNTITLED_FEED,
+            "https://example.com/other.xml",
+            "https://example.com",
+            FeedType::Atom,
+        );
+
+        let deduped = dedupe_feeds(vec![fallback, other, titled, untitled]);
+
+        assert_eq!(deduped.len(), 2);
+        assert_eq!(deduped[0].title, "Example Blog");
+        assert_eq!(deduped[0].html_url, "https://example.com");
+        assert_eq!(deduped[0].categories, vec!["tech", "rust"]);
+        assert_eq!(deduped[1].title, "Other");
+
+        let temp_file = NamedTempFile::new().unwrap();
+        create_opml_file(&deduped, temp_file.path()).unwrap();
+        let content = fs::read_to_string(temp_file.path()).unwrap();
+        assert!(content.contains(r#"category="tech,rust""#));
+    }
+
+    #[test]
+    fn test_merge_feeds_unions_sets() {
+        let mut first_a = RssFeed::new(
+            "a.example.com",
+            "https://a.example.com/feed",
+            "https://a.example.com",
+            FeedType::Rss,
+        );
+        first_a.categories = vec!["news".to_string()];
+        let b = RssFeed::new(
+            "Blog B",
+            "https://b.example.com/feed",
+            "https://b.example.com",
+            FeedType::Atom,
+        );
+        let mut second_a = RssFeed::new(
+            "Blog A",
+            "https://a.example.com/feed",
+            "https://a.example.com",
+            FeedType::Rss,
+        );
+        second_a.categories = vec!["tech".to_string()];
+        second_a.discovery_method = Some(DiscoveryMethod::Link);
+        let c = RssFeed::new(
+            "Blog C",
+            "https://c.example.com/feed",
+            "https://c.example.com",
+            FeedType::Rss,
+        );
+
+        let merged = merge_feeds(vec![vec![first_a, b], vec![second_a, c], vec![]]);
+
+        let urls: Vec<&str> = merged.iter().map(|feed| feed.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://a.example.com/feed",
+                "https://b.example.com/feed",
+                "https://c.example.com/feed"
+            ]
+        );
+        assert_eq!(merged[0].title, "Blog A");
+        assert_eq!(merged[0].categories, vec!["news", "tech"]);
+        assert_eq!(merged[0].discovery_method, Some(DiscoveryMethod::Link));
+    }
+
+    #[test]
+    fn test_feed_id_is_stable_for_normalized_url() {
+        let feed = |url: &str| RssFeed::new("Feed", url, "https://example.com", FeedType::Rss);
+
+        let id = feed("https://example.com/feed.xml").id();
+        assert_eq!(id, "2e86815cbc504197");
+        assert_eq!(id, feed("HTTPS://Example.COM:443/feed.xml#top").id());
+        assert_ne!(id, feed("https://example.com/atom.xml").id());
+        assert_ne!(id, feed("http://example.com/feed.xml").id());
+
+        let json_file = NamedTempFile::new().unwrap();
+        create_json_file(&[feed("https://example.com/feed.xml")], json_file.path()).unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(json_file.path()).unwrap()).unwrap();
+        assert_eq!(json[0]["id"], id);
+        assert_eq!(json[0]["url"], "https://example.com/feed.xml");
+    }
+
+    #[test]
+    fn test_dedupe_similar_titles() {
+        let feed = |title: &str, url: &str, html_url: &str| {
+            RssFeed::new(title, url, html_url, FeedType::Rss)
+        };
+        let feeds = vec![
+            feed("Blog", "https://example.com/feed", "https://example.com"),
+            feed(
+                "Blog Comments",
+                "https://example.com/comments",
+                "https://example.com",
+            ),
+            feed(
+                "Blog - RSS",
+                "https://feeds.example.net/blog",
+                "https://example.com",
+            ),
+            feed(
+                "blog » Atom Feed",
+                "https://example.com/atom",
+                "https://example.com",
+            ),
+            feed(
+                "Blog",
+                "https://other.example.org/feed",
+                "https://other.example.org",
+            ),
+            feed("RSS", "https://example.com/rss", "https://example.com"),
+        ];
+
+        let (kept, removed) = dedupe_similar_titles(feeds);
+        let urls: Vec<&str> = kept.iter().map(|feed| feed.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://example.com/feed",
+                "https://example.com/comments",
+                "https://other.example.org/feed",
+                "https://example.com/rss",
+            ]
+        );
+        let removed: Vec<&str> = removed.iter().map(|feed| feed.url.as_str()).collect();
+        assert_eq!(
+            removed,
+            vec!["https://feeds.example.net/blog", "https://example.com/atom"]
+        );
+    }
+
+    #[test]
+    fn test_collapse_www_duplicates() {
+        let feeds = || {
+            vec![
+                RssFeed::new(
+                    "Example",
+                    "https://www.example.com/feed",
+                    "https://www.example.com",
+                    FeedType::Rss,
+                ),
+                RssFeed::new(
+                    "Other",
+                    "https://www.other.example/feed",
+                    "https://www.other.example",
+                    FeedType::Rss,
+                ),
+                RssFeed::new(
+                    "Example",
+                    "https://example.com/feed",
+                    "https://example.com",
+                    FeedType::Rss,
+                ),
+                RssFeed::new(
+                    "Example Comments",
+                    "https://example.com/comments/feed",
+                    "https://example.com",
+                    FeedType::Rss,
+                ),
+            ]
+        };
+        let urls = |feeds: &[RssFeed]| -> Vec<String> {
+            feeds.iter().map(|feed| feed.url.clone()).collect()
+        };
+
+        let (kept, removed) = collapse_www_duplicates(feeds(), WwwPreference::Bare);
+        assert_eq!(
+            urls(&kept),
+            vec![
+                "https://example.com/feed",
+                "https://www.other.example/feed",
+                "https://example.com/comments/feed",
+            ]
+        );
+        assert_eq!(urls(&removed), vec!["https://example.com/feed"]);
+        assert_eq!(kept[0].html_url, "https://www.example.com");
+
+        let (kept, _) = collapse_www_duplicates(feeds(), WwwPreference::Www);
+        assert_eq!(
+            urls(&kept),
+            vec![
+                "https://www.example.com/feed",
+                "https://www.other.example/feed",
+                "https://example.com/comments/feed",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dedupe_feeds_reports_removed_duplicates() {
+        let feeds = vec![
+            RssFeed::new(
+                "A",
+                "https://a.example.com/feed",
+                "https://a.example.com",
+                FeedType::Rss,
+            ),
+            RssFeed::new(
+                "B",
+                "https://b.example.com/feed",
+                "https://b.example.com",
+                FeedType::Rss,
+            ),
+            RssFeed::new(
+                "A2",
+                "https://a.example.com/feed",
+                "https://a.example.com/x",
+                FeedType::Rss,
+            ),
+            RssFeed::new(
+                "A3",
+                "https://a.example.com/feed",
+                "https://a.example.com/y",
+                FeedType::Rss,
+            ),
+        ];
+
+        let (kept, removed) = dedupe_feeds_with_report(feeds);
+
+        let kept: Vec<&str> = kept.iter().map(|feed| feed.title.as_str()).collect();
+        assert_eq!(kept, vec!["A", "B"]);
+        let removed: Vec<(&str, &str)> = removed
+            .iter()
+            .map(|feed| (feed.title.as_str(), feed.html_url.as_str()))
+            .collect();
+        assert_eq!(
+            removed,
+            vec![
+                ("A2", "https://a.example.com/x"),
+                ("A3", "https://a.example.com/y")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_miner_config_from_toml() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(
+            temp_file,
+            r#"
+timeout = 30
+extra_headers = [["X-Api-Key", "secret"]]
+keep_unknown = true
+"#
+        )
+        .unwrap();
+
+        let config = MinerConfig::from_toml_file(temp_file.path()).unwrap();
+        assert_eq!(config.timeout, Duration::from_secs(30));
+        assert_eq!(
+            config.extra_headers,
+            vec![("X-Api-Key".to_string(), "secret".to_string())]
+        );
+        assert!(config.keep_unknown);
+        assert!(!config.use_head_probe);
+
+        assert!(MinerConfig::from_toml_str("no_such_option = true").is_err());
+        assert!(MinerConfig::from_toml_str(r#"extra_headers = [["Bad Name", "x"]]"#).is_err());
+    }
+
+    #[test]
+    fn test_validate_feed_reports_metadata() {
+        let rss = r#"<?xml version="1.0"?>
+<rss version="2.0">
+  <channel>
+    <title>Health Check</title>
+    <link>https://example.com</link>
+    <description>Feed</description>
+    <lastBuildDate>Mon, 01 Jan 2024 00:00:00 GMT</lastBuildDate>
+    <category>news</category>
+    <category>tech</category>
+    <item><title>One</title></item>
+    <item><title>Two</title></item>
+    <item><title>Three</title></item>
+  </channel>
+</rss>"#;
+        let server =
+            MockServer::with_routes(vec![("/feed.xml", "application/rss+xml", rss.to_string())]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        // feed-rs normalizes dates to RFC 3339; the built-in parsers keep them as written.
+        let last_updated = if cfg!(feature = "feed-rs") {
+            "2024-01-01T00:00:00+00:00"
+        } else {
+            "Mon, 01 Jan 2024 00:00:00 GMT"
+        };
+
+        let info = validate_feed(&server.url("/feed.xml"), &client).unwrap();
+        assert_eq!(info.feed_type, FeedType::Rss);
+        assert_eq!(info.title, "Health Check");
+        assert_eq!(info.item_count, 3);
+        assert_eq!(info.last_updated.as_deref(), Some(last_updated));
+        assert_eq!(info.categories, vec!["news", "tech"]);
+        assert_eq!(info.description.as_deref(), Some("Feed"));
+        assert_eq!(info.language, None);
+        assert_eq!(info.self_link, None);
+
+        let report = format_feed_report(&server.url("/feed.xml"), &info);
+        assert!(report.contains("Type: rss"));
+        assert!(report.contains("Title: Health Check"));
+        assert!(report.contains("Items: 3"));
+        assert!(report.contains(&format!("Last updated: {last_updated}")));
+        assert!(report.contains("Categories: news, tech"));
+        assert!(report.contains("Language: unknown"));
+
+        assert!(validate_feed(&server.url("/missing.xml"), &client).is_none());
+    }
+
+    #[test]
+    fn test_validate_feed_reports_atom_metadata() {
+        let atom = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom" xml:lang="de-de">
+  <title>Atom Check</title>
+  <subtitle>All the news</subtitle>
+  <id>urn:uuid:60a76c80-d399-11d9-b93C-0003939e0af6</id>
+  <updated>2024-02-03T04:05:06Z</updated>
+  <link rel="self" href="https://example.com/atom.xml"/>
+  <category term="science"/>
+  <entry>
+    <title>First entry</title>
+    <id>urn:uuid:1225c695-cfb8-4ebb-aaaa-80da344efa6a</id>
+    <updated>2024-02-03T04:05:06Z</updated>
+  </entry>
+</feed>"#;
+        let server = MockServer::with_routes(vec![(
+            "/atom.xml",
+            "application/atom+xml",
+            atom.to_string(),
+        )]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        let info = validate_feed(&server.url("/atom.xml"), &client).unwrap();
+        assert_eq!(
+            info,
+            FeedInfo {
+                feed_type: FeedType::Atom,
+                title: "Atom Check".to_string(),
+                description: Some("All the news".to_string()),
+                item_count: 1,
+                last_updated: Some("2024-02-03T04:05:06+00:00".to_string()),
+                etag: None,
+                last_modified: None,
+                categories: vec!["science".to_string()],
+                self_link: Some("https://example.com/atom.xml".to_string()),
+                language: Some("de-DE".to_string()),
+                author: None,
+                sample_titles: Vec::new(),
+                moved_to: None,
+                related_feeds: Vec::new(),
+                format_version: Some("Atom 1.0".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_read_feed_urls_keeps_full_paths() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "# Feeds").unwrap();
+        writeln!(temp_file, "https://example.com/feed.xml").unwrap();
+        writeln!(temp_file, "https://example.com/atom.xml").unwrap();
+        writeln!(temp_file, "https://example.com/feed.xml").unwrap();
+
+        let urls = read_feed_urls_from_file(temp_file.path()).unwrap();
+        assert_eq!(
+            urls,
+            vec![
+                "https://example.com/feed.xml",
+                "https://example.com/atom.xml"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_expand_url_ranges() {
+        assert_eq!(
+            expand_url_ranges("https://x/p{1..3}").unwrap(),
+            vec!["https://x/p1", "https://x/p2", "https://x/p3"]
+        );
+        assert_eq!(
+            expand_url_ranges("https://x/{08..10}/{b..c}").unwrap(),
+            vec![
+                "https://x/08/{b..c}",
+                "https://x/09/{b..c}",
+                "https://x/10/{b..c}"
+            ]
+        );
+        assert_eq!(
+            expand_url_ranges("https://x/{2..1}-{1..2}").unwrap(),
+            vec![
+                "https://x/2-1",
+                "https://x/2-2",
+                "https://x/1-1",
+                "https://x/1-2"
+            ]
+        );
+        assert!(expand_url_ranges("https://x/{1..100}/{1..100}/{1..100}").is_err());
+    }
+
+    #[test]
+    fn test_read_feed_urls_range_expansion_is_opt_in() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "https://x/p{{1..3}}").unwrap();
+        writeln!(temp_file, "https://x/q{{id}}").unwrap();
+
+        let urls = read_feed_urls_from_file(temp_file.path()).unwrap();
+        assert_eq!(urls, vec!["https://x/p{1..3}", "https://x/q{id}"]);
+
+        let options = InputOptions {
+            expand_ranges: true,
+            ..Default::default()
+        };
+        let urls = read_feed_urls_from_file_with_options(temp_file.path(), &options).unwrap();
+        assert_eq!(
+            urls,
+            vec![
+                "https://x/p1",
+                "https://x/p2",
+                "https://x/p3",
+                "https://x/q{id}"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_urls_from_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(
+            temp_dir.path().join("a.txt"),
+            "https://a.example.com\nhttps://shared.example.com/one\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("b.txt"),
+            "# more\nhttps://shared.example.com/two\nhttps://b.example.com\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("notes.md"),
+            "https://ignored.example.com\n",
+        )
+        .unwrap();
+        fs::create_dir(temp_dir.path().join("nested")).unwrap();
+        fs::write(
+            temp_dir.path().join("nested/c.txt"),
+            "https://c.example.com\n",
+        )
+        .unwrap();
+
+        let urls = read_urls_from_file(temp_dir.path()).unwrap();
+        assert_eq!(
+            urls,
+            vec![
+                "https://a.example.com",
+                "https://shared.example.com",
+                "https://b.example.com"
+            ]
+        );
+
+        let options = InputOptions {
+            recursive: true,
+            ..Default::default()
+        };
+        let urls = read_urls_from_file_with_options(temp_dir.path(), &options).unwrap();
+        assert_eq!(urls.len(), 4);
+        assert_eq!(urls[3], "https://c.example.com");
+    }
+
+    #[test]
+    fn test_self_link_preferred_over_discovery_url() {
+        let atom = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Canonical</title>
+  <id>urn:uuid:60a76c80-d399-11d9-b93C-0003939e0af6</id>
+  <updated>2024-01-01T00:00:00Z</updated>
+  <link rel="alternate" href="https://example.com/"/>
+  <link rel="self" href="https://example.com/canonical.atom"/>
+</feed>"#;
+        let rss = r#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom">
+  <channel>
+    <title>Canonical RSS</title>
+    <link>https://example.com</link>
+    <description>Feed</description>
+    <atom:link rel="self" type="application/rss+xml" href="https://example.com/canonical.rss"/>
+  </channel>
+</rss>"#;
+        let server = MockServer::with_routes(vec![
+            (
+                "/",
+                "text/html",
+                r#"<html><head>
+                <link rel="alternate" type="application/atom+xml" href="/mirror.atom">
+                <link rel="alternate" type="application/rss+xml" href="/mirror.rss">
+                <link rel="alternate" type="application/rss+xml" href="/plain.xml">
+                </head></html>"#
+                    .to_string(),
+            ),
+            ("/mirror.atom", "application/atom+xml", atom.to_string()),
+            ("/mirror.rss", "application/rss+xml", rss.to_string()),
+            ("/plain.xml", "application/rss+xml", RSS_SAMPLE.to_string()),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        let feeds = find_rss_feeds(&server.url("/"), &client).unwrap();
+        assert_eq!(feeds.len(), 3);
+        assert_eq!(feeds[0].url, "https://example.com/canonical.atom");
+        assert_eq!(feeds[0].raw_url, server.url("/mirror.atom"));
+        assert_eq!(feeds[1].url, "https://example.com/canonical.rss");
+        assert_eq!(feeds[1].raw_url, server.url("/mirror.rss"));
+        assert_eq!(feeds[2].url, server.url("/plain.xml"));
+        assert_eq!(feeds[2].raw_url, server.url("/plain.xml"));
+    }
+
+    #[test]
+    fn test_create_opml_files_by_host() {
+        let feeds = vec![
+            RssFeed::new(
+                "Blog A",
+                "https://a.example.com/feed.xml",
+                "https://a.example.com",
+                FeedType::Rss,
+            ),
+            RssFeed::new(
+                "Blog B",
+                "https://b.example.com/atom.xml",
+                "https://b.example.com:8443",
+                FeedType::Atom,
+            ),
+            RssFeed::new(
+                "Blog A Comments",
+                "https://a.example.com/comments.xml",
+                "https://a.example.com",
+                FeedType::Rss,
+            ),
+        ];
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_dir = temp_dir.path().join("by-host");
+
+        let paths =
+            create_opml_files_by_host(&feeds, &output_dir, &OpmlOptions::default()).unwrap();
+
+        assert_eq!(
+            paths,
+            vec![
+                output_dir.join("a.example.com.opml"),
+                output_dir.join("b.example.com_8443.opml"),
+            ]
+        );
+        let a = fs::read_to_string(&paths[0]).unwrap();
+        assert!(a.contains("Blog A"));
+        assert!(a.contains("Blog A Comments"));
+        assert!(!a.contains("Blog B"));
+        let b = fs::read_to_string(&paths[1]).unwrap();
+        assert!(b.contains("Blog B"));
+        assert!(!b.contains("Blog A"));
+    }
+
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!(sanitize_file_name("example.com:8080"), "example.com_8080");
+        assert_eq!(sanitize_file_name("../../etc/passwd"), "_.._etc_passwd");
+        assert_eq!(sanitize_file_name(".."), "unknown");
+        assert_eq!(sanitize_file_name(""), "unknown");
+    }
+
+    #[test]
+    fn test_create_opml_files_from_template() {
+        let mut feed = RssFeed::new(
+            "News/Updates: Daily",
+            "https://a.example.com/feed.xml",
+            "https://a.example.com:8080",
+            FeedType::Rss,
+        );
+        feed.folder = Some("../secret".to_string());
+
+        assert_eq!(
+            expand_output_template("out/{host}/{type}-{id}.opml", &feed).unwrap(),
+            PathBuf::from(format!("out/a.example.com_8080/rss-{}.opml", feed.id()))
+        );
+        assert_eq!(
+            expand_output_template("{folder}/{title}.opml", &feed).unwrap(),
+            PathBuf::from("_secret/News_Updates__Daily.opml")
+        );
+        assert!(expand_output_template("{nope}.opml", &feed).is_err());
+        assert!(expand_output_template("{host.opml", &feed).is_err());
+
+        let other = RssFeed::new(
+            "Other",
+            "https://b.example.com/atom.xml",
+            "https://b.example.com",
+            FeedType::Atom,
+        );
+        let temp_dir = tempfile::tempdir().unwrap();
+        let template = format!("{}/{{host}}/{{id}}.opml", temp_dir.path().display());
+        let paths = create_opml_files_from_template(
+            &[feed.clone(), other.clone()],
+            &template,
+            &OpmlOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            paths,
+            vec![
+                temp_dir
+                    .path()
+                    .join("a.example.com_8080")
+                    .join(format!("{}.opml", feed.id())),
+                temp_dir
+                    .path()
+                    .join("b.example.com")
+                    .join(format!("{}.opml", other.id())),
+            ]
+        );
+        assert!(fs::read_to_string(&paths[1]).unwrap().contains("Other"));
+    }
+
+    #[test]
+    fn test_compact_opml_is_smaller_and_equivalent() {
+        let feeds = vec![
+            RssFeed::new(
+                "Blog A",
+                "https://a.example.com/feed.xml",
+                "https://a.example.com",
+                FeedType::Rss,
+            ),
+            RssFeed::new(
+                "Blog B",
+                "https://b.example.com/atom.xml",
+                "https://b.example.com",
+                FeedType::Atom,
+            ),
+        ];
+        let temp_dir = tempfile::tempdir().unwrap();
+        let pretty_path = temp_dir.path().join("pretty.opml");
+        let compact_path = temp_dir.path().join("compact.opml");
+
+        create_opml_file_with_options(&feeds, &pretty_path, &OpmlOptions::default()).unwrap();
+        let compact = OpmlOptions {
+            pretty: false,
+            ..Default::default()
+        };
+        create_opml_file_with_options(&feeds, &compact_path, &compact).unwrap();
+
+        let pretty_xml = fs::read_to_string(&pretty_path).unwrap();
+        let compact_xml = fs::read_to_string(&compact_path).unwrap();
+        assert!(compact_xml.len() < pretty_xml.len());
+        assert!(!compact_xml.contains("\n  <"));
+
+        let pretty_opml = opml::OPML::from_str(&pretty_xml).unwrap();
+        let compact_opml = opml::OPML::from_str(&compact_xml).unwrap();
+        assert_eq!(pretty_opml.body.outlines, compact_opml.body.outlines);
+        assert_eq!(compact_opml.body.outlines.len(), 2);
+    }
+
+    #[test]
+    fn test_netnewswire_compat_outlines() {
+        let atom = RssFeed::new(
+            "Atom Blog",
+            "https://a.example.com/atom.xml",
+            "https://a.example.com",
+            FeedType::Atom,
+        );
+        let orphan = RssFeed::new(
+            "Orphan",
+            "https://b.example.com/feeds/rss.xml",
+            "",
+            FeedType::Rss,
+        );
+
+        let standard = feed_outline(&atom, ReaderCompat::Standard);
+        assert_eq!(standard.r#type.as_deref(), Some("atom"));
+
+        let outline = feed_outline(&atom, ReaderCompat::NetNewsWire);
+        assert_eq!(outline.r#type.as_deref(), Some("rss"));
+        assert_eq!(outline.html_url.as_deref(), Some("https://a.example.com"));
+        assert_eq!(
+            outline.xml_url.as_deref(),
+            Some("https://a.example.com/atom.xml")
+        );
+
+        let outline = feed_outline(&orphan, ReaderCompat::NetNewsWire);
+        assert_eq!(outline.html_url.as_deref(), Some("https://b.example.com"));
+    }
+
+    #[test]
+    fn test_outline_carries_feed_format_version() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/rss.xml" => MockResponse::new(200, "application/rss+xml", RSS_SAMPLE),
+            "/atom.xml" => MockResponse::new(200, "application/atom+xml", ATOM_SAMPLE),
+            _ => MockResponse::not_found(),
+        });
+        let config = MinerConfig::default();
+        let client = build_client(&config).unwrap();
+        let stats = RunStats::default();
+
+        for (path, feed_type, expected) in [
+            ("/rss.xml", FeedType::Rss, "RSS 2.0"),
+            ("/atom.xml", FeedType::Atom, "Atom 1.0"),
+        ] {
+            let feed_url = server.url(path);
+            let info = validate_rss_feed(&feed_url, None, &client, &config, &stats).unwrap();
+            assert_eq!(info.format_version.as_deref(), Some(expected));
+
+            let mut feed = RssFeed::new("Example", &feed_url, &server.base, feed_type);
+            feed.format_version = info.format_version;
+            let outline = feed_outline(&feed, ReaderCompat::Standard);
+            assert_eq!(outline.version.as_deref(), Some(expected));
+        }
+
+        assert_eq!(
+            feed_format_version(
+                br#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"/>"#
+            )
+            .as_deref(),
+            Some("RSS 1.0")
+        );
+    }
+
+    #[test]
+    fn test_normalize_opml() {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(
+            file,
+            r#"<?xml version="1.0"?>
+<opml version="2.0">
+  <head><title>Messy</title></head>
+  <body>
+    <outline text="Tech">
+      <outline text="Blog A" type="RSS" xmlUrl="https://a.example.com/feed.xml"/>
+      <outline text="" title="Blog B" xmlUrl="https://b.example.com/atom.xml"/>
+    </outline>
+    <outline text="Dupes">
+      <outline text="Blog A again" xmlUrl="https://a.example.com/feed.xml"/>
+    </outline>
+    <outline text="Blog C" xmlUrl=" https://c.example.com/index.xml "/>
+  </body>
+</opml>"#
+        )
+        .unwrap();
+
+        let document = normalize_opml(file.path()).unwrap();
+        let outlines = &document.body.outlines;
+
+        assert_eq!(outlines.len(), 2);
+        let tech = &outlines[0];
+        assert_eq!(tech.title.as_deref(), Some("Tech"));
+        assert_eq!(tech.outlines[0].r#type.as_deref(), Some("rss"));
+        assert_eq!(tech.outlines[1].text, "Blog B");
+        assert_eq!(tech.outlines[1].r#type.as_deref(), Some("atom"));
+        assert_eq!(outlines[1].text, "Blog C");
+        assert_eq!(outlines[1].r#type.as_deref(), Some("rss"));
+        assert_eq!(
+            outlines[1].xml_url.as_deref(),
+            Some("https://c.example.com/index.xml")
+        );
+
+        let output = NamedTempFile::new().unwrap();
+        write_opml_file(&document, output.path(), true).unwrap();
+        let reread = opml::OPML::from_str(&fs::read_to_string(output.path()).unwrap()).unwrap();
+        assert_eq!(reread.body.outlines, document.body.outlines);
+    }
+
+    #[test]
+    fn test_normalize_opml_resolves_relative_xml_urls() {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(
+            file,
+            r#"<?xml version="1.0"?>
+<opml version="2.0">
+  <head><title>Hand-edited</title></head>
+  <body>
+    <outline text="Blog A" xmlUrl="/feed.xml" htmlUrl="https://a.example.com/blog/"/>
+    <outline text="Blog B" xmlUrl="atom.xml" htmlUrl="https://b.example.com/news/"/>
+    <outline text="Blog C" xmlUrl="//c.example.com/rss" htmlUrl="https://c.example.com"/>
+    <outline text="No site" xmlUrl="/feed.xml"/>
+    <outline text="Relative site" xmlUrl="/feed.xml" htmlUrl="/blog"/>
+  </body>
+</opml>"#
+        )
+        .unwrap();
+
+        let document = normalize_opml(file.path()).unwrap();
+        let xml_urls: Vec<&str> = document
+            .body
+            .outlines
+            .iter()
+            .filter_map(|outline| outline.xml_url.as_deref())
+            .collect();
+        assert_eq!(
+            xml_urls,
+            vec![
+                "https://a.example.com/feed.xml",
+                "https://b.example.com/news/atom.xml",
+                "https://c.example.com/rss",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prune_and_baseline_resolve_relative_xml_urls() {
+        let server = MockServer::with_routes(vec![(
+            "/live.xml",
+            "application/rss+xml",
+            RSS_SAMPLE.to_string(),
+        )]);
+        let file = NamedTempFile::new().unwrap();
+        fs::write(
+            file.path(),
+            format!(
+                r#"<?xml version="1.0"?>
+<opml version="2.0">
+  <body>
+    <outline text="Live" type="rss" xmlUrl="/live.xml" htmlUrl="{0}/blog/"/>
+    <outline text="Gone" type="rss" xmlUrl="gone.xml" htmlUrl="{0}/blog/"/>
+    <outline text="No site" type="rss" xmlUrl="/orphan.xml"/>
+  </body>
+</opml>"#,
+                server.base
+            ),
+        )
+        .unwrap();
+
+        let live = RssFeed::new(
+            "Live",
+            server.url("/live.xml"),
+            server.url("/blog/"),
+            FeedType::Rss,
+        );
+        let fresh = RssFeed::new(
+            "Fresh",
+            "https://fresh.example.com/feed",
+            "https://fresh.example.com",
+            FeedType::Rss,
+        );
+        let remaining = exclude_baseline_feeds(vec![live, fresh], file.path()).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].title, "Fresh");
+
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let report = prune_opml(file.path(), &client).unwrap();
+        assert_eq!(report.kept, 1);
+        assert_eq!(report.removed, vec![server.url("/blog/gone.xml")]);
+        assert!(
+            !server
+                .requests()
+                .iter()
+                .any(|request| request.path == "/orphan.xml")
+        );
+
+        let document = read_opml_file(file.path()).unwrap();
+        let xml_urls: Vec<&str> = document
+            .body
+            .outlines
+            .iter()
+            .filter_map(|outline| outline.xml_url.as_deref())
+            .collect();
+        assert_eq!(
+            xml_urls,
+            vec![server.url("/live.xml").as_str(), "/orphan.xml"]
+        );
+    }
+
+    #[test]
+    fn test_append_to_opml_file_preserves_structure() {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(
+            file,
+            r#"<?xml version="1.0"?>
+<opml version="2.0">
+  <head><title>Curated</title></head>
+  <body>
+    <outline text="Tech" title="Tech">
+      <outline text="Hand-named A" type="rss" xmlUrl="https://a.example.com/feed.xml"/>
+    </outline>
+    <outline text="Misc" type="rss" xmlUrl="https://m.example.com/rss"/>
+  </body>
+</opml>"#
+        )
+        .unwrap();
+
+        let mut known = RssFeed::new(
+            "Blog A",
+            "https://a.example.com/feed.xml",
+            "https://a.example.com",
+            FeedType::Rss,
+        );
+        known.folder = Some("Other".to_string());
+        let mut filed = RssFeed::new(
+            "Blog B",
+            "https://b.example.com/atom.xml",
+            "https://b.example.com",
+            FeedType::Atom,
+        );
+        filed.folder = Some("Tech".to_string());
+        let unfiled = RssFeed::new(
+            "Blog C",
+            "https://c.example.com/feed",
+            "https://c.example.com",
+            FeedType::Rss,
+        );
+
+        let added = append_to_opml_file(
+            &[known, filed, unfiled],
+            file.path(),
+            &OpmlOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(added, 2);
+        let document = opml::OPML::from_str(&fs::read_to_string(file.path()).unwrap()).unwrap();
+        assert_eq!(document.head.unwrap().title.as_deref(), Some("Curated"));
+        let outlines = &document.body.outlines;
+        assert_eq!(outlines.len(), 3);
+        assert_eq!(outlines[0].text, "Tech");
+        assert_eq!(outlines[0].outlines.len(), 2);
+        assert_eq!(outlines[0].outlines[0].text, "Hand-named A");
+        assert_eq!(outlines[0].outlines[1].text, "Blog B");
+        assert_eq!(outlines[1].text, "Misc");
+        assert_eq!(outlines[2].text, "Blog C");
+    }
+
+    #[test]
+    fn test_validate_opml_file_flags_problems() {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(
+            file,
+            r#"<?xml version="1.0"?>
+<opml version="2.0">
+  <head><title>Hand-edited</title></head>
+  <body>
+    <outline text="Tech">
+      <outline text="Blog A" type="rss" xmlUrl="https://a.example.com/feed.xml"/>
+      <outline text="No URL" type="rss"/>
+    </outline>
+    <outline text="Blog A again" type="RSS" xmlUrl="https://a.example.com/feed.xml"/>
+    <outline text="Podcast" type="podcast" xmlUrl="https://p.example.com/feed"/>
+  </body>
+</opml>"#
+        )
+        .unwrap();
+
+        let report = validate_opml_file(file.path()).unwrap();
+
+        assert_eq!(report.outline_count, 5);
+        assert_eq!(report.feed_count, 3);
+        assert_eq!(report.missing_xml_url, ["No URL"]);
+        assert_eq!(report.duplicate_urls, ["https://a.example.com/feed.xml"]);
+        assert_eq!(
+            report.unknown_types,
+            [(
+                "https://p.example.com/feed".to_string(),
+                "podcast".to_string()
+            )]
+        );
+        assert!(!report.is_clean());
+
+        let mut broken = NamedTempFile::new().unwrap();
+        write!(broken, "<opml><body><outline").unwrap();
+        assert!(validate_opml_file(broken.path()).is_err());
+    }
+
+    #[test]
+    fn test_feeds_to_url_lines() {
+        let feeds = vec![
+            RssFeed::new(
+                "B",
+                "https://b.example.com/feed",
+                "https://b.example.com",
+                FeedType::Rss,
+            ),
+            RssFeed::new(
+                "A",
+                "https://a.example.com/atom.xml",
+                "https://a.example.com",
+                FeedType::Atom,
+            ),
+            RssFeed::new(
+                "B again",
+                "https://b.example.com/feed",
+                "https://b.example.com/blog",
+                FeedType::Rss,
+            ),
+        ];
+
+        assert_eq!(
+            feeds_to_url_lines(&feeds),
+            "https://a.example.com/atom.xml\nhttps://b.example.com/feed\n"
+        );
+        assert_eq!(feeds_to_url_lines(&[]), "");
+    }
+
+    #[test]
+    fn test_prune_opml_removes_dead_feeds() {
+        let server = MockServer::with_routes(vec![
+            ("/live.xml", "application/rss+xml", RSS_SAMPLE.to_string()),
+            ("/atom.xml", "application/atom+xml", ATOM_SAMPLE.to_string()),
+            ("/page", "text/html", "<html></html>".to_string()),
+        ]);
+        let file = NamedTempFile::new().unwrap();
+        fs::write(
+            file.path(),
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<opml version="2.0">
+  <head><title>My Feeds</title></head>
+  <body>
+    <outline text="Live" type="rss" xmlUrl="{0}/live.xml"/>
+    <outline text="Gone" type="rss" xmlUrl="{0}/gone.xml"/>
+    <outline text="News">
+      <outline text="Atom" type="atom" xmlUrl="{0}/atom.xml"/>
+      <outline text="Not a feed" type="rss" xmlUrl="{0}/page"/>
+    </outline>
+    <outline text="Dead folder">
+      <outline text="Gone too" type="rss" xmlUrl="{0}/gone.xml"/>
+    </outline>
+  </body>
+</opml>"#,
+                server.base
+            ),
+        )
+        .unwrap();
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        let report = prune_opml(file.path(), &client).unwrap();
+        assert_eq!(report.kept, 2);
+        assert_eq!(
+            report.removed,
+            vec![
+                server.url("/gone.xml"),
+                server.url("/page"),
+                server.url("/gone.xml")
+            ]
+        );
+
+        let document = opml::OPML::from_str(&fs::read_to_string(file.path()).unwrap()).unwrap();
+        assert_eq!(document.head.unwrap().title.as_deref(), Some("My Feeds"));
+        let outlines = &document.body.outlines;
+        assert_eq!(outlines.len(), 2);
+        assert_eq!(outlines[0].xml_url, Some(server.url("/live.xml")));
+        assert_eq!(outlines[1].text, "News");
+        assert_eq!(outlines[1].outlines.len(), 1);
+        assert_eq!(
+            outlines[1].outlines[0].xml_url,
+            Some(server.url("/atom.xml"))
+        );
+    }
+
+    #[test]
+    fn test_prune_opml_keeps_unreachable_feeds() {
+        let server = MockServer::start(|req| match req.path.as_str() {
+            "/live.xml" => MockResponse::new(200, "application/rss+xml", RSS_SAMPLE),
+            "/gone.xml" => MockResponse::new(410, "text/plain", "gone"),
+            _ => MockResponse::new(500, "text/plain", "oops"),
+        });
+        let file = NamedTempFile::new().unwrap();
+        fs::write(
+            file.path(),
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<opml version="2.0">
+  <body>
+    <outline text="Live" type="rss" xmlUrl="{0}/live.xml"/>
+    <outline text="Offline" type="rss" xmlUrl="http://127.0.0.1:1/feed.xml"/>
+    <outline text="Broken" type="rss" xmlUrl="{0}/broken.xml"/>
+    <outline text="Gone" type="rss" xmlUrl="{0}/gone.xml"/>
+  </body>
+</opml>"#,
+                server.base
+            ),
+        )
+        .unwrap();
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        let report = prune_opml(file.path(), &client).unwrap();
+        assert_eq!(report.kept, 1);
+        assert_eq!(report.removed, vec![server.url("/gone.xml")]);
+        assert_eq!(
+            report.unreachable,
+            vec![
+                "http://127.0.0.1:1/feed.xml".to_string(),
+                server.url("/broken.xml")
+            ]
+        );
+
+        let document = read_opml_file(file.path()).unwrap();
+        let texts: Vec<&str> = document
+            .body
+            .outlines
+            .iter()
+            .map(|outline| outline.text.as_str())
+            .collect();
+        assert_eq!(texts, vec!["Live", "Offline", "Broken"]);
+    }
+
+    #[test]
+    fn test_prune_opml_revalidates_conditionally() {
+        let server = MockServer::start(|req| {
+            if req.header("if-none-match") == Some("\"v1\"") {
+                return MockResponse::new(304, "application/rss+xml", "");
+            }
+            let mut response = MockResponse::new(200, "application/rss+xml", RSS_SAMPLE);
+            if req.path == "/fresh.xml" {
+                response
+                    .headers
+                    .push(("ETag".to_string(), "\"v2\"".to_string()));
+            }
+            response
+        });
+        let file = NamedTempFile::new().unwrap();
+        fs::write(
+            file.path(),
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<opml version="2.0">
+  <body>
+    <outline text="Cached" type="rss" xmlUrl="{0}/cached.xml" etag="&quot;v1&quot;"/>
+    <outline text="Fresh" type="rss" xmlUrl="{0}/fresh.xml"/>
+    <outline text="Stale" type="rss" xmlUrl="{0}/stale.xml" etag="&quot;v0&quot;"/>
+  </body>
+</opml>"#,
+                server.base
+            ),
+        )
+        .unwrap();
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        let report = prune_opml(file.path(), &client).unwrap();
+        assert_eq!(report.kept, 3);
+        assert_eq!(report.unchanged, 1);
+        assert!(report.removed.is_empty());
+
+        let requests = server.requests();
+        let cached = requests
+            .iter()
+            .find(|request| request.path == "/cached.xml")
+            .unwrap();
+        assert_eq!(cached.header("if-none-match"), Some("\"v1\""));
+        let fresh = requests
+            .iter()
+            .find(|request| request.path == "/fresh.xml")
+            .unwrap();
+        assert_eq!(fresh.header("if-none-match"), None);
+
+        // Validators are refreshed from each refetch, and dropped when the
+        // feed no longer sends any
+        let validators = read_feed_validators(file.path()).unwrap();
+        assert_eq!(
+            validators[&server.url("/cached.xml")].etag.as_deref(),
+            Some("\"v1\"")
+        );
+        assert_eq!(
+            validators[&server.url("/fresh.xml")].etag.as_deref(),
+            Some("\"v2\"")
+        );
+        assert!(!validators.contains_key(&server.url("/stale.xml")));
+    }
+
+    #[test]
+    fn test_create_reading_list_rss() {
+        let mut tagged = RssFeed::new(
+            "Tech & Co",
+            "https://a.example.com/feed?x=1&y=2",
+            "https://a.example.com",
+            FeedType::Rss,
+        );
+        tagged.categories = vec!["tech".to_string()];
+        let feeds = vec![
+            tagged.clone(),
+            RssFeed::new(
+                "Blog",
+                "https://b.example.com/atom.xml",
+                "https://b.example.com",
+                FeedType::Atom,
+            ),
+            tagged,
+        ];
+        let temp_dir = tempfile::tempdir().unwrap();
+        create_reading_list_rss(&feeds, temp_dir.path()).unwrap();
+
+        let xml = fs::read(temp_dir.path().join(DEFAULT_READING_LIST_FILE_NAME)).unwrap();
+        let channel = rss::Channel::read_from(&xml[..]).unwrap();
+        assert_eq!(channel.title(), "RSS Feeds");
+        let items: Vec<(Option<&str>, Option<&str>)> = channel
+            .items()
+            .iter()
+            .map(|item| (item.title(), item.link()))
+            .collect();
+        assert_eq!(
+            items,
+            vec![
+                (
+                    Some("Tech & Co"),
+                    Some("https://a.example.com/feed?x=1&y=2")
+                ),
+                (Some("Blog"), Some("https://b.example.com/atom.xml")),
+            ]
+        );
+        let first = &channel.items()[0];
+        assert_eq!(first.description(), Some("https://a.example.com"));
+        assert_eq!(first.categories()[0].name(), "tech");
+        assert_eq!(
+            first.guid().map(|guid| guid.value()),
+            Some("https://a.example.com/feed?x=1&y=2")
+        );
+    }
+
+    #[test]
+    fn test_create_bookmarks_html() {
+        let mut filed = RssFeed::new(
+            "Tech & Co",
+            "https://a.example.com/feed?x=1&y=2",
+            "https://a.example.com",
+            FeedType::Rss,
+        );
+        filed.folder = Some("News".to_string());
+        let feeds = vec![
+            filed,
+            RssFeed::new(
+                "Blog",
+                "https://b.example.com/atom.xml",
+                "https://b.example.com",
+                FeedType::Atom,
+            ),
+        ];
+        let temp_dir = tempfile::tempdir().unwrap();
+        create_bookmarks_html(&feeds, temp_dir.path()).unwrap();
+
+        let html = fs::read_to_string(temp_dir.path().join(DEFAULT_BOOKMARKS_FILE_NAME)).unwrap();
+        assert!(html.starts_with("<!DOCTYPE NETSCAPE-Bookmark-file-1>"));
+        assert!(
+            html.contains(r#"<A HREF="https://a.example.com/feed?x=1&amp;y=2">Tech &amp; Co</A>"#)
+        );
+        assert!(html.contains(r#"<A HREF="https://b.example.com/atom.xml">Blog</A>"#));
+
+        let document = Html::parse_document(&html);
+        let top = Selector::parse("dl").unwrap();
+        let root = document.select(&top).next().unwrap();
+        let folder = Selector::parse("dt > h3").unwrap();
+        assert_eq!(
+            root.select(&folder)
+                .next()
+                .unwrap()
+                .text()
+                .collect::<String>(),
+            "News"
+        );
+        let links = Selector::parse("dt > a").unwrap();
+        let hrefs: Vec<&str> = root
+            .select(&links)
+            .filter_map(|link| link.value().attr("href"))
+            .collect();
+        assert_eq!(
+            hrefs,
+            vec![
+                "https://a.example.com/feed?x=1&y=2",
+                "https://b.example.com/atom.xml"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_create_opml_file_with_custom_outline() {
+        let feeds = vec![RssFeed::new(
+            "Blog",
+            "https://example.com/feed",
+            "https://example.com",
+            FeedType::Rss,
+        )];
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("custom.opml");
+
+        create_opml_file_with(&feeds, &path, &OpmlOptions::default(), |feed| {
+            opml::Outline {
+                description: Some(format!("Found at {}", feed.html_url)),
+                ..feed_outline(feed, ReaderCompat::Standard)
+            }
+        })
+        .unwrap();
+
+        let document = opml::OPML::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let outline = &document.body.outlines[0];
+        assert_eq!(
+            outline.description.as_deref(),
+            Some("Found at https://example.com")
+        );
+        assert_eq!(outline.xml_url.as_deref(), Some("https://example.com/feed"));
+
+        let default_path = temp_dir.path().join("default.opml");
+        create_opml_file_with_options(&feeds, &default_path, &OpmlOptions::default()).unwrap();
+        let default = opml::OPML::from_str(&fs::read_to_string(&default_path).unwrap()).unwrap();
+        assert_eq!(default.body.outlines[0].description, None);
+    }
+
+    #[test]
+    fn test_exclude_baseline_feeds() {
+        let mut filed = RssFeed::new(
+            "Old",
+            "https://old.example.com/feed",
+            "https://old.example.com",
+            FeedType::Rss,
+        );
+        filed.folder = Some("News".to_string());
+        let temp_dir = tempfile::tempdir().unwrap();
+        let baseline = temp_dir.path().join("previous.opml");
+        create_opml_file(&[filed], &baseline).unwrap();
+
+        let mut moved = RssFeed::new(
+            "Moved",
+            "https://moved.example.com/feed",
+            "https://moved.example.com",
+            FeedType::Atom,
+        );
+        moved.raw_url = "https://old.example.com/feed".to_string();
+        let feeds = vec![
+            RssFeed::new(
+                "Old again",
+                "https://old.example.com/feed",
+                "https://old.example.com/blog",
+                FeedType::Rss,
+            ),
+            RssFeed::new(
+                "New",
+                "https://new.example.com/feed",
+                "https://new.example.com",
+                FeedType::Rss,
+            ),
+            moved,
+        ];
+
+        let fresh = exclude_baseline_feeds(feeds, &baseline).unwrap();
+        let urls: Vec<&str> = fresh.iter().map(|feed| feed.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://new.example.com/feed"]);
+        assert!(exclude_baseline_feeds(fresh, &temp_dir.path().join("missing.opml")).is_err());
+    }
+
+    #[test]
+    fn test_transform_titles_in_order() {
+        let mut feeds = vec![
+            RssFeed::new(
+                "MySite » RSS Feed",
+                "https://example.com/feed",
+                "https://example.com",
+                FeedType::Rss,
+            ),
+            RssFeed::new(
+                "Other Blog",
+                "https://other.example.com/feed",
+                "https://other.example.com",
+                FeedType::Rss,
+            ),
+        ];
+        let transforms = vec![
+            TitleTransform::parse("s/»\\s*RSS Feed$//").unwrap(),
+            TitleTransform::parse("s|^(\\w+)Site$|$1 Website|").unwrap(),
+            TitleTransform::parse(r"s/ Website/\/Web/").unwrap(),
+        ];
+
+        transform_titles(&mut feeds, &transforms);
+        assert_eq!(feeds[0].title, "My/Web");
+        assert_eq!(feeds[1].title, "Other Blog");
+    }
+
+    #[test]
+    fn test_normalize_title() {
+        assert_eq!(
+            normalize_title("  Example\n\t  Blog \r\n News  ", 0),
+            "Example Blog News"
+        );
+        assert_eq!(normalize_title("Short title", 11), "Short title");
+        assert_eq!(normalize_title("A rather long title", 10), "A rather…");
+        assert_eq!(normalize_title("Ünïcödé títlé", 6), "Ünïcö…");
+
+        let mut feeds = vec![RssFeed::new(
+            "\n  Padded   Feed \n",
+            "https://example.com/feed",
+            "https://example.com",
+            FeedType::Rss,
+        )];
+        normalize_titles(&mut feeds, DEFAULT_MAX_TITLE_LENGTH);
+        assert_eq!(feeds[0].title, "Padded Feed");
+    }
+
+    #[test]
+    fn test_title_transform_rejects_invalid_expressions() {
+        assert!(TitleTransform::parse("s/(unclosed/x/").is_err());
+        assert!(TitleTransform::parse("s/missing-replacement").is_err());
+        assert!(TitleTransform::parse("y/a/b/").is_err());
+        assert!(TitleTransform::parse("s/a/b/c/").is_err());
+        assert!(TitleTransform::parse("s").is_err());
+    }
+
+    #[test]
+    fn test_latency_histogram_percentiles() {
+        let mut histogram = LatencyHistogram::default();
+        assert_eq!(histogram.percentile(50.0), None);
+
+        for millis in 1..=100 {
+            histogram.record(Duration::from_millis(millis));
+        }
+        assert_eq!(histogram.count(), 100);
+        // 50th sample (50ms) falls in (32, 64]; 90th and 99th in (64, 128].
+        assert_eq!(histogram.percentile(50.0), Some(Duration::from_millis(64)));
+        assert_eq!(histogram.percentile(90.0), Some(Duration::from_millis(128)));
+        assert_eq!(histogram.percentile(99.0), Some(Duration::from_millis(128)));
+        assert_eq!(histogram.percentile(0.0), Some(Duration::from_millis(1)));
+
+        let mut fast = LatencyHistogram::default();
+        for _ in 0..10 {
+            fast.record(Duration::from_micros(300));
+        }
+        fast.record(Duration::from_secs(3));
+        assert_eq!(fast.percentile(90.0), Some(Duration::from_millis(1)));
+        assert_eq!(fast.percentile(99.0), Some(Duration::from_millis(4096)));
+        assert!(fast.summary().contains("p50 <= 1ms"));
+    }
+
+    #[test]
+    fn test_parallel_report_records_latencies() {
+        let server = MockServer::with_routes(vec![
+            (
+                "/",
+                "text/html",
+                r#"<html><head><link rel="alternate" type="application/rss+xml" href="/feed.xml"></head></html>"#
+                    .to_string(),
+            ),
+            ("/feed.xml", "application/rss+xml", RSS_SAMPLE.to_string()),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let urls = vec![server.url("/"), "http://127.0.0.1:1/".to_string()];
+
+        let report = find_rss_feeds_parallel_report(&urls, &client, &MinerConfig::default(), false);
+
+        assert_eq!(report.feeds.len(), 1);
+        assert_eq!(report.urls_processed, 2);
+        assert_eq!(report.urls_failed, 1);
+        assert_eq!(report.page_latency.count(), 2);
+        assert_eq!(report.validation_latency.count(), 1);
+    }
+
+    #[test]
+    fn test_json_input_titles_propagate_to_output() {
+        let server = MockServer::with_routes(vec![
+            (
+                "/",
+                "text/html",
+                r#"<html><head>
+                <link rel="alternate" type="application/rss+xml" title="Site RSS" href="/feed.xml">
+                <link rel="alternate" type="application/atom+xml" title="Site Atom" href="/atom.xml">
+                </head></html>"#
+                    .to_string(),
+            ),
+            ("/feed.xml", "application/rss+xml", RSS_SAMPLE.to_string()),
+            ("/atom.xml", "application/atom+xml", ATOM_SAMPLE.to_string()),
+        ]);
+        let input_dir = tempfile::tempdir().unwrap();
+        let input_path = input_dir.path().join("input.json");
+        fs::write(
+            &input_path,
+            format!(
+                r#"[
+                    {{"url": "{}/some/page", "title": "My Preferred Title", "feed_type": "rss"}},
+                    {{"url": "https://other.example.com"}}
+                ]"#,
+                server.base
+            ),
+        )
+        .unwrap();
+
+        let entries = read_input_entries(&input_path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].url, server.base);
+        assert_eq!(entries[0].title.as_deref(), Some("My Preferred Title"));
+        assert_eq!(entries[1], InputEntry::new("https://other.example.com"));
+
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let feeds = find_rss_feeds(&entries[0].url, &client).unwrap();
+        assert_eq!(feeds.len(), 2);
+        let feeds = InputOverrides::new(&entries).apply_all(feeds);
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].feed_type, FeedType::Rss);
+
+        let output = NamedTempFile::new().unwrap();
+        create_opml_file(&feeds, output.path()).unwrap();
+        let content = fs::read_to_string(output.path()).unwrap();
+        assert!(content.contains("My Preferred Title"));
+        assert!(!content.contains("Site RSS"));
+    }
+
+    #[test]
+    fn test_csv_input_entries() {
+        let input_dir = tempfile::tempdir().unwrap();
+        let input_path = input_dir.path().join("input.csv");
+        fs::write(
+            &input_path,
+            "url,title,feed_type\n\
+             https://example.com/blog,\"Example, Inc. Blog\",atom\n\
+             https://test.com,,\n",
+        )
+        .unwrap();
+
+        let entries = read_input_entries(&input_path).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                InputEntry {
+                    url: "https://example.com".to_string(),
+                    title: Some("Example, Inc. Blog".to_string()),
+                    feed_type: Some(FeedType::Atom),
+                    category: None,
+                },
+                InputEntry::new("https://test.com"),
+            ]
+        );
+
+        fs::write(&input_path, "url,feed_type\nhttps://example.com,feed\n").unwrap();
+        let error = read_input_entries(&input_path).unwrap_err();
+        assert!(format!("{:#}", error).contains("unknown variant `feed`"));
+    }
+
+    #[test]
+    fn test_output_path_directory_gets_default_file_name() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let feeds = vec![RssFeed::new(
+            "Test Feed",
+            "https://example.com/feed.xml",
+            "https://example.com",
+            FeedType::Rss,
+        )];
+
+        create_opml_file(&feeds, temp_dir.path()).unwrap();
+        create_json_file(&feeds, temp_dir.path()).unwrap();
+
+        let opml_path = temp_dir.path().join(DEFAULT_OPML_FILE_NAME);
+        assert!(
+            fs::read_to_string(&opml_path)
+                .unwrap()
+                .contains("Test Feed")
+        );
+        assert!(temp_dir.path().join(DEFAULT_JSON_FILE_NAME).is_file());
+        assert_eq!(
+            resolve_output_path(&opml_path, DEFAULT_OPML_FILE_NAME),
+            opml_path
+        );
+    }
+
+    #[test]
+    fn test_scan_anchors_discovers_body_feed_link() {
+        let server = MockServer::with_routes(vec![
+            (
+                "/",
+                "text/html",
+                r#"<html><head><title>Minimal</title></head><body>
+                <a href="/about">About</a>
+                <a href="mailto:me@example.com">Contact</a>
+                <a href="/subscribe/posts">RSS</a>
+                </body></html>"#
+                    .to_string(),
+            ),
+            (
+                "/subscribe/posts",
+                "application/rss+xml",
+                RSS_SAMPLE.to_string(),
+            ),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        let feeds = find_rss_feeds(&server.url("/"), &client).unwrap();
+        assert!(feeds.is_empty());
+
+        let config = MinerConfig {
+            scan_anchors: true,
+            ..Default::default()
+        };
+        let feeds = find_rss_feeds_with_config(&server.url("/"), &client, &config).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].url, server.url("/subscribe/posts"));
+        assert_eq!(feeds[0].discovery_method, Some(DiscoveryMethod::Anchor));
+        assert!(
+            !server
+                .requests()
+                .iter()
+                .any(|request| request.path == "/about")
+        );
+    }
+
+    #[test]
+    fn test_feed_like_anchors_capped() {
+        let body: String = (0..10)
+            .map(|i| format!(r#"<a href="/feed{}.xml">Feed {}</a>"#, i, i))
+            .collect();
+        let document = Html::parse_document(&format!("<html><body>{}</body></html>", body));
+
+        let candidates = feed_like_anchors(&document, "https://example.com", 3);
+        assert_eq!(
+            candidates,
+            vec![
+                "https://example.com/feed0.xml",
+                "https://example.com/feed1.xml",
+                "https://example.com/feed2.xml",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_group_by_host_flattens_single_feed_hosts() {
+        let feeds = vec![
+            RssFeed::new(
+                "Blog A",
+                "https://a.example.com/feed.xml",
+                "https://a.example.com",
+                FeedType::Rss,
+            ),
+            RssFeed::new(
+                "Blog B",
+                "https://b.example.com/atom.xml",
+                "https://b.example.com",
+                FeedType::Atom,
+            ),
+            RssFeed::new(
+                "Blog A Comments",
+                "https://a.example.com/comments.xml",
+                "https://a.example.com",
+                FeedType::Rss,
+            ),
+        ];
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_path = temp_dir.path().join("grouped.opml");
+
+        let options = OpmlOptions {
+            group_by_host: true,
+            ..Default::default()
+        };
+        create_opml_file_with_options(&feeds, &output_path, &options).unwrap();
+        let mut file = fs::File::open(&output_path).unwrap();
+        let grouped = opml::OPML::from_reader(&mut file).unwrap();
+        assert_eq!(grouped.body.outlines.len(), 2);
+        assert!(
+            grouped
+                .body
+                .outlines
+                .iter()
+                .all(|outline| outline.xml_url.is_none())
+        );
+
+        let options = OpmlOptions {
+            flatten_single_group: true,
+            ..options
+        };
+        create_opml_file_with_options(&feeds, &output_path, &options).unwrap();
+        let mut file = fs::File::open(&output_path).unwrap();
+        let flattened = opml::OPML::from_reader(&mut file).unwrap();
+        let outlines = &flattened.body.outlines;
+        assert_eq!(outlines.len(), 2);
+        assert_eq!(outlines[0].text, "a.example.com");
+        assert_eq!(outlines[0].outlines.len(), 2);
+        assert_eq!(outlines[0].outlines[0].text, "Blog A");
+        assert_eq!(outlines[1].text, "Blog B");
+        assert!(outlines[1].outlines.is_empty());
+        assert_eq!(
+            outlines[1].xml_url.as_deref(),
+            Some("https://b.example.com/atom.xml")
+        );
+    }
+
+    #[test]
+    fn test_parse_feed_captures_language() {
+        let rss = RSS_SAMPLE.replace(
+            "<description>Example feed</description>",
+            "<description>Example feed</description><language>en-us</language>",
+        );
+        let info = parse_feed(rss.as_bytes(), 0).unwrap();
+        assert_eq!(info.language.as_deref(), Some("en-US"));
+
+        let atom = ATOM_SAMPLE.replace(
+            r#"<feed xmlns="http://www.w3.org/2005/Atom">"#,
+            r#"<feed xmlns="http://www.w3.org/2005/Atom" xml:lang="fr">"#,
+        );
+        let info = parse_feed(atom.as_bytes(), 0).unwrap();
+        assert_eq!(info.language.as_deref(), Some("fr"));
+
+        assert_eq!(parse_feed(RSS_SAMPLE.as_bytes(), 0).unwrap().language, None);
+    }
+
+    #[test]
+    fn test_validate_feed_samples_item_titles() {
+        let items: String = (1..=4)
+            .map(|n| {
+                format!("<item><title>Post {n}</title><link>https://example.com/{n}</link></item>")
+            })
+            .collect();
+        let rss = format!(
+            r#"<?xml version="1.0"?><rss version="2.0"><channel><title>Sampled</title><link>https://example.com</link><description>d</description>{items}</channel></rss>"#
+        );
+        let server = MockServer::with_routes(vec![("/feed.xml", "application/rss+xml", rss)]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        let info = validate_feed(&server.url("/feed.xml"), &client).unwrap();
+        assert!(info.sample_titles.is_empty());
+
+        let config = MinerConfig {
+            sample_titles: 3,
+            ..Default::default()
+        };
+        let info = validate_feed_with_config(&server.url("/feed.xml"), &client, &config).unwrap();
+        assert_eq!(info.sample_titles, ["Post 1", "Post 2", "Post 3"]);
+        assert!(format_feed_report("u", &info).contains("    - Post 2\n"));
+    }
+
+    #[test]
+    fn test_parse_feed_captures_author() {
+        let atom = ATOM_SAMPLE.replace(
+            "<title>Example Atom</title>",
+            "<title>Example Atom</title><author><name>Ada Lovelace</name></author>",
+        );
+        let info = parse_feed(atom.as_bytes(), 0).unwrap();
+        assert_eq!(info.author.as_deref(), Some("Ada Lovelace"));
+
+        let rss = RSS_SAMPLE
+            .replace(
+                r#"<rss version="2.0">"#,
+                r#"<rss version="2.0" xmlns:dc="http://purl.org/dc/elements/1.1/">"#,
+            )
+            .replace(
+                "<description>Example feed</description>",
+                "<description>Example feed</description><dc:creator>Grace Hopper</dc:creator>",
+            );
+        let info = parse_feed(rss.as_bytes(), 0).unwrap();
+        assert_eq!(info.author.as_deref(), Some("Grace Hopper"));
+
+        assert_eq!(parse_feed(RSS_SAMPLE.as_bytes(), 0).unwrap().author, None);
+
+        let feed = discovered_feed(
+            "Blog",
+            "https://example.com/feed".to_string(),
+            "https://example.com",
+            info,
+            DiscoveryMethod::Link,
+        );
+        let json = serde_json::to_value(&feed).unwrap();
+        assert_eq!(json["author"], "Grace Hopper");
+    }
+
+    #[cfg(feature = "feed-rs")]
+    #[test]
+    fn test_feed_rs_backend_classifies_formats() {
+        let json = r#"{
+            "version": "https://jsonfeed.org/version/1.1",
+            "title": "Example JSON",
+            "items": [{"id": "1", "title": "First item", "content_text": "Hi"}]
+        }"#;
+
+        let rss = parse_feed_with_feed_rs(RSS_SAMPLE.as_bytes(), 1).unwrap();
+        assert_eq!(rss.feed_type, FeedType::Rss);
+        assert_eq!(rss.title, "Example RSS");
+        assert_eq!(rss.sample_titles, ["First post"]);
+
+        let atom = parse_feed_with_feed_rs(ATOM_SAMPLE.as_bytes(), 0).unwrap();
+        assert_eq!(atom.feed_type, FeedType::Atom);
+        assert_eq!(atom.title, "Example Atom");
+        assert_eq!(atom.item_count, 1);
+
+        let json = parse_feed_with_feed_rs(json.as_bytes(), 0).unwrap();
+        assert_eq!(json.feed_type, FeedType::Json);
+        assert_eq!(json.title, "Example JSON");
+        assert_eq!(json.item_count, 1);
+
+        assert!(parse_feed_with_feed_rs(b"<html></html>", 0).is_none());
+    }
+
+    #[test]
+    fn test_normalize_language_tag() {
+        assert_eq!(normalize_language_tag("EN_gb").as_deref(), Some("en-GB"));
+        assert_eq!(
+            normalize_language_tag("zh-hant-tw").as_deref(),
+            Some("zh-Hant-TW")
+        );
+        assert_eq!(normalize_language_tag(" fr ").as_deref(), Some("fr"));
+        assert_eq!(
+            normalize_language_tag("English").as_deref(),
+            Some("english")
+        );
+        assert_eq!(normalize_language_tag("  "), None);
+    }
+
+    #[test]
+    fn test_peek_feeds_returns_unvalidated_candidates() {
+        let server = MockServer::with_routes(vec![(
+            "/",
+            "text/html",
+            r#"<html><head>
+            <link rel="alternate" type="application/rss+xml" title="Posts" href="/posts.xml">
+            <link rel="alternate" type="application/atom+xml" href="https://other.example.com/atom">
+            </head><body></body></html>"#
+                .to_string(),
+        )]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        let feeds = peek_feeds(&server.url("/"), &client).unwrap();
+
+        assert_eq!(feeds.len(), 2);
+        assert_eq!(feeds[0].title, "Posts");
+        assert_eq!(feeds[0].url, server.url("/posts.xml"));
+        assert_eq!(feeds[0].feed_type, FeedType::Rss);
+        assert_eq!(feeds[1].title, UNTITLED_FEED);
+        assert_eq!(feeds[1].url, "https://other.example.com/atom");
+        assert_eq!(feeds[1].feed_type, FeedType::Atom);
+
+        // Only the page itself was fetched; /posts.xml doesn't even exist
+        let paths: Vec<String> = server
+            .requests()
+            .into_iter()
+            .map(|request| request.path)
+            .collect();
+        assert_eq!(paths, vec!["/"]);
+    }
+
+    #[test]
+    fn test_peek_feeds_retries_like_other_fetches() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&attempts);
+        let server = MockServer::start(move |_| {
+            if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                let mut response = MockResponse::new(503, "text/plain", "busy");
+                response
+                    .headers
+                    .push(("Retry-After".to_string(), "0".to_string()));
+                response
+            } else {
+                MockResponse::new(
+                    200,
+                    "text/html",
+                    r#"<html><head><link type="application/rss+xml" href="/feed.xml"></head></html>"#,
+                )
+            }
+        });
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let config = MinerConfig {
+            max_retries: 1,
+            retry_jitter: Duration::ZERO,
+            max_inflight_requests: Some(1),
+            ..Default::default()
+        };
+
+        let feeds = peek_feeds_with_config(&server.url("/"), &client, &config).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].url, server.url("/feed.xml"));
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_probe_fediverse_actor_feed() {
+        let server = MockServer::with_routes(vec![(
+            "/@user.rss",
+            "application/rss+xml",
+            RSS_SAMPLE.to_string(),
+        )]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let config = MinerConfig {
+            probe_fediverse: true,
+            ..Default::default()
+        };
+
+        let feeds = find_rss_feeds_with_config(&server.url("/@user"), &client, &config).unwrap();
+
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].url, server.url("/@user.rss"));
+        assert_eq!(feeds[0].title, "@user@127.0.0.1");
+        assert_eq!(feeds[0].discovery_method, Some(DiscoveryMethod::Fediverse));
+        assert!(
+            find_rss_feeds(&server.url("/@user"), &client)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_known_site_feed_urls() {
+        let all = [KnownSite::YouTube, KnownSite::Reddit];
+        assert_eq!(
+            known_site_feed_url("https://www.youtube.com/channel/UC123abc", &all).as_deref(),
+            Some("https://www.youtube.com/feeds/videos.xml?channel_id=UC123abc")
+        );
+        assert_eq!(
+            known_site_feed_url("https://m.youtube.com/playlist?list=PL42", &all).as_deref(),
+            Some("https://www.youtube.com/feeds/videos.xml?playlist_id=PL42")
+        );
+        assert_eq!(
+            known_site_feed_url("https://www.reddit.com/r/rust/", &all).as_deref(),
+            Some("https://www.reddit.com/r/rust/.rss")
+        );
+        assert_eq!(
+            known_site_feed_url("https://old.reddit.com/u/spez", &all).as_deref(),
+            Some("https://www.reddit.com/user/spez/.rss")
+        );
+        assert_eq!(known_site_feed_url("https://www.youtube.com/", &all), None);
+        assert_eq!(
+            known_site_feed_url("https://example.com/r/rust", &all),
+            None
+        );
+
+        // Each family is opt-in
+        assert_eq!(
+            known_site_feed_url("https://www.reddit.com/r/rust", &[KnownSite::YouTube]),
+            None
+        );
+        let options = InputOptions {
+            known_sites: vec![KnownSite::Reddit],
+            ..Default::default()
+        };
+        assert_eq!(
+            normalize_input_url("https://www.reddit.com/r/rust", &options),
+            "https://www.reddit.com/r/rust"
+        );
+        assert_eq!(
+            normalize_input_url("https://www.youtube.com/channel/UC1", &options),
+            "https://www.youtube.com"
+        );
+    }
+
+    #[test]
+    fn test_fediverse_actor_feed_url() {
+        assert_eq!(
+            fediverse_actor_feed_url("https://mastodon.social/@user").as_deref(),
+            Some("https://mastodon.social/@user.rss")
+        );
+        assert_eq!(
+            fediverse_actor_feed_url("https://mastodon.social/users/user/").as_deref(),
+            Some("https://mastodon.social/@user.rss")
+        );
+        assert_eq!(
+            fediverse_actor_feed_url("https://mastodon.social/@user/123"),
+            None
+        );
+        assert_eq!(
+            fediverse_actor_feed_url("https://mastodon.social/@user.rss"),
+            None
+        );
+        assert_eq!(fediverse_actor_feed_url("https://example.com/blog"), None);
+    }
+
+    #[test]
+    fn test_custom_validator_used_after_builtins() {
+        #[derive(Debug, Default)]
+        struct TwtxtValidator {
+            calls: AtomicUsize,
+        }
+
+        impl FeedValidator for TwtxtValidator {
+            fn try_validate(&self, body: &[u8]) -> Option<FeedInfo> {
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                let body = std::str::from_utf8(body).ok()?;
+                body.starts_with("# twtxt").then(|| FeedInfo {
+                    title: "twtxt".to_string(),
+                    item_count: body.lines().filter(|line| !line.starts_with('#')).count(),
+                    ..FeedInfo::new(FeedType::Unknown)
+                })
+            }
+        }
+
+        let server = MockServer::with_routes(vec![
+            (
+                "/twtxt.txt",
+                "text/plain",
+                "# twtxt\n2024-01-01T00:00:00Z\tHello\n".to_string(),
+            ),
+            ("/feed.xml", "application/rss+xml", RSS_SAMPLE.to_string()),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let validator = Arc::new(TwtxtValidator::default());
+        let config = MinerConfig {
+            custom_validators: vec![validator.clone()],
+            ..Default::default()
+        };
+
+        assert!(validate_feed(&server.url("/twtxt.txt"), &client).is_none());
+
+        let info = validate_feed_with_config(&server.url("/twtxt.txt"), &client, &config).unwrap();
+        assert_eq!(info.title, "twtxt");
+        assert_eq!(info.item_count, 1);
+        assert_eq!(validator.calls.load(Ordering::SeqCst), 1);
+
+        // Built-in formats win without consulting custom validators
+        let info = validate_feed_with_config(&server.url("/feed.xml"), &client, &config).unwrap();
+        assert_eq!(info.feed_type, FeedType::Rss);
+        assert_eq!(validator.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_report_event_log() {
+        let server = MockServer::with_routes(vec![
+            ("/", "text/html", "<html></html>".to_string()),
+            ("/feed", "application/rss+xml", RSS_SAMPLE.to_string()),
+        ]);
+        let empty = MockServer::with_routes(vec![("/", "text/html", "<html></html>".to_string())]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let urls = vec![
+            server.url("/"),
+            empty.url("/"),
+            "http://127.0.0.1:1/".to_string(),
+        ];
+
+        let report = find_rss_feeds_parallel_report(&urls, &client, &MinerConfig::default(), false);
+        assert!(report.log.is_empty());
+
+        let config = MinerConfig {
+            event_log: true,
+            ..Default::default()
+        };
+        let report = find_rss_feeds_parallel_report(&urls, &client, &config, false);
+
+        assert_eq!(report.log.len(), 3);
+        assert!(
+            report
+                .log
+                .windows(2)
+                .all(|pair| pair[0].timestamp <= pair[1].timestamp)
+        );
+        let event_for = |url: &str| {
+            report
+                .log
+                .iter()
+                .find(|entry| entry.url == url)
+                .map(|entry| entry.event.clone())
+                .unwrap()
+        };
+        assert_eq!(event_for(&urls[0]), LogEvent::FeedsFound(1));
+        assert_eq!(
+            event_for(&urls[1]),
+            LogEvent::NoFeeds(NoFeedsReason::NoFeedLinks)
+        );
+        assert!(matches!(event_for(&urls[2]), LogEvent::Failed(_)));
+    }
+
+    #[test]
+    fn test_report_classifies_no_feeds_reasons() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/links/" => MockResponse::new(
+                200,
+                "text/html",
+                r#"<html><head><link rel="alternate" type="application/rss+xml" href="/broken.xml"></head></html>"#,
+            ),
+            "/broken.xml" => MockResponse::new(200, "application/rss+xml", "not a feed"),
+            "/plain/" => MockResponse::new(200, "text/html", "<html></html>"),
+            "/gone/" => MockResponse::new(410, "text/html", "<html>Gone</html>"),
+            "/challenge/" => MockResponse::new(
+                403,
+                "text/html",
+                "<html><head><title>Just a moment...</title></head></html>",
+            ),
+            "/denied/" => MockResponse::new(
+                200,
+                "text/html",
+                r#"<html><head><link rel="alternate" type="application/rss+xml" href="/denied/feed.xml"></head></html>"#,
+            ),
+            "/denied/feed.xml" => MockResponse::new(200, "application/rss+xml", RSS_SAMPLE),
+            _ => MockResponse::not_found(),
+        });
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let config = MinerConfig {
+            feed_url_denylist: vec!["/denied/".to_string()],
+            ..Default::default()
+        };
+        let urls: Vec<String> = ["/links/", "/plain/", "/gone/", "/challenge/", "/denied/"]
+            .iter()
+            .map(|path| server.url(path))
+            .collect();
+
+        let report = find_rss_feeds_parallel_report(&urls, &client, &config, false);
+
+        assert_eq!(report.urls_without_feeds, 5);
+        let reasons: Vec<NoFeedsReason> = report
+            .no_feeds_reasons
+            .iter()
+            .map(|(_, reason)| *reason)
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                NoFeedsReason::InvalidFeedLinks,
+                NoFeedsReason::NoFeedLinks,
+                NoFeedsReason::PageStatus(410),
+                NoFeedsReason::ChallengePage,
+                NoFeedsReason::Denylisted,
+            ]
+        );
+        assert_eq!(report.no_feeds_reasons[2].0, urls[2]);
+    }
+
+    #[test]
+    fn test_html_strategies_share_one_page_fetch() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/" => MockResponse::new(
+                200,
+                "text/html",
+                r#"<html><head><base href="/blog/"><link rel="alternate" type="application/rss+xml" href="broken.xml"></head><body><a href="atom.xml">Atom feed</a></body></html>"#,
+            ),
+            "/old" => MockResponse::new(
+                200,
+                "text/html",
+                r#"<html><head><meta http-equiv="Refresh" content="0; URL='/'"></head></html>"#,
+            ),
+            "/blog/atom.xml" => MockResponse::new(200, "application/atom+xml", ATOM_SAMPLE),
+            _ => MockResponse::not_found(),
+        });
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let config = MinerConfig {
+            scan_anchors: true,
+            use_head_probe: true,
+            ..Default::default()
+        };
+        let gets = |path: &str| {
+            server
+                .requests()
+                .iter()
+                .filter(|request| request.method == "GET" && request.path == path)
+                .count()
+        };
+
+        // The refresh stub is fetched once and leads to the page, fetched once
+        let feeds = find_rss_feeds_with_config(&server.url("/old"), &client, &config).unwrap();
+
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].url, server.url("/blog/atom.xml"));
+        assert_eq!(gets("/old"), 1);
+        assert_eq!(gets("/"), 1);
+        // The <link> was resolved against <base href>, not the page URL
+        assert_eq!(gets("/blog/broken.xml"), 1);
+
+        // A feed given directly is validated from its one page fetch
+        let feed_gets = gets("/blog/atom.xml");
+        let feeds =
+            find_rss_feeds_with_config(&server.url("/blog/atom.xml"), &client, &config).unwrap();
+
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].title, "Example Atom");
+        assert_eq!(feeds[0].discovery_method, Some(DiscoveryMethod::Direct));
+        assert_eq!(gets("/blog/atom.xml"), feed_gets + 1);
+    }
+
+    #[test]
+    fn test_page_fetch_cap_truncates_run() {
+        let server = MockServer::with_routes(vec![("/", "text/html", "<html></html>".to_string())]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let urls: Vec<String> = (0..5)
+            .map(|n| server.url(&format!("/?page={}", n)))
+            .collect();
+        let config = MinerConfig {
+            max_page_fetches: Some(2),
+            ..Default::default()
+        };
+
+        let report = find_rss_feeds_parallel_report(&urls, &client, &config, false);
+
+        assert_eq!(report.urls_processed, 5);
+        assert_eq!(report.urls_without_feeds, 2);
+        assert_eq!(report.urls_skipped, 3);
+        assert_eq!(report.urls_failed, 0);
+        let page_gets = server
+            .requests()
+            .iter()
+            .filter(|request| request.path.starts_with("/?page="))
+            .count();
+        assert_eq!(page_gets, 2);
+    }
+
+    #[test]
+    fn test_meta_alternate_feed_discovered() {
+        let server = MockServer::with_routes(vec![
+            (
+                "/",
+                "text/html",
+                r#"<html><head><meta name="description" content="/about.xml"><meta name="alternate" content="/news/feed.xml"></head></html>"#
+                    .to_string(),
+            ),
+            (
+                "/news/feed.xml",
+                "application/rss+xml",
+                RSS_SAMPLE.to_string(),
+            ),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        let feeds = find_rss_feeds(&server.url("/"), &client).unwrap();
+
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].url, server.url("/news/feed.xml"));
+        assert_eq!(feeds[0].discovery_method, Some(DiscoveryMethod::Meta));
+        assert!(!server.requests().iter().any(|r| r.path == "/about.xml"));
+
+        let config = MinerConfig {
+            meta_feed_selector: String::new(),
+            ..Default::default()
+        };
+        let feeds = find_rss_feeds_with_config(&server.url("/"), &client, &config).unwrap();
+        assert!(feeds.is_empty());
+    }
+
+    #[test]
+    fn test_gzip_sitemap_leads_to_feed() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/" => MockResponse::new(200, "text/html", "<html></html>"),
+            "/sitemap.xml.gz" => {
+                let base = request.header("Host").unwrap_or_default().to_string();
+                let sitemap = format!(
+                    r#"<?xml version="1.0"?><urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"><url><loc>http://{base}/about</loc></url><url><loc>http://{base}/blog/feeds/posts.rss</loc></url></urlset>"#
+                );
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(sitemap.as_bytes()).unwrap();
+                MockResponse {
+                    status: 200,
+                    headers: vec![("Content-Type".to_string(), "application/gzip".to_string())],
+                    body: encoder.finish().unwrap(),
+                }
+            }
+            "/blog/feeds/posts.rss" => MockResponse::new(200, "application/rss+xml", RSS_SAMPLE),
+            _ => MockResponse::not_found(),
+        });
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        assert!(
+            find_rss_feeds(&server.url("/"), &client)
+                .unwrap()
+                .is_empty()
+        );
+
+        let config = MinerConfig {
+            scan_sitemap: true,
+            ..Default::default()
+        };
+        let feeds = find_rss_feeds_with_config(&server.url("/"), &client, &config).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].url, server.url("/blog/feeds/posts.rss"));
+        assert_eq!(feeds[0].discovery_method, Some(DiscoveryMethod::Sitemap));
+        assert!(!server.requests().iter().any(|r| r.path == "/about"));
+    }
+
+    #[test]
+    fn test_max_feeds_per_site_keeps_main_feed() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/" => MockResponse::new(
+                200,
+                "text/html",
+                r#"<html><head>
+                <link rel="alternate" type="application/rss+xml" title="Comments" href="/comments/feed/">
+                <link rel="alternate" type="application/rss+xml" title="Rust" href="/category/rust/feed/">
+                <link rel="alternate" type="application/rss+xml" title="Main" href="/feed/">
+                </head></html>"#,
+            ),
+            "/comments/feed/" | "/category/rust/feed/" | "/feed/" => {
+                MockResponse::new(200, "application/rss+xml", RSS_SAMPLE)
+            }
+            _ => MockResponse::not_found(),
+        });
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        assert_eq!(find_rss_feeds(&server.url("/"), &client).unwrap().len(), 3);
+
+        let config = MinerConfig {
+            max_feeds_per_site: Some(1),
+            ..Default::default()
+        };
+        let feeds = find_rss_feeds_with_config(&server.url("/"), &client, &config).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].title, "Main");
+    }
+
+    #[test]
+    fn test_checkpoint_resume_processes_only_remaining_urls() {
+        let first = MockServer::with_routes(vec![
+            ("/", "text/html", "<html></html>".to_string()),
+            ("/feed", "application/rss+xml", RSS_SAMPLE.to_string()),
+        ]);
+        let second = MockServer::with_routes(vec![
+            ("/", "text/html", "<html></html>".to_string()),
+            ("/feed", "application/rss+xml", RSS_SAMPLE.to_string()),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let config = MinerConfig::default();
+        let urls = vec![first.url("/"), second.url("/")];
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("run.checkpoint");
+
+        // The first run is interrupted after half the list
+        let checkpoint = Checkpoint::create(&path).unwrap();
+        let report = find_rss_feeds_parallel_report_with_checkpoint(
+            &urls[..1],
+            &client,
+            &config,
+            false,
+            &checkpoint,
+            |_| {},
+        )
+        .unwrap();
+        assert_eq!(report.feeds.len(), 1);
+        drop(checkpoint);
+        let first_requests = first.requests().len();
+
+        let checkpoint = Checkpoint::resume(&path).unwrap();
+        assert!(checkpoint.is_processed(&urls[0]));
+        assert_eq!(checkpoint.processed_count(), 1);
+        let report = find_rss_feeds_parallel_report_with_checkpoint(
+            &urls,
+            &client,
+            &config,
+            false,
+            &checkpoint,
+            |_| {},
+        )
+        .unwrap();
+
+        assert_eq!(first.requests().len(), first_requests);
+        assert!(!second.requests().is_empty());
+        assert_eq!(report.urls_resumed, 1);
+        assert_eq!(report.urls_processed, 1);
+        let feed_urls: Vec<&str> = report.feeds.iter().map(|feed| feed.url.as_str()).collect();
+        assert_eq!(feed_urls, vec![first.url("/feed"), second.url("/feed")]);
+        assert_eq!(Checkpoint::resume(&path).unwrap().processed_count(), 2);
+    }
+
+    #[test]
+    fn test_checkpoint_resume_drops_truncated_line() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("run.checkpoint");
+        let feed = RssFeed::new(
+            "Blog",
+            "https://a.example/feed",
+            "https://a.example",
+            FeedType::Rss,
+        );
+
+        let checkpoint = Checkpoint::create(&path).unwrap();
+        checkpoint.record("https://a.example", &[feed]).unwrap();
+        drop(checkpoint);
+        let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(br#"{"url":"https://b.example","fe"#)
+            .unwrap();
+
+        let checkpoint = Checkpoint::resume(&path).unwrap();
+        assert!(checkpoint.is_processed("https://a.example"));
+        assert!(!checkpoint.is_processed("https://b.example"));
+        // Rewritten in place via a temporary file, without the partial line
+        assert!(fs::read_to_string(&path).unwrap().ends_with("}\n"));
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+        assert_eq!(checkpoint.feeds()[0].url, "https://a.example/feed");
+        checkpoint.record("https://b.example", &[]).unwrap();
+        assert_eq!(Checkpoint::resume(&path).unwrap().processed_count(), 2);
+    }
+
+    #[test]
+    fn test_subdomain_probe_finds_blog_feed() {
+        let server = MockServer::start(|req| {
+            let host = req.header("host").unwrap_or_default();
+            match (
+                host.split(':').next().unwrap_or_default(),
+                req.path.as_str(),
+            ) {
+                ("example.com", "/") => MockResponse::new(200, "text/html", "<html></html>"),
+                ("blog.example.com", "/") => MockResponse::new(
+                    200,
+                    "text/html",
+                    r#"<html><head><link rel="alternate" type="application/rss+xml" href="/rss.xml"></head></html>"#,
+                ),
+                ("blog.example.com", "/rss.xml") => {
+                    MockResponse::new(200, "application/rss+xml", RSS_SAMPLE)
+                }
+                _ => MockResponse::not_found(),
+            }
+        });
+        let port = server.base.rsplit(':').next().unwrap().to_string();
+        let loopback: std::net::SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let client = Client::builder()
+            .resolve("example.com", loopback)
+            .resolve("blog.example.com", loopback)
+            .resolve("www.example.com", loopback)
+            .resolve("news.example.com", loopback)
+            .build()
+            .unwrap();
+        let url = format!("http://example.com:{}/", port);
+
+        let feeds = find_rss_feeds_with_config(&url, &client, &MinerConfig::default()).unwrap();
+        assert!(feeds.is_empty());
+
+        let config = MinerConfig {
+            probe_subdomains: true,
+            ..Default::default()
+        };
+        let feeds = find_rss_feeds_with_config(&url, &client, &config).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(
+            feeds[0].url,
+            format!("http://blog.example.com:{}/rss.xml", port)
+        );
+        assert_eq!(
+            subdomain_urls("https://www.example.com/"),
+            vec!["https://blog.example.com/", "https://news.example.com/"]
+        );
+        assert!(subdomain_urls("http://127.0.0.1:8080/").is_empty());
+    }
+
+    #[test]
+    fn test_first_feed_only_stops_after_first_valid_feed() {
+        let html = r#"<html><head>
+<link rel="alternate" type="application/rss+xml" href="/one.xml">
+<link rel="alternate" type="application/rss+xml" href="/two.xml">
+<link rel="alternate" type="application/atom+xml" href="/three.xml">
+</head></html>"#;
+        let server = MockServer::with_routes(vec![
+            ("/", "text/html", html.to_string()),
+            ("/one.xml", "application/rss+xml", RSS_SAMPLE.to_string()),
+            ("/two.xml", "application/rss+xml", RSS_SAMPLE.to_string()),
+            (
+                "/three.xml",
+                "application/atom+xml",
+                ATOM_SAMPLE.to_string(),
+            ),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let feed_fetches = |server: &MockServer| {
+            server
+                .requests()
+                .iter()
+                .filter(|req| req.path.ends_with(".xml"))
+                .count()
+        };
+
+        let feeds =
+            find_rss_feeds_with_config(&server.url("/"), &client, &MinerConfig::default()).unwrap();
+        assert_eq!(feeds.len(), 3);
+        let all_fetches = feed_fetches(&server);
+
+        let config = MinerConfig {
+            first_feed_only: true,
+            ..Default::default()
+        };
+        let feeds = find_rss_feeds_with_config(&server.url("/"), &client, &config).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].url, server.url("/one.xml"));
+        assert_eq!(feed_fetches(&server) - all_fetches, 1);
+        assert!(all_fetches > 1);
+    }
+
+    #[test]
+    fn test_mixed_content_feed_flagged_and_excluded() {
+        let info = FeedInfo::new(FeedType::Rss);
+        let mixed = discovered_feed(
+            "Blog",
+            "http://example.com/feed".to_string(),
+            "https://example.com/",
+            info.clone(),
+            DiscoveryMethod::Link,
+        );
+        let secure = discovered_feed(
+            "Blog",
+            "https://example.com/feed".to_string(),
+            "https://example.com/",
+            info.clone(),
+            DiscoveryMethod::Link,
+        );
+        let plain = discovered_feed(
+            "Blog",
+            "http://example.com/feed".to_string(),
+            "http://example.com/",
+            info,
+            DiscoveryMethod::Link,
+        );
+        assert!(mixed.mixed_content);
+        assert!(!secure.mixed_content);
+        assert!(!plain.mixed_content);
+
+        let config = MinerConfig::default();
+        assert!(!config.excludes_feed(&mixed));
+        let config = MinerConfig {
+            exclude_mixed_content: true,
+            ..Default::default()
+        };
+        assert!(config.excludes_feed(&mixed));
+        assert!(!config.excludes_feed(&secure));
+        assert!(!config.excludes_feed(&plain));
+    }
+
+    #[test]
+    fn test_report_counts_downloaded_bytes() {
+        let html = r#"<html><head><link rel="alternate" type="application/rss+xml" href="/feed.xml"></head></html>"#;
+        let server = MockServer::with_routes(vec![
+            ("/", "text/html", html.to_string()),
+            ("/feed.xml", "application/rss+xml", RSS_SAMPLE.to_string()),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        let report = find_rss_feeds_parallel_report(
+            &[server.url("/")],
+            &client,
+            &MinerConfig::default(),
+            false,
+        );
+        assert_eq!(report.feeds.len(), 1);
+        assert_eq!(
+            report.bytes_downloaded,
+            (html.len() + RSS_SAMPLE.len()) as u64
+        );
+    }
+
+    #[test]
+    fn test_cookies_sent_only_to_matching_host() {
+        let server = MockServer::start(|req| match req.header("cookie") {
+            Some("session=abc") => MockResponse::new(200, "application/rss+xml", RSS_SAMPLE),
+            _ => MockResponse::new(403, "text/plain", "Forbidden"),
+        });
+        let feed_url = server.url("/private.xml");
+
+        let client = build_client(&MinerConfig::default()).unwrap();
+        assert!(validate_feed(&feed_url, &client).is_none());
+
+        let other_host = MinerConfig {
+            cookies: vec![(
+                "example.com".to_string(),
+                "session".to_string(),
+                "abc".to_string(),
+            )],
+            ..Default::default()
+        };
+        let client = build_client(&other_host).unwrap();
+        assert!(validate_feed(&feed_url, &client).is_none());
+
+        let config = MinerConfig {
+            cookies: vec![parse_cookie("127.0.0.1:session=abc").unwrap()],
+            ..Default::default()
+        };
+        let client = build_client(&config).unwrap();
+        assert!(validate_feed(&feed_url, &client).is_some());
+        assert!(
+            server
+                .requests()
+                .iter()
+                .filter_map(|req| req.header("cookie"))
+                .all(|cookie| cookie == "session=abc")
+        );
+
+        assert!(parse_cookie("no-separator").is_err());
+        assert!(parse_cookie(":name=value").is_err());
+    }
+
+    #[test]
+    fn test_report_host_stats() {
+        let html = r#"<html><head><link rel="alternate" type="application/rss+xml" href="/feed.xml"></head></html>"#;
+        let good = MockServer::with_routes(vec![
+            ("/", "text/html", html.to_string()),
+            ("/feed.xml", "application/rss+xml", RSS_SAMPLE.to_string()),
+        ]);
+        let empty = MockServer::with_routes(vec![("/", "text/html", "<html></html>".to_string())]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let urls = vec![
+            good.url("/"),
+            empty.url("/"),
+            "http://127.0.0.1:1/".to_string(),
+        ];
+
+        let report = find_rss_feeds_parallel_report(&urls, &client, &MinerConfig::default(), false);
+        assert!(report.host_stats.is_empty());
+
+        let config = MinerConfig {
+            host_stats: true,
+            ..Default::default()
+        };
+        let report = find_rss_feeds_parallel_report(&urls, &client, &config, false);
+        let good_stats = &report.host_stats[&host_key(&good.url("/")).unwrap()];
+        assert_eq!(good_stats.pages_fetched, 1);
+        assert_eq!(good_stats.feeds_found, 1);
+        assert_eq!(good_stats.errors, 0);
+        assert_eq!(good_stats.fetches, 2);
+        assert_eq!(
+            good_stats.bytes_downloaded,
+            (html.len() + RSS_SAMPLE.len()) as u64
+        );
+        assert!(good_stats.average_latency().is_some());
+
+        let empty_stats = &report.host_stats[&host_key(&empty.url("/")).unwrap()];
+        assert_eq!(empty_stats.pages_fetched, 1);
+        assert_eq!(empty_stats.feeds_found, 0);
+        // The page plus the six common feed paths
+        assert_eq!(empty_stats.fetches, 7);
+        assert_eq!(empty_stats.bytes_downloaded, "<html></html>".len() as u64);
+
+        let failed_stats = &report.host_stats["127.0.0.1:1"];
+        assert_eq!(failed_stats.errors, 1);
+        assert_eq!(failed_stats.bytes_downloaded, 0);
+    }
+
+    #[test]
+    fn test_host_stats_count_bytes_as_sent() {
+        // Each Latin-1 "é" is one byte on the wire but two once decoded
+        let page = b"<html><head><title>Caf\xe9 \xe9t\xe9</title></head></html>".to_vec();
+        let page_len = page.len() as u64;
+        let server = MockServer::start(move |req| match req.path.as_str() {
+            "/" => {
+                let mut response = MockResponse::new(200, "text/html; charset=iso-8859-1", "");
+                response.body = page.clone();
+                response
+            }
+            _ => MockResponse::not_found(),
+        });
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let config = MinerConfig {
+            host_stats: true,
+            probe_paths: Vec::new(),
+            ..Default::default()
+        };
+
+        let report = find_rss_feeds_parallel_report(&[server.url("/")], &client, &config, false);
+        assert_eq!(report.bytes_downloaded, page_len);
+        let host = &report.host_stats[&host_key(&server.url("/")).unwrap()];
+        assert_eq!(host.bytes_downloaded, page_len);
+    }
+
+    #[test]
+    fn test_validation_cache_persists_between_runs() {
+        let html = r#"<html><head><link rel="alternate" type="application/rss+xml" href="/feed.xml"></head></html>"#;
+        let server = MockServer::with_routes(vec![
+            ("/", "text/html", html.to_string()),
+            ("/feed.xml", "application/rss+xml", RSS_SAMPLE.to_string()),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let cache_dir = tempfile::tempdir().unwrap();
+        let feed_fetches = || {
+            server
+                .requests()
+                .iter()
+                .filter(|req| req.path == "/feed.xml")
+                .count()
+        };
+        let config = MinerConfig {
+            cache_dir: Some(cache_dir.path().join("cache")),
+            ..Default::default()
+        };
+
+        let first = find_rss_feeds_with_config(&server.url("/"), &client, &config).unwrap();
+        assert_eq!(feed_fetches(), 1);
+        assert!(
+            cache_dir
+                .path()
+                .join("cache")
+                .join(VALIDATION_CACHE_FILE_NAME)
+                .exists()
+        );
+
+        let second = find_rss_feeds_with_config(&server.url("/"), &client, &config).unwrap();
+        assert_eq!(feed_fetches(), 1);
+        assert_eq!(second[0].title, first[0].title);
+        assert_eq!(second[0].feed_type, FeedType::Rss);
+
+        let expired = MinerConfig {
+            cache_ttl: Duration::ZERO,
+            ..config.clone()
+        };
+        find_rss_feeds_with_config(&server.url("/"), &client, &expired).unwrap();
+        assert_eq!(feed_fetches(), 2);
+
+        // Validations made with other settings don't answer for these
+        let with_titles = MinerConfig {
+            sample_titles: 1,
+            ..config.clone()
+        };
+        find_rss_feeds_with_config(&server.url("/"), &client, &with_titles).unwrap();
+        assert_eq!(feed_fetches(), 3);
+
+        let sniffed = MinerConfig {
+            validation_mode: ValidationMode::ContentType,
+            ..config.clone()
+        };
+        find_rss_feeds_with_config(&server.url("/"), &client, &sniffed).unwrap();
+        assert_eq!(feed_fetches(), 4);
+
+        #[derive(Debug)]
+        struct NoValidator;
+
+        impl FeedValidator for NoValidator {
+            fn try_validate(&self, _body: &[u8]) -> Option<FeedInfo> {
+                None
+            }
+        }
+
+        let custom = MinerConfig {
+            custom_validators: vec![Arc::new(NoValidator)],
+            ..config
+        };
+        find_rss_feeds_with_config(&server.url("/"), &client, &custom).unwrap();
+        assert_eq!(feed_fetches(), 5);
+    }
+
+    #[test]
+    fn test_min_tls_version() {
+        for version in [TlsVersion::Tls12, TlsVersion::Tls13] {
+            let config = MinerConfig {
+                min_tls_version: Some(version),
+                ..Default::default()
+            };
+            assert!(build_client(&config).is_ok());
+        }
+        let config = MinerConfig::from_toml_str(r#"min_tls_version = "1.3""#).unwrap();
+        assert_eq!(config.min_tls_version, Some(TlsVersion::Tls13));
+
+        let alert = io::Error::new(
+            io::ErrorKind::InvalidData,
+            rustls::Error::AlertReceived(rustls::AlertDescription::ProtocolVersion),
+        );
+        assert!(is_tls_version_error(&alert));
+        assert!(tls_error(&alert).is_some());
+        let refused = io::Error::from(io::ErrorKind::ConnectionRefused);
+        assert!(!is_tls_version_error(&refused));
+        // Only the typed error counts, not text that happens to match
+        let lookalike = io::Error::other("received fatal alert: ProtocolVersion");
+        assert!(!is_tls_version_error(&lookalike));
+        // A malformed response body is not a TLS failure
+        let malformed = io::Error::new(io::ErrorKind::InvalidData, "invalid chunk size");
+        assert!(tls_error(&malformed).is_none());
+    }
+
+    #[test]
+    fn test_require_alternate_rel_skips_mistyped_links() {
+        let html = r#"<html><head>
+<link rel="stylesheet" type="application/rss+xml" href="/print.xml">
+<link rel="Alternate nofollow" type="application/rss+xml" href="/feed.xml">
+<link type="application/atom+xml" href="/atom.xml">
+</head></html>"#;
+        let server = MockServer::with_routes(vec![
+            ("/", "text/html", html.to_string()),
+            ("/print.xml", "application/rss+xml", RSS_SAMPLE.to_string()),
+            ("/feed.xml", "application/rss+xml", RSS_SAMPLE.to_string()),
+            ("/atom.xml", "application/atom+xml", ATOM_SAMPLE.to_string()),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let feed_urls = |config: &MinerConfig| -> Vec<String> {
+            find_rss_feeds_with_config(&server.url("/"), &client, config)
+                .unwrap()
+                .into_iter()
+                .map(|feed| feed.raw_url)
+                .collect()
+        };
+
+        assert_eq!(feed_urls(&MinerConfig::default()).len(), 3);
+        let strict = MinerConfig {
+            require_alternate_rel: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            feed_urls(&strict),
+            vec![server.url("/feed.xml"), server.url("/atom.xml")]
+        );
+    }
+
+    #[test]
+    fn test_tls_errors_reported_apart_from_missing_feeds() {
+        // A plain HTTP answer to a TLS ClientHello fails the handshake
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let tls_base = format!("https://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let _ = stream.read(&mut [0; 1024]);
+                let _ = stream.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n");
+            }
+        });
+        let tls_feed = format!("{}/feed.xml", tls_base);
+        let html = format!(
+            r#"<html><head>
+<link rel="alternate" type="application/rss+xml" href="{}">
+<link rel="alternate" type="application/rss+xml" href="/missing.xml">
+</head></html>"#,
+            tls_feed
+        );
+        let server = MockServer::with_routes(vec![("/", "text/html", html)]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        let report = find_rss_feeds_parallel_report(
+            &[server.url("/")],
+            &client,
+            &MinerConfig::default(),
+            false,
+        );
+        assert!(report.feeds.is_empty());
+        assert_eq!(report.tls_failures.len(), 1);
+        assert_eq!(report.tls_failures[0].0, tls_feed);
+
+        let err = find_rss_feeds(&format!("{}/", tls_base), &client).unwrap_err();
+        assert!(err.to_string().starts_with("TLS error: "));
+        let err = find_rss_feeds("http://127.0.0.1:1/", &client).unwrap_err();
+        assert!(!err.to_string().starts_with("TLS error: "));
+    }
+
+    #[test]
+    fn test_probe_paths_tried_in_order() {
+        let server = MockServer::with_routes(vec![
+            ("/", "text/html", "<html></html>".to_string()),
+            ("/feed", "application/rss+xml", RSS_SAMPLE.to_string()),
+            ("/atom.xml", "application/atom+xml", ATOM_SAMPLE.to_string()),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        let feeds =
+            find_rss_feeds_with_config(&server.url("/"), &client, &MinerConfig::default()).unwrap();
+        assert_eq!(feeds[0].raw_url, server.url("/feed"));
+
+        let config = MinerConfig {
+            probe_paths: vec!["/feed.xml".to_string(), "/atom.xml".to_string()],
+            ..Default::default()
+        };
+        let before = server.requests().len();
+        let feeds = find_rss_feeds_with_config(&server.url("/"), &client, &config).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].raw_url, server.url("/atom.xml"));
+        let probed: Vec<String> = server.requests()[before..]
+            .iter()
+            .map(|req| req.path.clone())
+            .collect();
+        assert_eq!(probed, vec!["/", "/feed.xml", "/atom.xml"]);
+    }
+
+    #[test]
+    fn test_find_feeds_in_html_validates_candidates() {
+        let server = MockServer::with_routes(vec![
+            (
+                "/blog/rss.xml",
+                "application/rss+xml",
+                RSS_SAMPLE.to_string(),
+            ),
+            ("/atom.xml", "application/atom+xml", ATOM_SAMPLE.to_string()),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let html = r#"<html><head><base href="/blog/">
+            <link rel="alternate" type="application/rss+xml" title="Blog" href="rss.xml">
+            <link rel="alternate" type="application/atom+xml" href="missing.xml">
+            </head><body></body></html>"#;
+
+        let feeds = find_feeds_in_html(html, &server.url("/"), &client).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].title, "Blog");
+        assert_eq!(feeds[0].raw_url, server.url("/blog/rss.xml"));
+        assert_eq!(feeds[0].discovery_method, Some(DiscoveryMethod::Link));
+
+        // Anchors are scanned when the head advertises nothing
+        let html = r#"<html><body><a href="/atom.xml">Atom feed</a></body></html>"#;
+        let feeds = find_feeds_in_html(html, &server.url("/"), &client).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].raw_url, server.url("/atom.xml"));
+        assert_eq!(feeds[0].discovery_method, Some(DiscoveryMethod::Anchor));
+
+        // Only the candidates were fetched, never the page itself
+        assert!(server.requests().iter().all(|req| req.path != "/"));
+    }
+
+    #[test]
+    fn test_follow_related_feeds_surfaces_atom_links() {
+        let server = MockServer::start(|req| {
+            let base = format!("http://{}", req.header("host").unwrap_or_default());
+            match req.path.as_str() {
+                "/" => MockResponse::new(
+                    200,
+                    "text/html",
+                    r#"<html><head><link rel="alternate" type="application/atom+xml" href="/atom.xml"></head></html>"#,
+                ),
+                "/atom.xml" => MockResponse::new(
+                    200,
+                    "application/atom+xml",
+                    &ATOM_SAMPLE.replacen(
+                        "<entry>",
+                        &format!(
+                            r#"<link rel="self" href="{0}/atom.xml"/>
+  <link rel="related" href="{0}/comments.xml"/>
+  <link rel="alternate" type="application/rss+xml" href="{0}/rss.xml"/>
+  <link rel="alternate" type="text/html" href="{0}/"/>
+  <link rel="related" href="{0}/missing.xml"/>
+  <entry><source><link rel="self" href="{0}/partner.xml"/></source>"#,
+                            base
+                        ),
+                        1,
+                    ),
+                ),
+                "/comments.xml" | "/partner.xml" => {
+                    MockResponse::new(200, "application/atom+xml", ATOM_SAMPLE)
+                }
+                "/rss.xml" => MockResponse::new(200, "application/rss+xml", RSS_SAMPLE),
+                _ => MockResponse::not_found(),
+            }
+        });
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        let feeds = find_rss_feeds(&server.url("/"), &client).unwrap();
+        assert_eq!(feeds.len(), 1);
+
+        let config = MinerConfig {
+            follow_related_feeds: true,
+            ..Default::default()
+        };
+        let feeds = find_rss_feeds_with_config(&server.url("/"), &client, &config).unwrap();
+        let related: Vec<&str> = feeds
+            .iter()
+            .filter(|feed| feed.discovery_method == Some(DiscoveryMethod::Related))
+            .map(|feed| feed.url.as_str())
+            .collect();
+        let mut expected = vec![server.url("/comments.xml"), server.url("/rss.xml")];
+        // feed-rs does not parse entries' <source>
+        if !cfg!(feature = "feed-rs") {
+            expected.push(server.url("/partner.xml"));
+        }
+        assert_eq!(related, expected);
+        assert_eq!(feeds.len(), expected.len() + 1);
+        assert!(feeds.iter().all(|feed| feed.html_url == server.url("/")));
+    }
+
+    #[test]
+    fn test_host_timeouts_override_global_timeout() {
+        let server = MockServer::start(|req| {
+            if req.path == "/" {
+                thread::sleep(Duration::from_millis(600));
+            }
+            MockResponse::new(200, "text/html", "<html><head></head></html>")
+        });
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let host = server.base.trim_start_matches("http://").to_string();
+
+        let short = MinerConfig {
+            timeout: Duration::from_millis(200),
+            ..Default::default()
+        };
+        assert!(find_rss_feeds_with_config(&server.url("/"), &client, &short).is_err());
+
+        let extended = MinerConfig {
+            host_timeouts: HashMap::from([(host.clone(), Duration::from_secs(5))]),
+            ..short.clone()
+        };
+        assert_eq!(
+            extended.timeout_for(&server.url("/")),
+            Duration::from_secs(5)
+        );
+        assert_eq!(
+            extended.timeout_for("https://example.com/"),
+            Duration::from_millis(200)
+        );
+        assert!(find_rss_feeds_with_config(&server.url("/"), &client, &extended).is_ok());
+
+        let shortened = MinerConfig {
+            timeout: Duration::from_secs(5),
+            host_timeouts: HashMap::from([("127.0.0.1".to_string(), Duration::from_millis(200))]),
+            ..Default::default()
+        };
+        assert!(find_rss_feeds_with_config(&server.url("/"), &client, &shortened).is_err());
+    }
+
+    #[test]
+    fn test_parse_host_timeout() {
+        assert_eq!(
+            parse_host_timeout("Slow.Example.com=60").unwrap(),
+            ("slow.example.com".to_string(), Duration::from_secs(60))
+        );
+        assert!(parse_host_timeout("slow.example.com").is_err());
+        assert!(parse_host_timeout("slow.example.com=soon").is_err());
+        assert!(parse_host_timeout("=60").is_err());
+
+        let config =
+            MinerConfig::from_toml_str("[host_timeouts]\n\"Slow.Example.com\" = 45").unwrap();
+        assert_eq!(
+            config.timeout_for("https://slow.example.com/feed"),
+            Duration::from_secs(45)
+        );
+    }
+
+    #[test]
+    fn test_source_title_taken_from_page_head() {
+        let html = r#"<html><head>
+            <title>
+                Example   Engineering Blog
+            </title>
+            <link rel="alternate" type="application/rss+xml" title="RSS" href="/feed.xml">
+            </head><body><title>Not this</title></body></html>"#;
+        let server = MockServer::with_routes(vec![
+            ("/", "text/html", html.to_string()),
+            ("/feed.xml", "application/rss+xml", RSS_SAMPLE.to_string()),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        let feeds = find_rss_feeds(&server.url("/"), &client).unwrap();
+        assert_eq!(feeds[0].title, "RSS");
+        assert_eq!(
+            feeds[0].source_title.as_deref(),
+            Some("Example Engineering Blog")
+        );
+
+        let json = serde_json::to_value(&feeds[0]).unwrap();
+        assert_eq!(json["source_title"], "Example Engineering Blog");
+
+        let feeds = find_feeds_in_html(html, &server.url("/"), &client).unwrap();
+        assert_eq!(
+            feeds[0].source_title.as_deref(),
+            Some("Example Engineering Blog")
+        );
+    }
+
+    #[test]
+    fn test_amp_page_follows_canonical_link() {
+        let amp = r#"<!doctype html><html ⚡ lang="en"><head>
+            <link rel="canonical" href="/post">
+            </head><body></body></html>"#;
+        let canonical = r#"<html><head>
+            <link rel="alternate" type="application/rss+xml" href="/feed.xml">
+            </head><body></body></html>"#;
+        let server = MockServer::with_routes(vec![
+            ("/post/amp", "text/html", amp.to_string()),
+            ("/post", "text/html", canonical.to_string()),
+            ("/feed.xml", "application/rss+xml", RSS_SAMPLE.to_string()),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let config = MinerConfig {
+            probe_paths: Vec::new(),
+            ..Default::default()
+        };
+
+        let feeds = find_rss_feeds_with_config(&server.url("/post/amp"), &client, &config).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].url, server.url("/feed.xml"));
+        assert_eq!(feeds[0].discovery_method, Some(DiscoveryMethod::Link));
+
+        let config = MinerConfig {
+            amp_follow_depth: 0,
+            ..config
+        };
+        assert!(
+            find_rss_feeds_with_config(&server.url("/post/amp"), &client, &config)
+                .unwrap()
+                .is_empty()
+        );
+
+        // Only AMP pages are left for their canonical page
+        assert_eq!(
+            amp_canonical_url(amp, &server.url("/post/amp")),
+            Some(server.url("/post"))
+        );
+        assert_eq!(
+            amp_canonical_url(
+                r#"<html amp><head><link rel="canonical" href="/post/amp"></head></html>"#,
+                &server.url("/post/amp")
+            ),
+            None
+        );
+        assert_eq!(
+            amp_canonical_url(
+                r#"<html lang="en"><head><link rel="canonical" href="/post"></head></html>"#,
+                &server.url("/post/amp")
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_scan_json_ld_discovers_declared_feeds() {
+        let html = r#"<html><head><title>Podcast</title>
+            <script type="application/ld+json">
+            {"@context": "https://schema.org", "@graph": [
+                {"@type": "WebSite", "url": "/", "name": "Example"},
+                {"@type": "PodcastSeries", "webFeed": "/podcast.xml"},
+                {"@type": "WebPage", "potentialAction": {
+                    "@type": "SubscribeAction",
+                    "target": {"@type": "EntryPoint", "urlTemplate": "/atom.xml"}
+                }}
+            ]}
+            </script>
+            <script type="application/ld+json">not json</script>
+            </head><body></body></html>"#;
+        let server = MockServer::with_routes(vec![
+            ("/", "text/html", html.to_string()),
+            (
+                "/podcast.xml",
+                "application/rss+xml",
+                RSS_SAMPLE.to_string(),
+            ),
+            ("/atom.xml", "application/atom+xml", ATOM_SAMPLE.to_string()),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let config = MinerConfig {
+            scan_json_ld: true,
+            probe_paths: Vec::new(),
+            ..Default::default()
+        };
+
+        let feeds = find_rss_feeds_with_config(&server.url("/"), &client, &config).unwrap();
+        let urls: Vec<&str> = feeds.iter().map(|feed| feed.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec![server.url("/podcast.xml"), server.url("/atom.xml")]
+        );
+        assert!(
+            feeds
+                .iter()
+                .all(|feed| feed.discovery_method == Some(DiscoveryMethod::JsonLd))
+        );
+
+        // Off by default
+        let config = MinerConfig {
+            probe_paths: Vec::new(),
+            ..Default::default()
+        };
+        assert!(
+            find_rss_feeds_with_config(&server.url("/"), &client, &config)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_max_inflight_requests_caps_concurrency() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (current, highest) = (Arc::clone(&in_flight), Arc::clone(&peak));
+        let server = MockServer::start(move |_| {
+            let now = current.fetch_add(1, Ordering::SeqCst) + 1;
+            highest.fetch_max(now, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(50));
+            current.fetch_sub(1, Ordering::SeqCst);
+            MockResponse::not_found()
+        });
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let urls: Vec<String> = (0..8).map(|i| server.url(&format!("/{}", i))).collect();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(8)
+            .build()
+            .unwrap();
+
+        let config = MinerConfig {
+            max_inflight_requests: Some(2),
+            ..Default::default()
+        };
+        pool.install(|| find_rss_feeds_parallel_with_config(&urls, &client, &config, false));
+        // Pages and common paths alike were requested two at a time at most
+        assert!(server.requests().len() > urls.len());
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+        assert!(peak.load(Ordering::SeqCst) >= 1);
+
+        peak.store(0, Ordering::SeqCst);
+        pool.install(|| {
+            find_rss_feeds_parallel_with_config(&urls, &client, &MinerConfig::default(), false)
+        });
+        assert!(peak.load(Ordering::SeqCst) > 2);
+    }
+
+    #[test]
+    fn test_url_exclusions_by_host_and_exact_url() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "# never scan these").unwrap();
+        writeln!(file, "spam.example.com").unwrap();
+        writeln!(file, "https://mastodon.example/@noisy").unwrap();
+        let exclusions = UrlExclusions::from_file(file.path()).unwrap();
+        assert_eq!(exclusions.len(), 2);
+
+        assert!(exclusions.excludes("https://spam.example.com"));
+        assert!(exclusions.excludes("http://www.spam.example.com/"));
+        assert!(exclusions.excludes("https://mastodon.example/@noisy"));
+        assert!(!exclusions.excludes("https://mastodon.example/@quiet"));
+        assert!(!exclusions.excludes("https://mastodon.example"));
+        assert!(!exclusions.excludes("https://example.com"));
+
+        // Matched before inputs are reduced to their domain
+        let mut input = NamedTempFile::new().unwrap();
+        writeln!(input, "https://mastodon.example/@noisy").unwrap();
+        writeln!(input, "www.spam.example.com/page").unwrap();
+        writeln!(input, "https://example.com/blog").unwrap();
+        let options = InputOptions {
+            exclusions,
+            ..Default::default()
+        };
+        assert_eq!(
+            read_urls_from_file_with_options(input.path(), &options).unwrap(),
+            vec!["https://example.com"]
+        );
+    }
+
+    #[test]
+    fn test_sample_inputs_is_deterministic_per_seed() {
+        let urls: Vec<String> = (0..50)
+            .map(|i| format!("https://site{}.example.com", i))
+            .collect();
+
+        let sample = sample_inputs(urls.clone(), 5, 42);
+        assert_eq!(sample.len(), 5);
+        assert_eq!(sample, sample_inputs(urls.clone(), 5, 42));
+        assert_ne!(sample, sample_inputs(urls.clone(), 5, 7));
+
+        // Picked URLs keep their input order and are distinct
+        let positions: Vec<usize> = sample
+            .iter()
+            .map(|url| urls.iter().position(|u| u == url).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+
+        assert_eq!(sample_inputs(urls.clone(), 50, 42), urls);
+        assert_eq!(sample_inputs(urls.clone(), 80, 42), urls);
+        assert!(sample_inputs(urls, 0, 42).is_empty());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_create_sqlite_db_upserts_by_url() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut blog = RssFeed::new(
+            "Blog",
+            "https://example.com/feed.xml",
+            "https://example.com",
+            FeedType::Rss,
+        );
+        blog.categories = vec!["Tech".to_string()];
+        blog.discovery_method = Some(DiscoveryMethod::CommonPath);
+        let news = RssFeed::new(
+            "News",
+            "https://news.example.com/atom.xml",
+            "https://news.example.com",
+            FeedType::Atom,
+        );
+
+        create_sqlite_db(&[blog.clone(), news], temp_dir.path()).unwrap();
+        blog.title = "Renamed Blog".to_string();
+        create_sqlite_db(&[blog], temp_dir.path()).unwrap();
+
+        let connection =
+            rusqlite::Connection::open(temp_dir.path().join(DEFAULT_SQLITE_FILE_NAME)).unwrap();
+        let count: i64 = connection
+            .query_row("SELECT COUNT(*) FROM feeds", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 2);
+
+        let (title, feed_type, method, categories): (String, String, String, String) = connection
+            .query_row(
+                "SELECT title, feed_type, discovery_method, categories FROM feeds WHERE url = ?1",
+                ["https://example.com/feed.xml"],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!(title, "Renamed Blog");
+        assert_eq!(feed_type, "rss");
+        assert_eq!(method, "common_path");
+        assert_eq!(categories, r#"["Tech"]"#);
+    }
+
+    #[test]
+    fn test_report_progress_callback() {
+        let server = MockServer::with_routes(vec![
+            ("/", "text/html", "<html></html>".to_string()),
+            ("/feed", "application/rss+xml", RSS_SAMPLE.to_string()),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let urls = vec![server.url("/"), "http://127.0.0.1:1/".to_string()];
+        let updates = Mutex::new(Vec::new());
+
+        let report = find_rss_feeds_parallel_report_with_progress(
+            &urls,
+            &client,
+            &MinerConfig::default(),
+            false,
+            |progress| updates.lock().unwrap().push(progress),
+        );
+
+        let mut updates = updates.into_inner().unwrap();
+        updates.sort_by_key(|progress| progress.processed);
+        assert_eq!(updates.len(), 2);
+        assert!(updates.iter().all(|progress| progress.total == 2));
+        assert_eq!(updates[1].processed, 2);
+        let feeds_found = updates.iter().map(|progress| progress.feeds_found).max();
+        assert_eq!(feeds_found, Some(report.feeds.len()));
+        assert_eq!(report.feeds.len(), 1);
+    }
+
+    #[test]
+    fn test_group_children_sorted_by_title() {
+        let feeds: Vec<RssFeed> = ["zebra", "Apple", "mango"]
+            .iter()
+            .map(|title| {
+                RssFeed::new(
+                    *title,
+                    format!("https://example.com/{}.xml", title),
+                    "https://example.com",
+                    FeedType::Rss,
+                )
+            })
+            .collect();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_path = temp_dir.path().join("sorted.opml");
+        let options = OpmlOptions {
+            group_by_host: true,
+            sort_group_children: true,
+            ..Default::default()
+        };
+
+        create_opml_file_with_options(&feeds, &output_path, &options).unwrap();
+
+        let mut file = fs::File::open(&output_path).unwrap();
+        let opml = opml::OPML::from_reader(&mut file).unwrap();
+        let titles: Vec<&str> = opml.body.outlines[0]
+            .outlines
+            .iter()
+            .map(|outline| outline.text.as_str())
+            .collect();
+        assert_eq!(titles, vec!["Apple", "mango", "zebra"]);
+    }
+
+    #[test]
+    fn test_send_referer_unlocks_hotlink_protected_feed() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/" => MockResponse::new(
+                200,
+                "text/html",
+                r#"<html><head><link type="application/rss+xml" href="/feed.xml"></head></html>"#,
+            ),
+            "/feed.xml"
+                if request
+                    .header("referer")
+                    .is_some_and(|referer| referer.starts_with("http://127.0.0.1")) =>
+            {
+                MockResponse::new(200, "application/rss+xml", RSS_SAMPLE)
+            }
+            "/feed.xml" => MockResponse::new(403, "text/plain", "Forbidden"),
+            _ => MockResponse::not_found(),
+        });
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        assert!(
+            find_rss_feeds(&server.url("/"), &client)
+                .unwrap()
+                .is_empty()
+        );
+
+        let config = MinerConfig {
+            send_referer: true,
+            ..Default::default()
+        };
+        let feeds = find_rss_feeds_with_config(&server.url("/"), &client, &config).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].url, server.url("/feed.xml"));
+        let referers: Vec<Option<String>> = server
+            .requests()
+            .iter()
+            .filter(|request| request.path == "/feed.xml")
+            .map(|request| request.header("referer").map(str::to_string))
+            .collect();
+        assert_eq!(
+            referers.last().unwrap().as_deref(),
+            Some(server.url("/").as_str())
+        );
+    }
+
+    #[test]
+    fn test_build_client_protocol_options() {
+        let server = MockServer::with_routes(vec![("/", "text/html", "ok".to_string())]);
+
+        let config = MinerConfig {
+            http1_only: true,
+            ..Default::default()
+        };
+        let client = build_client(&config).unwrap();
+        assert!(client.get(server.url("/")).send().is_ok());
+
+        // The mock server only speaks HTTP/1.1, so an h2-only client fails
+        let config = MinerConfig {
+            http2_prior_knowledge: true,
+            ..Default::default()
+        };
+        let client = build_client(&config).unwrap();
+        assert!(client.get(server.url("/")).send().is_err());
+
+        let config = MinerConfig {
+            http2_prior_knowledge: true,
+            http1_only: true,
+            ..Default::default()
+        };
+        assert!(build_client(&config).is_err());
+    }
+
+    #[test]
+    fn test_atomic_write_preserves_file_when_disk_fills() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("feeds.opml");
+        fs::write(&path, "good contents").unwrap();
+
+        let err = write_file_atomically(&path, "OPML", |file| {
+            file.write_all(b"<opml>partial")?;
+            Err(io::Error::from(io::ErrorKind::StorageFull))
+        })
+        .unwrap_err()
+        .to_string();
+
+        assert!(err.contains("Disk full"), "{}", err);
+        assert!(err.contains(&path.display().to_string()), "{}", err);
+        assert!(err.contains("left unchanged"), "{}", err);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "good contents");
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+
+        let fresh = temp_dir.path().join("new.opml");
+        let err = write_file_atomically(&fresh, "OPML", |_| {
+            Err(io::Error::from(io::ErrorKind::StorageFull))
+        })
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("no file was written"), "{}", err);
+        assert!(!fresh.exists());
+
+        write_file_atomically(&path, "OPML", |file| file.write_all(b"new contents")).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new contents");
+    }
+
+    #[test]
+    fn test_feed_url_denylist_excludes_matches() {
+        let server = MockServer::with_routes(vec![
+            (
+                "/",
+                "text/html",
+                r#"<html><head>
+                <link type="application/rss+xml" href="/feed.xml">
+                <link type="application/rss+xml" href="/tracking/feed.xml">
+                <link type="application/atom+xml" href="/comments.atom">
+                </head></html>"#
+                    .to_string(),
+            ),
+            ("/feed.xml", "application/rss+xml", RSS_SAMPLE.to_string()),
+            (
+                "/tracking/feed.xml",
+                "application/rss+xml",
+                RSS_SAMPLE.to_string(),
+            ),
+            (
+                "/comments.atom",
+                "application/atom+xml",
+                ATOM_SAMPLE.to_string(),
+            ),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let config = MinerConfig {
+            feed_url_denylist: vec!["/TRACKING/".to_string(), "http://*.atom".to_string()],
+            ..Default::default()
+        };
 
-        for path in common_paths {
-            if let Ok(feed_url) = resolve_url(url, path) {
-                if let Some(feed_type) = validate_rss_feed(&feed_url, client) {
-                    feeds.push(RssFeed {
-                        title: extract_title_from_url(url),
-                        url: feed_url,
-                        html_url: url.to_string(),
-                        feed_type,
-                    });
-                    break; // Only add the first valid common feed found
-                }
-            }
-        }
+        let feeds = find_rss_feeds_with_config(&server.url("/"), &client, &config).unwrap();
+
+        let urls: Vec<&str> = feeds.iter().map(|feed| feed.url.as_str()).collect();
+        assert_eq!(urls, vec![server.url("/feed.xml")]);
     }
 
-    Ok(feeds)
-}
+    #[test]
+    fn test_url_matches_pattern() {
+        let url = "https://feeds.example.com/rss/all.xml";
+        assert!(url_matches_pattern(url, "example.com/rss"));
+        assert!(url_matches_pattern(url, "https://*.example.com/*"));
+        assert!(url_matches_pattern(url, "*all.xml"));
+        assert!(!url_matches_pattern(url, "*.atom"));
+        assert!(!url_matches_pattern(url, "feeds.example.com*"));
+        assert!(!url_matches_pattern(url, ""));
+    }
 
-pub fn find_rss_feeds_parallel(urls: &[String], client: &Client, verbose: bool) -> Vec<RssFeed> {
-    urls.par_iter()
-        .filter_map(|url| {
-            if verbose {
-                println!("Processing: {}", url);
-            }
-            match find_rss_feeds(url, client) {
-                Ok(feeds) => {
-                    if !feeds.is_empty() {
-                        if verbose {
-                            println!("  Found {} feed(s) for {}", feeds.len(), url);
-                        }
-                        Some(feeds)
-                    } else {
-                        if verbose {
-                            println!("  No feeds found for {}", url);
-                        }
-                        None
-                    }
-                }
-                Err(e) => {
-                    if verbose {
-                        eprintln!("  Error processing {}: {}", url, e);
-                    }
-                    None
-                }
+    #[test]
+    fn test_parsed_feed_cached_by_etag() {
+        let etag = Arc::new(Mutex::new("\"v1\"".to_string()));
+        let served = Arc::clone(&etag);
+        let server = MockServer::start(move |request| match request.path.as_str() {
+            "/feed.xml" => {
+                let mut response = MockResponse::new(200, "application/rss+xml", RSS_SAMPLE);
+                response
+                    .headers
+                    .push(("ETag".to_string(), served.lock().unwrap().clone()));
+                response
             }
-        })
-        .flatten()
-        .collect()
-}
+            _ => MockResponse::not_found(),
+        });
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let config = MinerConfig::default();
+        let stats = RunStats::default();
+        let feed_url = server.url("/feed.xml");
 
-fn resolve_url(base: &str, href: &str) -> Result<String> {
-    let base_url = Url::parse(base)?;
-    let resolved = base_url.join(href)?;
-    Ok(resolved.to_string())
-}
+        let first = validate_rss_feed(&feed_url, None, &client, &config, &stats).unwrap();
+        let second = validate_rss_feed(&feed_url, None, &client, &config, &stats).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(stats.feeds_parsed.load(Ordering::Relaxed), 1);
 
-fn validate_rss_feed(feed_url: &str, client: &Client) -> Option<FeedType> {
-    // Try to fetch and parse the feed
-    match client.get(feed_url).send() {
-        Ok(response) => {
-            if !response.status().is_success() {
-                return None;
+        *etag.lock().unwrap() = "\"v2\"".to_string();
+        validate_rss_feed(&feed_url, None, &client, &config, &stats).unwrap();
+        assert_eq!(stats.feeds_parsed.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_concurrent_validations_of_one_feed_share_a_fetch() {
+        let server = MockServer::start(|request| match request.path.as_str() {
+            "/feed.xml" => {
+                thread::sleep(Duration::from_millis(200));
+                MockResponse::new(200, "application/rss+xml", RSS_SAMPLE)
             }
+            _ => MockResponse::not_found(),
+        });
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let config = MinerConfig::default();
+        let stats = RunStats::default();
+        let feed_url = server.url("/feed.xml");
 
-            match response.text() {
-                Ok(content) => {
-                    // Try to parse as RSS
-                    if rss::Channel::read_from(content.as_bytes()).is_ok() {
-                        return Some(FeedType::Rss);
-                    }
+        let results: Vec<Option<FeedInfo>> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..4)
+                .map(|_| {
+                    scope.spawn(|| validate_rss_feed(&feed_url, None, &client, &config, &stats))
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .collect()
+        });
 
-                    // Try to parse as Atom
-                    if atom_syndication::Feed::read_from(content.as_bytes()).is_ok() {
-                        return Some(FeedType::Atom);
-                    }
+        assert!(
+            results
+                .iter()
+                .all(|info| info.as_ref() == results[0].as_ref())
+        );
+        assert_eq!(results[0].as_ref().unwrap().title, "Example RSS");
+        assert_eq!(server.requests().len(), 1);
+        assert_eq!(stats.feeds_parsed.load(Ordering::Relaxed), 1);
+        assert!(stats.in_flight.lock().unwrap().is_empty());
+    }
 
-                    None
-                }
-                Err(_) => None,
+    #[test]
+    fn test_trace_redirects_reports_each_hop() {
+        let server = MockServer::start(|request| {
+            let redirect = |status, location: &str| {
+                let mut response = MockResponse::new(status, "text/plain", "moved");
+                response
+                    .headers
+                    .push(("Location".to_string(), location.to_string()));
+                response
+            };
+            match request.path.as_str() {
+                "/old.xml" => redirect(301, "/moved.xml"),
+                "/moved.xml" => redirect(302, "feeds/current.xml"),
+                "/feeds/current.xml" => MockResponse::new(200, "application/rss+xml", RSS_SAMPLE),
+                _ => MockResponse::not_found(),
             }
-        }
-        Err(_) => None,
-    }
-}
+        });
+        let client = build_trace_client(&MinerConfig::default()).unwrap();
 
-fn extract_head_html(html: &str) -> Option<&str> {
-    let lower = html.to_ascii_lowercase();
-    let head_start = lower.find("<head")?;
-    let tag_end = lower[head_start..].find('>')? + head_start + 1;
-    let head_end = lower[tag_end..].find("</head")? + tag_end;
-    Some(&html[tag_end..head_end])
-}
+        let hops = trace_redirects(&server.url("/old.xml"), &client).unwrap();
+        let hops: Vec<(u16, String)> = hops.into_iter().map(|hop| (hop.status, hop.url)).collect();
+        assert_eq!(
+            hops,
+            vec![
+                (301, server.url("/old.xml")),
+                (302, server.url("/moved.xml")),
+                (200, server.url("/feeds/current.xml")),
+            ]
+        );
 
-fn extract_title_from_url(url: &str) -> String {
-    Url::parse(url)
-        .ok()
-        .and_then(|u| u.host_str().map(String::from))
-        .unwrap_or_else(|| "Unknown".to_string())
-}
+        let hops = trace_redirects(&server.url("/feeds/current.xml"), &client).unwrap();
+        assert_eq!(hops.len(), 1);
+    }
 
-fn normalize_to_domain_url(input: &str) -> String {
-    if let Ok(url) = Url::parse(input) {
-        if let Some(host) = url.host_str() {
-            let mut base = format!("{}://{}", url.scheme(), host);
-            if let Some(port) = url.port() {
-                base.push(':');
-                base.push_str(&port.to_string());
+    #[test]
+    fn test_blocked_cross_host_feed_redirect_surfaces_target() {
+        let target = MockServer::with_routes(vec![(
+            "/feed.xml",
+            "application/rss+xml",
+            RSS_SAMPLE.to_string(),
+        )]);
+        // Same port, different host name, so the redirect crosses hosts
+        let moved_url = target.url("/feed.xml").replace("127.0.0.1", "localhost");
+        let location = moved_url.clone();
+        let origin = MockServer::start(move |request| match request.path.as_str() {
+            "/" => MockResponse::new(
+                200,
+                "text/html",
+                r#"<html><head><link rel="alternate" type="application/rss+xml" href="/old.xml"></head></html>"#,
+            ),
+            "/old.xml" => {
+                let mut response = MockResponse::new(301, "text/plain", "moved");
+                response
+                    .headers
+                    .push(("Location".to_string(), location.clone()));
+                response
             }
-            return base;
-        }
+            _ => MockResponse::not_found(),
+        });
+        let restricted = MinerConfig {
+            same_host_redirects: true,
+            ..Default::default()
+        };
+        let client = build_client(&restricted).unwrap();
+
+        assert!(validate_feed_with_config(&origin.url("/old.xml"), &client, &restricted).is_none());
+
+        let config = MinerConfig {
+            follow_feed_redirects: true,
+            ..restricted
+        };
+        let feeds = find_rss_feeds_with_config(&origin.url("/"), &client, &config).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].url, moved_url);
+        assert_eq!(feeds[0].raw_url, origin.url("/old.xml"));
     }
 
-    input.to_string()
-}
+    #[test]
+    fn test_followed_feed_redirect_keeps_request_headers() {
+        let target = MockServer::with_routes(vec![(
+            "/feed.xml",
+            "application/rss+xml",
+            RSS_SAMPLE.to_string(),
+        )]);
+        let location = target.url("/feed.xml").replace("127.0.0.1", "localhost");
+        let origin = MockServer::start(move |_| {
+            let mut response = MockResponse::new(301, "text/plain", "moved");
+            response
+                .headers
+                .push(("Location".to_string(), location.clone()));
+            response
+        });
+        let config = MinerConfig {
+            same_host_redirects: true,
+            follow_feed_redirects: true,
+            ..Default::default()
+        };
+        let client = build_client(&config).unwrap();
+        let validators = FeedValidators {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: Some("Mon, 01 Jan 2024 00:00:00 GMT".to_string()),
+        };
 
-/// Formats XML string with indentation for better readability.
-///
-/// Takes compact XML and reformats it with proper line breaks and 2-space indentation.
-/// This makes the XML output more human-readable while maintaining semantic equivalence.
-///
-/// # Arguments
-///
-/// * `xml` - A string slice containing the XML to format
-///
-/// # Returns
-///
-/// * `Result<String>` - The formatted XML string, or an error if parsing/formatting fails
-///
-/// # Errors
-///
-/// Returns an error if:
-/// * The input XML is malformed
-/// * XML event writing fails
-/// * UTF-8 conversion fails
-fn pretty_print_xml(xml: &str) -> Result<String> {
-    const INDENT_CHAR: u8 = b' ';
-    const INDENT_SIZE: usize = 2;
+        let page = origin.url("/");
+        let fetched = try_fetch_feed_body(
+            &origin.url("/old.xml"),
+            Some(&page),
+            Some(&validators),
+            &client,
+            &config,
+            &RunStats::new(&config),
+        )
+        .unwrap();
+        assert!(fetched.moved_to.is_some());
+        let requests = target.requests();
+        assert_eq!(requests[0].header("Referer"), Some(page.as_str()));
+        assert_eq!(requests[0].header("If-None-Match"), Some("\"v1\""));
+        assert_eq!(
+            requests[0].header("If-Modified-Since"),
+            Some("Mon, 01 Jan 2024 00:00:00 GMT")
+        );
+    }
 
-    let mut reader = Reader::from_str(xml);
-    reader.config_mut().trim_text(true);
-    
-    let mut writer = Writer::new_with_indent(Cursor::new(Vec::new()), INDENT_CHAR, INDENT_SIZE);
-    
-    loop {
-        match reader.read_event() {
-            Ok(Event::Eof) => break,
-            Ok(event) => {
-                writer.write_event(event).context("Failed to write XML event")?;
-            }
-            Err(e) => return Err(anyhow::anyhow!("Error parsing XML: {}", e)),
-        }
+    #[test]
+    fn test_content_type_validation_mode() {
+        let truncated = r#"<?xml version="1.0"?><rss version="2.0"><channel><title>Cut off"#;
+        let server = MockServer::start(move |request| match request.path.as_str() {
+            "/truncated.xml" => MockResponse::new(200, "application/rss+xml", truncated),
+            "/atom" => MockResponse::new(200, "text/xml; charset=utf-8", ATOM_SAMPLE),
+            "/html-served.xml" => MockResponse::new(200, "text/html", RSS_SAMPLE),
+            _ => MockResponse::not_found(),
+        });
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let strict = MinerConfig::default();
+        let fast = MinerConfig {
+            validation_mode: ValidationMode::ContentType,
+            ..Default::default()
+        };
+        let validate = |path: &str, config: &MinerConfig| {
+            validate_feed_with_config(&server.url(path), &client, config).map(|info| info.feed_type)
+        };
+
+        assert_eq!(validate("/truncated.xml", &strict), None);
+        assert_eq!(validate("/truncated.xml", &fast), Some(FeedType::Rss));
+        assert_eq!(validate("/atom", &fast), Some(FeedType::Atom));
+        assert_eq!(validate("/html-served.xml", &strict), Some(FeedType::Rss));
+        assert_eq!(validate("/html-served.xml", &fast), None);
     }
-    
-    let result = writer.into_inner().into_inner();
-    String::from_utf8(result).context("Failed to convert XML to UTF-8")
-}
 
-pub fn create_opml_file(feeds: &[RssFeed], output_path: &Path) -> Result<()> {
-    create_opml_file_filtered(feeds, output_path, None)
-}
+    #[test]
+    fn test_normalize_feed_scheme() {
+        assert_eq!(
+            normalize_feed_scheme("feed://example.com/rss"),
+            "https://example.com/rss"
+        );
+        assert_eq!(normalize_feed_scheme("feed:https://x/rss"), "https://x/rss");
+        assert_eq!(normalize_feed_scheme("FEED:http://x/rss"), "http://x/rss");
+        assert_eq!(normalize_feed_scheme("https://x/feed"), "https://x/feed");
+        assert_eq!(normalize_feed_scheme("feedback"), "feedback");
 
-pub fn create_opml_file_filtered(
-    feeds: &[RssFeed],
-    output_path: &Path,
-    feed_type_filter: Option<FeedType>,
-) -> Result<()> {
-    let mut opml = opml::OPML::default();
+        assert_eq!(
+            resolve_url("https://example.com/blog", "feed://example.com/rss").unwrap(),
+            "https://example.com/rss"
+        );
 
-    let title = match feed_type_filter {
-        Some(FeedType::Rss) => "RSS Feeds",
-        Some(FeedType::Atom) => "Atom Feeds",
-        None => "RSS and Atom Feeds",
-    };
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "feed://example.com/rss").unwrap();
+        writeln!(temp_file, "feed:https://example.org/atom.xml").unwrap();
+        let urls = read_feed_urls_from_file(temp_file.path()).unwrap();
+        assert_eq!(
+            urls,
+            vec!["https://example.com/rss", "https://example.org/atom.xml"]
+        );
+    }
 
-    opml.head = Some(opml::Head {
-        title: Some(title.to_string()),
-        ..Default::default()
-    });
+    #[test]
+    fn test_input_categories_nest_feeds_in_opml() {
+        let server = MockServer::with_routes(vec![
+            (
+                "/",
+                "text/html",
+                r#"<html><head><link type="application/rss+xml" title="Daily" href="/feed.xml"></head></html>"#
+                    .to_string(),
+            ),
+            ("/feed.xml", "application/rss+xml", RSS_SAMPLE.to_string()),
+        ]);
+        let mut input = NamedTempFile::new().unwrap();
+        writeln!(input, "https://unfiled.example.com").unwrap();
+        writeln!(input, "[News]").unwrap();
+        writeln!(input, "{}", server.url("/")).unwrap();
+        writeln!(input, "[]").unwrap();
+        writeln!(input, "https://after.example.com").unwrap();
+
+        let entries = read_input_entries(input.path()).unwrap();
+        let categories: Vec<Option<&str>> = entries
+            .iter()
+            .map(|entry| entry.category.as_deref())
+            .collect();
+        assert_eq!(categories, vec![None, Some("News"), None]);
+        assert_eq!(
+            parse_urls_from_reader(fs::read(input.path()).unwrap().as_slice())
+                .unwrap()
+                .len(),
+            3
+        );
 
-    let mut outlines = Vec::new();
-    let mut seen_urls = HashSet::with_capacity(feeds.len());
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let overrides = InputOverrides::new(&entries);
+        let mut feeds = overrides.apply_all(find_rss_feeds(&entries[1].url, &client).unwrap());
+        feeds.push(RssFeed::new(
+            "Loose",
+            "https://unfiled.example.com/feed",
+            "https://unfiled.example.com",
+            FeedType::Rss,
+        ));
+        let output = NamedTempFile::new().unwrap();
+        create_opml_file(&feeds, output.path()).unwrap();
 
-    for feed in feeds {
-        // Skip if feed doesn't match the filter
-        if let Some(filter_type) = feed_type_filter {
-            if filter_type != feed.feed_type {
-                continue;
+        let mut file = fs::File::open(output.path()).unwrap();
+        let opml = opml::OPML::from_reader(&mut file).unwrap();
+        let outlines = &opml.body.outlines;
+        assert_eq!(outlines.len(), 2);
+        assert_eq!(outlines[0].text, "News");
+        assert_eq!(outlines[0].outlines.len(), 1);
+        assert_eq!(outlines[0].outlines[0].text, "Daily");
+        assert_eq!(outlines[1].text, "Loose");
+    }
+
+    #[test]
+    fn test_retry_honors_retry_after() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&attempts);
+        let server = MockServer::start(move |_| {
+            if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                let mut response = MockResponse::new(429, "text/plain", "slow down");
+                response
+                    .headers
+                    .push(("Retry-After".to_string(), "1".to_string()));
+                response
+            } else {
+                MockResponse::new(200, "application/rss+xml", RSS_SAMPLE)
             }
-        }
+        });
+        let client = build_client(&MinerConfig::default()).unwrap();
 
-        // Skip duplicate feeds based on URL
-        if seen_urls.contains(&feed.url) {
-            continue;
-        }
-        seen_urls.insert(feed.url.clone());
+        assert!(validate_feed(&server.url("/feed.xml"), &client).is_none());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
 
-        let feed_type_str = match feed.feed_type {
-            FeedType::Rss => "rss",
-            FeedType::Atom => "atom",
+        attempts.store(0, Ordering::SeqCst);
+        let config = MinerConfig {
+            max_retries: 2,
+            ..Default::default()
         };
+        let started = Instant::now();
+        let info = validate_feed_with_config(&server.url("/feed.xml"), &client, &config);
+        assert!(info.is_some());
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
 
-        let outline = opml::Outline {
-            text: feed.title.clone(),
-            r#type: Some(feed_type_str.to_string()),
-            xml_url: Some(feed.url.clone()),
-            html_url: Some(feed.html_url.clone()),
+    #[test]
+    fn test_retry_delay_includes_seeded_jitter() {
+        let config = MinerConfig {
+            max_retry_wait: Duration::from_secs(2),
+            retry_jitter: Duration::from_millis(250),
             ..Default::default()
         };
-        outlines.push(outline);
+        let delays = |seed| {
+            let mut rng = JitterRng::new(seed);
+            (0..200)
+                .map(|_| retry_delay(Duration::from_secs(5), &config, &mut rng))
+                .collect::<Vec<_>>()
+        };
+
+        let first = delays(42);
+        assert_eq!(first, delays(42));
+        assert_ne!(first, delays(43));
+        assert!(first.iter().all(|delay| {
+            (Duration::from_secs(2)..=Duration::from_millis(2250)).contains(delay)
+        }));
+        // Spread over the whole range rather than clustered at one end
+        assert!(
+            first
+                .iter()
+                .any(|delay| *delay < Duration::from_millis(2050))
+        );
+        assert!(
+            first
+                .iter()
+                .any(|delay| *delay > Duration::from_millis(2200))
+        );
+
+        let no_jitter = MinerConfig {
+            retry_jitter: Duration::ZERO,
+            ..config
+        };
+        let mut rng = JitterRng::new(42);
+        assert_eq!(
+            retry_delay(Duration::from_secs(1), &no_jitter, &mut rng),
+            Duration::from_secs(1)
+        );
     }
 
-    opml.body = opml::Body { outlines };
+    #[test]
+    fn test_retry_wait_is_capped() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&attempts);
+        let server = MockServer::start(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let mut response = MockResponse::new(503, "text/plain", "maintenance");
+            response.headers.push((
+                "Retry-After".to_string(),
+                httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(3600)),
+            ));
+            response
+        });
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let config = MinerConfig {
+            max_retries: 1,
+            max_retry_wait: Duration::from_millis(100),
+            ..Default::default()
+        };
 
-    let opml_string = opml.to_string()?;
-    let pretty_xml = pretty_print_xml(&opml_string)?;
-    fs::write(output_path, pretty_xml).context(format!(
-        "Failed to write OPML file: {}",
-        output_path.display()
-    ))?;
+        let started = Instant::now();
+        assert!(validate_feed_with_config(&server.url("/feed.xml"), &client, &config).is_none());
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
 
-    Ok(())
-}
+    #[test]
+    fn test_explicit_http_input_is_not_upgraded() {
+        let server = MockServer::with_routes(vec![
+            (
+                "/",
+                "text/html",
+                r#"<html><head><link rel="alternate" type="application/rss+xml" href="/feed.xml"></head></html>"#
+                    .to_string(),
+            ),
+            ("/feed.xml", "application/rss+xml", RSS_SAMPLE.to_string()),
+        ]);
+        let host = server.url("").trim_start_matches("http://").to_string();
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::io::Write;
-    use tempfile::NamedTempFile;
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "{}", server.url("/")).unwrap();
+        writeln!(file, "{}/blog", host).unwrap();
+        let urls = read_urls_from_file(file.path()).unwrap();
+        assert_eq!(
+            urls,
+            [format!("http://{}", host), format!("https://{}", host)]
+        );
+
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let feeds = find_rss_feeds(&urls[0], &client).unwrap();
+        assert_eq!(feeds[0].url, server.url("/feed.xml"));
+    }
 
     #[test]
-    fn test_read_urls_from_file() {
-        let mut temp_file = NamedTempFile::new().unwrap();
-        writeln!(temp_file, "# Comment line").unwrap();
-        writeln!(temp_file, "https://example.com/path?query=1").unwrap();
-        writeln!(temp_file).unwrap();
-        writeln!(temp_file, "https://test.com/another/path").unwrap();
-        writeln!(temp_file, "https://example.com/dup/path").unwrap();
-        writeln!(temp_file, "  https://trimmed.com/page  ").unwrap();
+    fn test_gzipped_input_matches_plain() {
+        let content = "# Blogs\nhttps://github.blog\n[Rust]\nhttps://www.rust-lang.org/\n";
+        let temp_dir = tempfile::tempdir().unwrap();
+        let plain = temp_dir.path().join("urls.txt");
+        fs::write(&plain, content).unwrap();
+        let gzipped = temp_dir.path().join("more.txt.gz");
+        let mut encoder = flate2::write::GzEncoder::new(
+            fs::File::create(&gzipped).unwrap(),
+            flate2::Compression::default(),
+        );
+        encoder.write_all(content.as_bytes()).unwrap();
+        encoder.finish().unwrap();
 
-        let urls = read_urls_from_file(temp_file.path()).unwrap();
-        assert_eq!(urls.len(), 3);
-        assert_eq!(urls[0], "https://example.com");
-        assert_eq!(urls[1], "https://test.com");
-        assert_eq!(urls[2], "https://trimmed.com");
+        assert_eq!(
+            read_urls_from_file(&gzipped).unwrap(),
+            read_urls_from_file(&plain).unwrap()
+        );
+        assert_eq!(
+            read_input_entries(&gzipped).unwrap(),
+            read_input_entries(&plain).unwrap()
+        );
+
+        let files = input_files_in_dir(temp_dir.path(), false).unwrap();
+        assert_eq!(files, [gzipped, plain]);
     }
 
     #[test]
@@ -361,6 +10679,37 @@ mod tests {
         assert_eq!(title, "example.com");
     }
 
+    #[test]
+    fn test_extract_title_from_ip_url() {
+        assert_eq!(
+            extract_title_from_url("http://192.0.2.7:8080/blog"),
+            "Feed at 192.0.2.7"
+        );
+        assert_eq!(
+            extract_title_from_url("http://[2001:db8::1]/"),
+            "Feed at [2001:db8::1]"
+        );
+    }
+
+    #[test]
+    fn test_reverse_dns_titles_name_ip_sites() {
+        let server = MockServer::with_routes(vec![
+            ("/", "text/html", "<html><head></head></html>".to_string()),
+            ("/feed", "application/rss+xml", RSS_SAMPLE.to_string()),
+        ]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        let feeds = find_rss_feeds(&server.url("/"), &client).unwrap();
+        assert_eq!(feeds[0].title, "Feed at 127.0.0.1");
+
+        let config = MinerConfig {
+            reverse_dns_titles: true,
+            ..Default::default()
+        };
+        let feeds = find_rss_feeds_with_config(&server.url("/"), &client, &config).unwrap();
+        assert_eq!(feeds[0].title, "localhost");
+    }
+
     #[test]
     fn test_extract_title_from_invalid_url() {
         let title = extract_title_from_url("not-a-url");
@@ -384,18 +10733,18 @@ mod tests {
     #[test]
     fn test_create_opml_file() {
         let feeds = vec![
-            RssFeed {
-                title: "Test Feed 1".to_string(),
-                url: "https://example.com/feed1.xml".to_string(),
-                html_url: "https://example.com".to_string(),
-                feed_type: FeedType::Rss,
-            },
-            RssFeed {
-                title: "Test Feed 2".to_string(),
-                url: "https://example.com/feed2.xml".to_string(),
-                html_url: "https://example.com".to_string(),
-                feed_type: FeedType::Atom,
-            },
+            RssFeed::new(
+                "Test Feed 1",
+                "https://example.com/feed1.xml",
+                "https://example.com",
+                FeedType::Rss,
+            ),
+            RssFeed::new(
+                "Test Feed 2",
+                "https://example.com/feed2.xml",
+                "https://example.com",
+                FeedType::Atom,
+            ),
         ];
 
         let temp_file = NamedTempFile::new().unwrap();
@@ -413,38 +10762,160 @@ mod tests {
     }
 
     #[test]
-    fn test_create_opml_file_with_duplicates() {
-        let feeds = vec![
-            RssFeed {
-                title: "Test Feed 1".to_string(),
-                url: "https://example.com/feed1.xml".to_string(),
-                html_url: "https://example.com".to_string(),
-                feed_type: FeedType::Rss,
-            },
-            RssFeed {
-                title: "Test Feed 2".to_string(),
-                url: "https://example.com/feed2.xml".to_string(),
-                html_url: "https://example.com".to_string(),
-                feed_type: FeedType::Atom,
-            },
-            RssFeed {
-                title: "Test Feed 1 Duplicate".to_string(),
-                url: "https://example.com/feed1.xml".to_string(), // Duplicate URL
-                html_url: "https://example.com".to_string(),
-                feed_type: FeedType::Rss,
-            },
-            RssFeed {
-                title: "Test Feed 3".to_string(),
-                url: "https://example.com/feed3.xml".to_string(),
-                html_url: "https://example.com".to_string(),
-                feed_type: FeedType::Rss,
+    fn test_opml_output_stores_cache_validators() {
+        let server = MockServer::start(|_| {
+            let mut response = MockResponse::new(200, "application/rss+xml", RSS_SAMPLE);
+            response
+                .headers
+                .push(("ETag".to_string(), "\"v1\"".to_string()));
+            response.headers.push((
+                "Last-Modified".to_string(),
+                "Wed, 21 Oct 2015 07:28:00 GMT".to_string(),
+            ));
+            response
+        });
+        let client = build_client(&MinerConfig::default()).unwrap();
+        let info = validate_feed(&server.url("/feed.xml"), &client).unwrap();
+        assert_eq!(info.etag.as_deref(), Some("\"v1\""));
+
+        let feed = discovered_feed(
+            "Feed",
+            server.url("/feed.xml"),
+            &server.base,
+            info,
+            DiscoveryMethod::Link,
+        );
+        let expected = HashMap::from([(
+            server.url("/feed.xml"),
+            FeedValidators {
+                etag: Some("\"v1\"".to_string()),
+                last_modified: Some("Wed, 21 Oct 2015 07:28:00 GMT".to_string()),
             },
-            RssFeed {
-                title: "Test Feed 2 Duplicate".to_string(),
-                url: "https://example.com/feed2.xml".to_string(), // Duplicate URL
-                html_url: "https://example.com".to_string(),
-                feed_type: FeedType::Atom,
+        )]);
+
+        let file = NamedTempFile::new().unwrap();
+        create_opml_file(std::slice::from_ref(&feed), file.path()).unwrap();
+        let xml = fs::read_to_string(file.path()).unwrap();
+        assert!(xml.contains(r#"etag="&quot;v1&quot;""#));
+        assert!(xml.contains(r#"lastModified="Wed, 21 Oct 2015 07:28:00 GMT""#));
+        assert_eq!(read_feed_validators(file.path()).unwrap(), expected);
+
+        let mut writer = OpmlStreamWriter::new(Vec::new(), &OpmlOptions::default()).unwrap();
+        writer.write_feed(&feed).unwrap();
+        fs::write(file.path(), writer.finish().unwrap()).unwrap();
+        assert_eq!(read_feed_validators(file.path()).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_check_opml_feeds_revalidates_conditionally() {
+        let server = MockServer::start(|req| {
+            if req.path == "/gone.xml" {
+                return MockResponse::not_found();
+            }
+            if req.header("if-none-match") == Some("\"v1\"") {
+                return MockResponse::new(304, "application/rss+xml", "");
+            }
+            MockResponse::new(200, "application/rss+xml", RSS_SAMPLE)
+        });
+        let file = NamedTempFile::new().unwrap();
+        fs::write(
+            file.path(),
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<opml version="2.0">
+  <body>
+    <outline text="Cached" type="rss" xmlUrl="{0}/cached.xml" etag="&quot;v1&quot;"/>
+    <outline text="Fresh" type="rss" xmlUrl="{0}/fresh.xml"/>
+    <outline text="Gone" type="rss" xmlUrl="{0}/gone.xml"/>
+  </body>
+</opml>"#,
+                server.base
+            ),
+        )
+        .unwrap();
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        let mut health = check_opml_feeds(file.path(), &client).unwrap();
+        health.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            health,
+            vec![
+                (server.url("/cached.xml"), FeedHealth::Unchanged),
+                (server.url("/fresh.xml"), FeedHealth::Live),
+                (server.url("/gone.xml"), FeedHealth::Dead),
+            ]
+        );
+
+        let requests = server.requests();
+        let header = |path: &str| {
+            requests
+                .iter()
+                .find(|request| request.path == path)
+                .unwrap()
+                .header("if-none-match")
+                .map(str::to_string)
+        };
+        assert_eq!(header("/cached.xml").as_deref(), Some("\"v1\""));
+        assert_eq!(header("/fresh.xml"), None);
+    }
+
+    #[test]
+    fn test_check_feeds_drops_validators_no_longer_sent() {
+        let server =
+            MockServer::start(|_| MockResponse::new(200, "application/rss+xml", RSS_SAMPLE));
+        let url = server.url("/feed.xml");
+        let mut validators = HashMap::from([(
+            url.clone(),
+            FeedValidators {
+                etag: Some("\"stale\"".to_string()),
+                last_modified: None,
             },
+        )]);
+        let client = build_client(&MinerConfig::default()).unwrap();
+
+        let health = check_feeds(
+            HashSet::from([url.clone()]),
+            &mut validators,
+            &client,
+            &MinerConfig::default(),
+        );
+        assert_eq!(health[&url], FeedHealth::Live);
+        assert!(validators.is_empty());
+    }
+
+    #[test]
+    fn test_create_opml_file_with_duplicates() {
+        let feeds = vec![
+            RssFeed::new(
+                "Test Feed 1",
+                "https://example.com/feed1.xml",
+                "https://example.com",
+                FeedType::Rss,
+            ),
+            RssFeed::new(
+                "Test Feed 2",
+                "https://example.com/feed2.xml",
+                "https://example.com",
+                FeedType::Atom,
+            ),
+            RssFeed::new(
+                "Test Feed 1 Duplicate",
+                "https://example.com/feed1.xml", // Duplicate URL
+                "https://example.com",
+                FeedType::Rss,
+            ),
+            RssFeed::new(
+                "Test Feed 3",
+                "https://example.com/feed3.xml",
+                "https://example.com",
+                FeedType::Rss,
+            ),
+            RssFeed::new(
+                "Test Feed 2 Duplicate",
+                "https://example.com/feed2.xml", // Duplicate URL
+                "https://example.com",
+                FeedType::Atom,
+            ),
         ];
 
         let temp_file = NamedTempFile::new().unwrap();
@@ -453,16 +10924,16 @@ mod tests {
         create_opml_file(&feeds, output_path).unwrap();
 
         let content = fs::read_to_string(output_path).unwrap();
-        
+
         // Should contain first occurrence of each feed
         assert!(content.contains("Test Feed 1"));
         assert!(content.contains("Test Feed 2"));
         assert!(content.contains("Test Feed 3"));
-        
+
         // Should NOT contain duplicate titles
         assert!(!content.contains("Test Feed 1 Duplicate"));
         assert!(!content.contains("Test Feed 2 Duplicate"));
-        
+
         // Count occurrences of each URL - should appear only once
         assert_eq!(content.matches("https://example.com/feed1.xml").count(), 1);
         assert_eq!(content.matches("https://example.com/feed2.xml").count(), 1);
@@ -472,24 +10943,24 @@ mod tests {
     #[test]
     fn test_create_opml_file_rss_only() {
         let feeds = vec![
-            RssFeed {
-                title: "RSS Feed 1".to_string(),
-                url: "https://example.com/rss1.xml".to_string(),
-                html_url: "https://example.com".to_string(),
-                feed_type: FeedType::Rss,
-            },
-            RssFeed {
-                title: "Atom Feed 1".to_string(),
-                url: "https://example.com/atom1.xml".to_string(),
-                html_url: "https://example.com".to_string(),
-                feed_type: FeedType::Atom,
-            },
-            RssFeed {
-                title: "RSS Feed 2".to_string(),
-                url: "https://example.com/rss2.xml".to_string(),
-                html_url: "https://example.com".to_string(),
-                feed_type: FeedType::Rss,
-            },
+            RssFeed::new(
+                "RSS Feed 1",
+                "https://example.com/rss1.xml",
+                "https://example.com",
+                FeedType::Rss,
+            ),
+            RssFeed::new(
+                "Atom Feed 1",
+                "https://example.com/atom1.xml",
+                "https://example.com",
+                FeedType::Atom,
+            ),
+            RssFeed::new(
+                "RSS Feed 2",
+                "https://example.com/rss2.xml",
+                "https://example.com",
+                FeedType::Rss,
+            ),
         ];
 
         let temp_file = NamedTempFile::new().unwrap();
@@ -504,11 +10975,11 @@ mod tests {
         assert!(content.contains("RSS Feed 2"));
         assert!(content.contains("https://example.com/rss1.xml"));
         assert!(content.contains("https://example.com/rss2.xml"));
-        
+
         // Should NOT contain Atom feeds
         assert!(!content.contains("Atom Feed 1"));
         assert!(!content.contains("https://example.com/atom1.xml"));
-        
+
         // Should have appropriate title
         assert!(content.contains("RSS Feeds"));
     }
@@ -516,24 +10987,24 @@ mod tests {
     #[test]
     fn test_create_opml_file_atom_only() {
         let feeds = vec![
-            RssFeed {
-                title: "RSS Feed 1".to_string(),
-                url: "https://example.com/rss1.xml".to_string(),
-                html_url: "https://example.com".to_string(),
-                feed_type: FeedType::Rss,
-            },
-            RssFeed {
-                title: "Atom Feed 1".to_string(),
-                url: "https://example.com/atom1.xml".to_string(),
-                html_url: "https://example.com".to_string(),
-                feed_type: FeedType::Atom,
-            },
-            RssFeed {
-                title: "Atom Feed 2".to_string(),
-                url: "https://example.com/atom2.xml".to_string(),
-                html_url: "https://example.com".to_string(),
-                feed_type: FeedType::Atom,
-            },
+            RssFeed::new(
+                "RSS Feed 1",
+                "https://example.com/rss1.xml",
+                "https://example.com",
+                FeedType::Rss,
+            ),
+            RssFeed::new(
+                "Atom Feed 1",
+                "https://example.com/atom1.xml",
+                "https://example.com",
+                FeedType::Atom,
+            ),
+            RssFeed::new(
+                "Atom Feed 2",
+                "https://example.com/atom2.xml",
+                "https://example.com",
+                FeedType::Atom,
+            ),
         ];
 
         let temp_file = NamedTempFile::new().unwrap();
@@ -548,11 +11019,11 @@ mod tests {
         assert!(content.contains("Atom Feed 2"));
         assert!(content.contains("https://example.com/atom1.xml"));
         assert!(content.contains("https://example.com/atom2.xml"));
-        
+
         // Should NOT contain RSS feeds
         assert!(!content.contains("RSS Feed 1"));
         assert!(!content.contains("https://example.com/rss1.xml"));
-        
+
         // Should have appropriate title
         assert!(content.contains("Atom Feeds"));
     }
@@ -564,7 +11035,6 @@ pub mod python {
     use super::*;
     use pyo3::prelude::*;
     use std::collections::HashMap;
-    use std::time::Duration;
 
     /// Python wrapper for RssFeed
     #[pyclass]
@@ -586,10 +11056,7 @@ pub mod python {
                 title: feed.title,
                 url: feed.url,
                 html_url: feed.html_url,
-                feed_type: match feed.feed_type {
-                    FeedType::Rss => "rss".to_string(),
-                    FeedType::Atom => "atom".to_string(),
-                },
+                feed_type: feed.feed_type.as_str().to_string(),
             }
         }
     }
@@ -614,9 +11081,7 @@ pub mod python {
     }
 
     fn build_client() -> PyResult<Client> {
-        Client::builder()
-            .timeout(Duration::from_secs(10))
-            .build()
+        super::build_client(&MinerConfig::default())
             .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("{}", e)))
     }
 
@@ -683,6 +11148,37 @@ pub mod python {
         Ok((feeds, statuses))
     }
 
+    /// Iterator over feeds as a background search finds them
+    #[pyclass]
+    pub struct PyFeedStream {
+        stream: FeedStream,
+    }
+
+    #[pymethods]
+    impl PyFeedStream {
+        fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+            slf
+        }
+
+        fn __next__(&self, py: Python<'_>) -> Option<PyRssFeed> {
+            // Let other Python threads run while waiting for the next feed
+            let feed = py.detach(|| self.stream.next_feed());
+            feed.map(PyRssFeed::from)
+        }
+    }
+
+    /// Find RSS/Atom feeds from multiple URLs in parallel, yielding each
+    /// feed as soon as it is found
+    #[pyfunction]
+    #[pyo3(signature = (urls, verbose=false))]
+    fn find_feeds_stream(urls: Vec<String>, verbose: bool) -> PyResult<PyFeedStream> {
+        let client = build_client()?;
+        let receiver = find_rss_feeds_stream(urls, client, MinerConfig::default(), verbose);
+        Ok(PyFeedStream {
+            stream: FeedStream::new(receiver),
+        })
+    }
+
     /// Read URLs from a text file
     #[pyfunction]
     fn read_urls(file_path: String) -> PyResult<Vec<String>> {
@@ -695,15 +11191,13 @@ pub mod python {
     fn convert_py_feeds_to_rust(feeds: Vec<PyRssFeed>) -> Vec<RssFeed> {
         feeds
             .into_iter()
-            .map(|py_feed| RssFeed {
-                title: py_feed.title,
-                url: py_feed.url,
-                html_url: py_feed.html_url,
-                feed_type: if py_feed.feed_type == "rss" {
-                    FeedType::Rss
-                } else {
-                    FeedType::Atom
-                },
+            .map(|py_feed| {
+                let feed_type = match py_feed.feed_type.as_str() {
+                    "rss" => FeedType::Rss,
+                    "unknown" => FeedType::Unknown,
+                    _ => FeedType::Atom,
+                };
+                RssFeed::new(py_feed.title, py_feed.url, py_feed.html_url, feed_type)
             })
             .collect()
     }
@@ -739,8 +11233,10 @@ pub mod python {
     #[pymodule]
     fn rss_miner(m: &Bound<'_, PyModule>) -> PyResult<()> {
         m.add_class::<PyRssFeed>()?;
+        m.add_class::<PyFeedStream>()?;
         m.add_function(wrap_pyfunction!(find_feeds, m)?)?;
         m.add_function(wrap_pyfunction!(find_feeds_parallel, m)?)?;
+        m.add_function(wrap_pyfunction!(find_feeds_stream, m)?)?;
         m.add_function(wrap_pyfunction!(read_urls, m)?)?;
         m.add_function(wrap_pyfunction!(create_opml, m)?)?;
         m.add_function(wrap_pyfunction!(create_opml_rss_only, m)?)?;
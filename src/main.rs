@@ -1,8 +1,28 @@
-use anyhow::Result;
+use anyhow::{Context, Result, bail};
 use clap::{Parser, ValueEnum};
-use reqwest::blocking::Client;
-use rss_miner::{create_opml_file_filtered, find_rss_feeds_parallel, read_urls_from_file, FeedType};
+use indicatif::{ProgressBar, ProgressStyle};
+use rayon::prelude::*;
+use rss_miner::{
+    Checkpoint, DEFAULT_BOOKMARKS_FILE_NAME, DEFAULT_JSON_FILE_NAME, DEFAULT_MAX_TITLE_LENGTH,
+    DEFAULT_OPML_FILE_NAME, DEFAULT_PROBE_PATHS, DEFAULT_READING_LIST_FILE_NAME,
+    DEFAULT_URLS_FILE_NAME, FeedType, InputOptions, InputOverrides, KnownSite, MinerConfig,
+    OpmlOptions, OpmlStreamWriter, Progress, ReaderCompat, RssFeed, TitleTransform, TlsVersion,
+    UrlExclusions, ValidationMode, WwwPreference, append_to_opml_file, build_client,
+    build_trace_client, collapse_www_duplicates, create_bookmarks_html, create_json_file,
+    create_opml_file_with_options, create_opml_files_by_host, create_opml_files_from_template,
+    create_parent_dirs, create_reading_list_rss, create_url_list_file, dedupe_feeds_with_report,
+    dedupe_similar_titles, exclude_baseline_feeds, find_rss_feeds_parallel_report_with_checkpoint,
+    find_rss_feeds_parallel_report_with_progress, find_rss_feeds_stream_with_failures,
+    format_feed_report, normalize_titles, parse_cookie, parse_header, parse_host_timeout,
+    parse_urls_from_reader, read_feed_urls_from_file_with_options, read_input_entries_with_options,
+    resolve_output_path, sample_inputs, trace_redirects, transform_titles,
+    validate_feed_with_config,
+};
+use std::fs;
+use std::io::{BufReader, IsTerminal};
 use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{Duration, SystemTime};
 
 #[derive(Debug, Clone, ValueEnum)]
 enum FeedFilter {
@@ -12,58 +32,1078 @@ enum FeedFilter {
     Atom,
     /// Save both RSS and Atom feeds
     Both,
+    /// Save RSS, Atom, and unknown feeds (see --keep-unknown)
+    All,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum SiteFamily {
+    /// YouTube channels and playlists
+    Youtube,
+    /// Subreddits and Reddit users
+    Reddit,
+}
+
+impl From<SiteFamily> for KnownSite {
+    fn from(family: SiteFamily) -> Self {
+        match family {
+            SiteFamily::Youtube => KnownSite::YouTube,
+            SiteFamily::Reddit => KnownSite::Reddit,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Compat {
+    /// Write outline attributes as discovered
+    Standard,
+    /// Type every feed as rss and always include htmlUrl, for older NetNewsWire
+    Netnewswire,
+}
+
+impl From<Compat> for ReaderCompat {
+    fn from(compat: Compat) -> Self {
+        match compat {
+            Compat::Standard => ReaderCompat::Standard,
+            Compat::Netnewswire => ReaderCompat::NetNewsWire,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Validation {
+    /// Fully parse every candidate feed
+    Strict,
+    /// Trust the Content-Type and root element without a full parse
+    ContentType,
+}
+
+impl From<Validation> for ValidationMode {
+    fn from(validation: Validation) -> Self {
+        match validation {
+            Validation::Strict => ValidationMode::Strict,
+            Validation::ContentType => ValidationMode::ContentType,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum MinTls {
+    /// TLS 1.2
+    #[value(name = "1.2")]
+    Tls12,
+    /// TLS 1.3
+    #[value(name = "1.3")]
+    Tls13,
+}
+
+impl From<MinTls> for TlsVersion {
+    fn from(version: MinTls) -> Self {
+        match version {
+            MinTls::Tls12 => TlsVersion::Tls12,
+            MinTls::Tls13 => TlsVersion::Tls13,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum PreferHost {
+    /// Keep example.com
+    Bare,
+    /// Keep www.example.com
+    Www,
+}
+
+impl From<PreferHost> for WwwPreference {
+    fn from(prefer: PreferHost) -> Self {
+        match prefer {
+            PreferHost::Bare => WwwPreference::Bare,
+            PreferHost::Www => WwwPreference::Www,
+        }
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+enum OutputFormat {
+    /// OPML outline file for feed readers
+    Opml,
+    /// JSON array with full feed metadata
+    Json,
+    /// Plain text: the distinct feed URLs, sorted, one per line
+    Urls,
+    /// Netscape bookmarks HTML, for browsers and readers that import bookmarks
+    Bookmarks,
+    /// RSS 2.0 feed with one item per discovered feed, for tools that consume RSS
+    ReadingList,
 }
 
 #[derive(Parser, Debug)]
 #[command(name = "rss-miner")]
 #[command(about = "Finds RSS feeds from URLs and generates an OPML file", long_about = None)]
 struct Args {
-    /// Input file containing URLs (one per line)
+    /// Input file containing URLs: plain text (one per line), or .json/.csv with per-URL options.
+    /// A directory reads every .txt file in it
     #[arg(short, long, value_name = "FILE")]
     input: PathBuf,
 
-    /// Output OPML file path
+    /// With a directory --input, also read .txt files in subdirectories
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// File of URLs to skip, in the input format; a bare host skips every URL on that host
+    #[arg(long, value_name = "FILE")]
+    exclude_file: Option<PathBuf>,
+
+    /// Process only N URLs picked at random from the input, to estimate feed yield quickly
+    #[arg(long, value_name = "N")]
+    sample: Option<usize>,
+
+    /// Seed for --sample, so the same URLs are picked again (default: random)
+    #[arg(long, value_name = "SEED", requires = "sample")]
+    seed: Option<u64>,
+
+    /// Expand numeric ranges such as {1..5} in plain-text input lines
+    #[arg(long)]
+    expand_ranges: bool,
+
+    /// Output file path; an existing directory gets feeds.opml (or feeds.json, feeds.txt, feeds.html) written inside it
     #[arg(short, long, value_name = "FILE", default_value = "feeds.opml")]
     output: PathBuf,
 
-    /// Filter feeds by type (rss, atom, or both)
+    /// Write one OPML file per host into the --output directory
+    #[arg(long, conflicts_with = "stream")]
+    split_by_host: bool,
+
+    /// Write OPML files at paths built from a template such as out/{host}/{id}.opml
+    /// (placeholders: {host}, {id}, {type}, {title}, {folder})
+    #[arg(long, value_name = "TEMPLATE", conflicts_with_all = ["stream", "split_by_host"])]
+    output_template: Option<String>,
+
+    /// Add newly found feeds to this existing OPML file, keeping its outlines and folders as they are
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["stream", "split_by_host", "output_template"])]
+    append: Option<PathBuf>,
+
+    /// Only output feeds not already listed in this OPML file, e.g. a previous run's output
+    #[arg(long, value_name = "FILE")]
+    baseline: Option<PathBuf>,
+
+    /// Nest feeds under one outline per host in the OPML file
+    #[arg(long, conflicts_with_all = ["stream", "split_by_host"])]
+    group_by_host: bool,
+
+    /// With --group-by-host, keep hosts with a single feed as top-level outlines
+    #[arg(long, requires = "group_by_host")]
+    flatten_single_group: bool,
+
+    /// With --group-by-host, sort the feeds within each host alphabetically by title
+    #[arg(long, requires = "group_by_host")]
+    sort_groups: bool,
+
+    /// Write OPML without indentation or line breaks
+    #[arg(long)]
+    compact: bool,
+
+    /// Adjust OPML outline attributes for a feed reader's import quirks
+    #[arg(long, value_enum, default_value = "standard")]
+    compat: Compat,
+
+    /// Create missing parent directories of the output path
+    #[arg(long)]
+    mkdir: bool,
+
+    /// TOML config file with MinerConfig settings; command-line flags take precedence
+    #[arg(short, long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Request timeout in seconds (default: 10)
+    #[arg(long, value_name = "SECS")]
+    timeout: Option<u64>,
+
+    /// Filter feeds by type (rss, atom, both, or all)
     #[arg(short, long, value_enum, default_value = "both")]
     filter: FeedFilter,
+
+    /// Output file format
+    #[arg(long, value_enum, default_value = "opml")]
+    format: OutputFormat,
+
+    /// Extra header sent with every request, e.g. "X-Api-Key: secret" (repeatable)
+    #[arg(long = "header", value_name = "NAME: VALUE", value_parser = parse_header)]
+    headers: Vec<(String, String)>,
+
+    /// Timeout in seconds for requests to exactly this host, e.g. "slow.example.com=60" (repeatable)
+    #[arg(long = "timeout-per-host", value_name = "HOST=SECONDS", value_parser = parse_host_timeout)]
+    host_timeouts: Vec<(String, Duration)>,
+
+    /// Cookie sent with every request to exactly this host, e.g. "example.com:session=abc" (repeatable)
+    #[arg(long = "cookie", value_name = "HOST:NAME=VALUE", value_parser = parse_cookie)]
+    cookies: Vec<(String, String, String)>,
+
+    /// Rewrite feed titles with a regex, e.g. 's/ » RSS Feed$//' (repeatable, applied in order)
+    #[arg(long = "transform-title", value_name = "s/PATTERN/REPLACEMENT/", value_parser = TitleTransform::parse)]
+    title_transforms: Vec<TitleTransform>,
+
+    /// Merge feeds whose URLs differ only by a leading "www.", keeping the given variant
+    #[arg(long, value_enum, value_name = "PREFER")]
+    collapse_www: Option<PreferHost>,
+
+    /// Also merge feeds of the same site whose titles differ only by a trailing marker like " - RSS"
+    #[arg(long)]
+    dedupe_titles: bool,
+
+    /// Truncate feed titles longer than this many characters with "…" (0 = never)
+    #[arg(long, value_name = "CHARS", default_value_t = DEFAULT_MAX_TITLE_LENGTH)]
+    max_title_length: usize,
+
+    /// Keep feed-typed URLs that fail to parse, reported as type "unknown"
+    #[arg(long)]
+    keep_unknown: bool,
+
+    /// Send a HEAD request before fetching each common feed path
+    #[arg(long)]
+    head_probe: bool,
+
+    /// Use HTTP/2 without negotiation (only for hosts known to support it)
+    #[arg(long, conflicts_with = "http1_only")]
+    http2_prior_knowledge: bool,
+
+    /// Never use HTTP/2
+    #[arg(long)]
+    http1_only: bool,
+
+    /// File of feed URL patterns to exclude, one per line (substring, or glob with *)
+    #[arg(long, value_name = "FILE")]
+    blocklist: Option<PathBuf>,
+
+    /// Retry requests answered with 429 or 503 up to N times, honoring Retry-After
+    #[arg(long, value_name = "N")]
+    retries: Option<u32>,
+
+    /// Allow at most N HTTP requests in flight at once, whatever the thread count
+    #[arg(long, value_name = "N")]
+    max_inflight: Option<usize>,
+
+    /// Add a random delay of up to MS milliseconds to each retry wait (default: 500)
+    #[arg(long, value_name = "MS")]
+    retry_jitter: Option<u64>,
+
+    /// Keep at most N feeds per site, preferring the main feed over comment and category feeds
+    #[arg(long, value_name = "N")]
+    max_feeds_per_site: Option<usize>,
+
+    /// Stop fetching pages after N across the whole run, skipping the remaining URLs
+    #[arg(long, value_name = "N")]
+    max_pages: Option<usize>,
+
+    /// Don't follow redirects that leave the requested host
+    #[arg(long)]
+    same_host_redirects: bool,
+
+    /// With --same-host-redirects, validate a feed's cross-host redirect target and keep the feed there
+    #[arg(long, requires = "same_host_redirects")]
+    follow_feed_redirects: bool,
+
+    /// Send the page URL as the Referer when fetching its feeds
+    #[arg(long)]
+    send_referer: bool,
+
+    /// Build feed URLs directly for these sites' channel pages (comma-separated)
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "SITES")]
+    site_feeds: Vec<SiteFamily>,
+
+    /// Probe <profile>.rss for fediverse account URLs such as https://host/@user
+    #[arg(long)]
+    fediverse: bool,
+
+    /// Ignore feed-typed <link> tags whose rel is set but doesn't include "alternate"
+    #[arg(long)]
+    require_alternate_rel: bool,
+
+    /// Fallback feed paths to try first, in order, e.g. "feed.xml,atom.xml,rss"; the other default paths follow
+    #[arg(long, value_delimiter = ',', value_name = "PATHS")]
+    probe_order: Vec<String>,
+
+    /// Probe <a> links that look like feeds (e.g. "RSS") when a page has no feed <link> tags
+    #[arg(long)]
+    scan_anchors: bool,
+
+    /// Look for feed URLs declared in the page's JSON-LD structured data
+    #[arg(long)]
+    scan_json_ld: bool,
+
+    /// How many AMP pages in a row to follow to their canonical page when they link no feeds (default: 1; 0 disables)
+    #[arg(long, value_name = "N")]
+    amp_follow_depth: Option<usize>,
+
+    /// Title feeds on IP-address sites after the address's reverse-DNS name instead of "Feed at <ip>"
+    #[arg(long)]
+    reverse_dns: bool,
+
+    /// When nothing else finds a feed, look for feed URLs in /sitemap.xml or /sitemap.xml.gz
+    #[arg(long)]
+    scan_sitemap: bool,
+
+    /// Also look for feeds on each site's blog., www., and news. subdomains
+    #[arg(long)]
+    probe_subdomains: bool,
+
+    /// Also keep feeds that discovered Atom feeds link to as related (at most 5 per site)
+    #[arg(long)]
+    follow_related: bool,
+
+    /// Drop http feeds found on https pages, which browsers block as mixed content
+    #[arg(long)]
+    exclude_mixed_content: bool,
+
+    /// Print pages, feeds, errors, bytes, and average latency per host after the run
+    #[arg(long)]
+    host_stats: bool,
+
+    /// Reuse feed validations from earlier runs stored in this directory
+    #[arg(long, value_name = "DIR")]
+    cache_dir: Option<PathBuf>,
+
+    /// How long cached validations stay fresh, in seconds (default: 86400)
+    #[arg(long, value_name = "SECS", requires = "cache_dir")]
+    cache_ttl: Option<u64>,
+
+    /// Stop at the first valid feed found for each site
+    #[arg(long)]
+    first_feed_only: bool,
+
+    /// How candidate feeds are validated (default: strict, or the config file's validation_mode)
+    #[arg(long, value_enum, value_name = "MODE")]
+    validation: Option<Validation>,
+
+    /// Refuse servers that only offer TLS versions older than this. TLS 1.2 is already the minimum the TLS library allows, so only 1.3 narrows it
+    #[arg(long, value_enum, value_name = "VERSION")]
+    min_tls: Option<MinTls>,
+
+    /// Accept invalid or self-signed TLS certificates (dangerous; trusted hosts only)
+    #[arg(long)]
+    insecure: bool,
+
+    /// Treat input lines as feed URLs and print a metadata report for each instead of writing output
+    #[arg(long)]
+    validate_only: bool,
+
+    /// With --validate-only, list the titles of the first N items of each feed (at most 20)
+    #[arg(long, value_name = "N")]
+    sample_titles: Option<usize>,
+
+    /// Print the redirect chain of each discovered feed URL instead of writing output
+    #[arg(long)]
+    trace: bool,
+
+    /// Write OPML incrementally as feeds are found instead of all at once
+    #[arg(long)]
+    stream: bool,
+
+    /// Record each processed URL and its feeds in this file as the run goes
+    #[arg(long, value_name = "FILE", conflicts_with = "stream")]
+    checkpoint: Option<PathBuf>,
+
+    /// With --checkpoint, skip URLs the checkpoint file already records and keep their feeds
+    #[arg(long, requires = "checkpoint")]
+    resume: bool,
+
+    /// Suppress per-URL output and the progress bar
+    #[arg(short, long)]
+    quiet: bool,
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
+/// How a run ended, reported as the process exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    /// Every URL was processed and at least one feed was found.
+    FeedsFound = 0,
+    /// Every URL was processed but no feeds were found.
+    NoFeeds = 1,
+    /// Some URLs could not be fetched or processed.
+    PartialErrors = 2,
+    /// The run could not complete, e.g. an unreadable input file.
+    Fatal = 3,
+}
 
-    // Read URLs from input file
-    let urls = read_urls_from_file(&args.input)?;
-    println!("Found {} URLs to process", urls.len());
+impl Outcome {
+    fn from_counts(feeds_found: usize, failures: usize) -> Self {
+        if failures > 0 {
+            Outcome::PartialErrors
+        } else if feeds_found == 0 {
+            Outcome::NoFeeds
+        } else {
+            Outcome::FeedsFound
+        }
+    }
+}
 
-    // Create a shared HTTP client for all operations
-    let client = Client::builder()
-        .timeout(std::time::Duration::from_secs(10))
-        .build()?;
+impl From<Outcome> for ExitCode {
+    fn from(outcome: Outcome) -> Self {
+        ExitCode::from(outcome as u8)
+    }
+}
 
-    // Find RSS feeds in parallel using Rayon
-    let feeds = find_rss_feeds_parallel(&urls, &client, true);
+/// Loads the config file, if any, and applies command-line overrides on top.
+fn miner_config(args: &Args) -> Result<MinerConfig> {
+    let mut config = match &args.config {
+        Some(path) => MinerConfig::from_toml_file(path)?,
+        None => MinerConfig::default(),
+    };
 
-    println!("\nTotal feeds found: {}", feeds.len());
+    if let Some(secs) = args.timeout {
+        config.timeout = Duration::from_secs(secs);
+    }
+    if let Some(retries) = args.retries {
+        config.max_retries = retries;
+    }
+    if let Some(limit) = args.max_inflight {
+        config.max_inflight_requests = Some(limit);
+    }
+    if let Some(millis) = args.retry_jitter {
+        config.retry_jitter = Duration::from_millis(millis);
+    }
+    if let Some(version) = args.min_tls {
+        config.min_tls_version = Some(version.into());
+    }
+    if let Some(validation) = args.validation {
+        config.validation_mode = validation.into();
+    }
+    if let Some(max_feeds) = args.max_feeds_per_site {
+        config.max_feeds_per_site = Some(max_feeds);
+    }
+    if let Some(max_pages) = args.max_pages {
+        config.max_page_fetches = Some(max_pages);
+    }
+    if let Some(cache_dir) = &args.cache_dir {
+        config.cache_dir = Some(cache_dir.clone());
+    }
+    if let Some(secs) = args.cache_ttl {
+        config.cache_ttl = Duration::from_secs(secs);
+    }
+    if let Some(sample_titles) = args.sample_titles {
+        config.sample_titles = sample_titles;
+    }
+    if !args.probe_order.is_empty() {
+        config.probe_paths = probe_order(&args.probe_order);
+    }
+    if !args.headers.is_empty() {
+        config.extra_headers = args.headers.clone();
+    }
+    config.cookies.extend(args.cookies.iter().cloned());
+    config
+        .host_timeouts
+        .extend(args.host_timeouts.iter().cloned());
+    config.keep_unknown |= args.keep_unknown;
+    config.use_head_probe |= args.head_probe;
+    config.danger_accept_invalid_certs |= args.insecure;
+    config.scan_anchors |= args.scan_anchors;
+    config.scan_json_ld |= args.scan_json_ld;
+    config.reverse_dns_titles |= args.reverse_dns;
+    if let Some(depth) = args.amp_follow_depth {
+        config.amp_follow_depth = depth;
+    }
+    config.require_alternate_rel |= args.require_alternate_rel;
+    config.scan_sitemap |= args.scan_sitemap;
+    config.probe_subdomains |= args.probe_subdomains;
+    config.follow_related_feeds |= args.follow_related;
+    config.first_feed_only |= args.first_feed_only;
+    config.host_stats |= args.host_stats;
+    config.exclude_mixed_content |= args.exclude_mixed_content;
+    config.probe_fediverse |= args.fediverse;
+    config.known_sites.extend(
+        args.site_feeds
+            .iter()
+            .map(|&family| KnownSite::from(family)),
+    );
+    config.send_referer |= args.send_referer;
+    config.same_host_redirects |= args.same_host_redirects;
+    config.follow_feed_redirects |= args.follow_feed_redirects;
+    config.http2_prior_knowledge |= args.http2_prior_knowledge;
+    config.http1_only |= args.http1_only;
+    // Patterns are kept as written, without the URL handling of input files
+    if let Some(path) = &args.blocklist {
+        let file = fs::File::open(path)
+            .with_context(|| format!("Failed to read blocklist file: {}", path.display()))?;
+        config
+            .feed_url_denylist
+            .extend(parse_urls_from_reader(BufReader::new(file))?);
+    }
 
-    if feeds.is_empty() {
-        println!("No RSS feeds found. OPML file will not be created.");
-        return Ok(());
+    Ok(config)
+}
+
+/// Prints the redirect chain from each feed's discovered URL.
+fn print_redirect_traces(feeds: &[RssFeed], config: &MinerConfig) -> Result<()> {
+    let client = build_trace_client(config)?;
+    let traces: Vec<_> = feeds
+        .par_iter()
+        .map(|feed| trace_redirects(&feed.raw_url, &client))
+        .collect();
+
+    println!("\nRedirect traces:");
+    for (feed, trace) in feeds.iter().zip(traces) {
+        println!("{}", feed.raw_url);
+        match trace {
+            Ok(hops) if hops.len() == 1 => println!("  {} (no redirects)", hops[0].status),
+            Ok(hops) => {
+                for hop in hops {
+                    println!("  {} {}", hop.status, hop.url);
+                }
+            }
+            Err(e) => println!("  Error: {:#}", e),
+        }
     }
+    Ok(())
+}
+
+/// Puts the `preferred` probe paths first, in the given order, followed by
+/// the remaining default paths. A leading `/` is optional.
+fn probe_order(preferred: &[String]) -> Vec<String> {
+    let mut paths: Vec<String> = Vec::new();
+    let preferred = preferred
+        .iter()
+        .map(|path| path.trim())
+        .filter(|path| !path.is_empty())
+        .map(|path| format!("/{}", path.trim_start_matches('/')));
+    for path in preferred.chain(DEFAULT_PROBE_PATHS.map(str::to_string)) {
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+/// Formats a byte count with a binary unit, e.g. `1.5 MiB`.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+fn input_options(args: &Args) -> Result<InputOptions> {
+    let exclusions = match &args.exclude_file {
+        Some(path) => {
+            let exclusions = UrlExclusions::from_file(path)?;
+            if !args.quiet {
+                println!(
+                    "Skipping {} URL(s) and host(s) listed in {}",
+                    exclusions.len(),
+                    path.display()
+                );
+            }
+            exclusions
+        }
+        None => UrlExclusions::default(),
+    };
+    Ok(InputOptions {
+        expand_ranges: args.expand_ranges,
+        keep_actor_paths: args.fediverse,
+        known_sites: args
+            .site_feeds
+            .iter()
+            .map(|&family| family.into())
+            .collect(),
+        recursive: args.recursive,
+        exclusions,
+    })
+}
+
+/// Narrows the input to a random `--sample`, if one was asked for, and
+/// prints the seed so the same sample can be drawn again.
+fn sample<T>(args: &Args, inputs: Vec<T>) -> Vec<T> {
+    let Some(count) = args.sample else {
+        return inputs;
+    };
+    let seed = args.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64
+    });
+    println!(
+        "Sampling {} of {} URLs (--seed {})",
+        count.min(inputs.len()),
+        inputs.len(),
+        seed
+    );
+    sample_inputs(inputs, count, seed)
+}
+
+/// Validates each input line as a feed URL and prints its metadata.
+fn validate_only(args: &Args) -> Result<Outcome> {
+    let urls = read_feed_urls_from_file_with_options(&args.input, &input_options(args)?)?;
+    let urls = sample(args, urls);
+    let config = miner_config(args)?;
+    let client = build_client(&config)?;
+
+    let results: Vec<_> = urls
+        .par_iter()
+        .map(|url| validate_feed_with_config(url, &client, &config))
+        .collect();
+
+    let mut valid = 0;
+    for (url, info) in urls.iter().zip(results) {
+        match info {
+            Some(info) => {
+                valid += 1;
+                print!("{}", format_feed_report(url, &info));
+            }
+            None => println!("{}\n  Invalid: not a reachable RSS or Atom feed", url),
+        }
+    }
+    println!("\n{} of {} feeds valid", valid, urls.len());
+
+    Ok(Outcome::from_counts(valid, urls.len() - valid))
+}
+
+fn main() -> ExitCode {
+    let args = match Args::try_parse() {
+        Ok(args) => args,
+        Err(err) => {
+            // Keep usage errors off exit code 2, which means partial errors here
+            let _ = err.print();
+            return if err.use_stderr() {
+                Outcome::Fatal.into()
+            } else {
+                ExitCode::SUCCESS
+            };
+        }
+    };
+
+    match run(&args) {
+        Ok(outcome) => outcome.into(),
+        Err(err) => {
+            eprintln!("Error: {:?}", err);
+            Outcome::Fatal.into()
+        }
+    }
+}
+
+fn run(args: &Args) -> Result<Outcome> {
+    if args.validate_only {
+        return validate_only(args);
+    }
+
+    // Read URLs and any per-URL overrides from the input file
+    let entries = read_input_entries_with_options(&args.input, &input_options(args)?)?;
+    let entries = sample(args, entries);
+    let overrides = InputOverrides::new(&entries);
+    let urls: Vec<String> = entries.into_iter().map(|entry| entry.url).collect();
+    println!("Found {} URLs to process", urls.len());
+
+    let config = miner_config(args)?;
+
+    // Create a shared HTTP client for all operations
+    let client = build_client(&config)?;
 
     // Convert filter option to FeedType
     let feed_type_filter = match args.filter {
         FeedFilter::Rss => Some(FeedType::Rss),
         FeedFilter::Atom => Some(FeedType::Atom),
-        FeedFilter::Both => None,
+        FeedFilter::Both | FeedFilter::All => None,
+    };
+    let opml_options = OpmlOptions {
+        feed_type_filter,
+        include_unknown: matches!(args.filter, FeedFilter::All),
+        group_by_host: args.group_by_host,
+        flatten_single_group: args.flatten_single_group,
+        sort_group_children: args.sort_groups,
+        pretty: !args.compact,
+        compat: args.compat.into(),
     };
 
-    // Create OPML file with the selected filter
-    create_opml_file_filtered(&feeds, &args.output, feed_type_filter)?;
-    println!("OPML file created: {}", args.output.display());
+    if args.mkdir {
+        create_parent_dirs(&args.output)?;
+    }
+
+    if args.stream {
+        if !matches!(args.format, OutputFormat::Opml) {
+            bail!("--stream only supports OPML output");
+        }
 
-    Ok(())
+        let mut writer = OpmlStreamWriter::create(&args.output, &opml_options)?;
+        let (feeds, discovery) =
+            find_rss_feeds_stream_with_failures(urls, client, config, !args.quiet);
+        for feed in feeds {
+            if let Some(mut feed) = overrides.apply(feed) {
+                transform_titles(std::slice::from_mut(&mut feed), &args.title_transforms);
+                normalize_titles(std::slice::from_mut(&mut feed), args.max_title_length);
+                writer.write_feed(&feed)?;
+            }
+        }
+        let written = writer.written();
+        writer.finish()?;
+        let failed = discovery
+            .join()
+            .map_err(|_| anyhow::anyhow!("Feed discovery thread panicked"))?;
+
+        println!("\nTotal feeds written: {}", written);
+        println!(
+            "OPML file created: {}",
+            resolve_output_path(&args.output, DEFAULT_OPML_FILE_NAME).display()
+        );
+        return Ok(Outcome::from_counts(written, failed));
+    }
+
+    let checkpoint = match &args.checkpoint {
+        Some(path) if args.resume => Some(Checkpoint::resume(path)?),
+        Some(path) => Some(Checkpoint::create(path)?),
+        None => None,
+    };
+    let already_processed = checkpoint.as_ref().map_or(0, |checkpoint| {
+        urls.iter()
+            .filter(|url| checkpoint.is_processed(url))
+            .count()
+    });
+    if already_processed > 0 {
+        println!(
+            "Resuming from checkpoint: {} URL(s) already processed",
+            already_processed
+        );
+    }
+
+    // Find RSS feeds in parallel using Rayon
+    // On a terminal a progress bar replaces the per-URL lines
+    let progress_bar = (!args.quiet && std::io::stderr().is_terminal()).then(|| {
+        ProgressBar::new(urls.len() as u64)
+            .with_style(
+                ProgressStyle::with_template("{bar:40} {pos}/{len} URLs, {msg} [{elapsed}]")
+                    .expect("valid progress bar template"),
+            )
+            .with_position(already_processed as u64)
+    });
+    let verbose = !args.quiet && progress_bar.is_none();
+    let on_progress = |progress: Progress| {
+        if let Some(bar) = &progress_bar {
+            bar.inc(1);
+            bar.set_message(format!("{} feeds found", progress.feeds_found));
+        }
+    };
+    let report = match &checkpoint {
+        Some(checkpoint) => find_rss_feeds_parallel_report_with_checkpoint(
+            &urls,
+            &client,
+            &config,
+            verbose,
+            checkpoint,
+            on_progress,
+        )?,
+        None => find_rss_feeds_parallel_report_with_progress(
+            &urls,
+            &client,
+            &config,
+            verbose,
+            on_progress,
+        ),
+    };
+    if let Some(bar) = progress_bar {
+        bar.finish();
+    }
+    let (mut feeds, mut duplicates) = dedupe_feeds_with_report(overrides.apply_all(report.feeds));
+    if let Some(prefer) = args.collapse_www {
+        let (kept, variants) = collapse_www_duplicates(feeds, prefer.into());
+        feeds = kept;
+        duplicates.extend(variants);
+    }
+    if args.dedupe_titles {
+        let (kept, similar) = dedupe_similar_titles(feeds);
+        feeds = kept;
+        duplicates.extend(similar);
+    }
+    transform_titles(&mut feeds, &args.title_transforms);
+    normalize_titles(&mut feeds, args.max_title_length);
+    let found = feeds.len();
+    if let Some(baseline) = &args.baseline {
+        feeds = exclude_baseline_feeds(feeds, baseline)?;
+    }
+    let outcome = Outcome::from_counts(feeds.len(), report.urls_failed);
+
+    println!("\nTotal feeds found: {}", found);
+    if let Some(baseline) = &args.baseline {
+        println!(
+            "New feeds not in {}: {} ({} already listed)",
+            baseline.display(),
+            feeds.len(),
+            found - feeds.len()
+        );
+    }
+    if report.urls_skipped > 0 {
+        println!(
+            "Page fetch limit reached: {} URL(s) were not processed",
+            report.urls_skipped
+        );
+    }
+    if !report.tls_failures.is_empty() {
+        println!(
+            "Feeds not checked because of TLS errors (possibly valid; see --insecure): {}",
+            report.tls_failures.len()
+        );
+        if !args.quiet {
+            for (url, error) in &report.tls_failures {
+                println!("  {} ({})", url, error);
+            }
+        }
+    }
+    let mixed_content = feeds.iter().filter(|feed| feed.mixed_content).count();
+    if mixed_content > 0 {
+        println!(
+            "Mixed-content feeds (http feed on an https page): {}",
+            mixed_content
+        );
+    }
+    if !duplicates.is_empty() {
+        println!("Duplicate feeds merged: {}", duplicates.len());
+        if !args.quiet {
+            for duplicate in &duplicates {
+                println!("  {} (from {})", duplicate.url, duplicate.html_url);
+            }
+        }
+    }
+    println!("Downloaded: {}", format_bytes(report.bytes_downloaded));
+    println!("Page fetch latency: {}", report.page_latency.summary());
+    println!(
+        "Validation latency: {}",
+        report.validation_latency.summary()
+    );
+    if !report.host_stats.is_empty() {
+        println!("\nPer-host statistics:");
+        for (host, stats) in &report.host_stats {
+            println!(
+                "  {}: {} page(s), {} feed(s), {} error(s), {}, avg {}",
+                host,
+                stats.pages_fetched,
+                stats.feeds_found,
+                stats.errors,
+                format_bytes(stats.bytes_downloaded),
+                stats
+                    .average_latency()
+                    .map_or("n/a".to_string(), |latency| format!(
+                        "{}ms",
+                        latency.as_millis()
+                    ))
+            );
+        }
+    }
+
+    if args.trace {
+        print_redirect_traces(&feeds, &config)?;
+        return Ok(outcome);
+    }
+
+    if feeds.is_empty() {
+        println!("No RSS feeds found. OPML file will not be created.");
+        return Ok(outcome);
+    }
+
+    if args.split_by_host {
+        if !matches!(args.format, OutputFormat::Opml) {
+            bail!("--split-by-host only supports OPML output");
+        }
+
+        let paths = create_opml_files_by_host(&feeds, &args.output, &opml_options)?;
+        println!(
+            "Wrote {} OPML file(s) to {}",
+            paths.len(),
+            args.output.display()
+        );
+        return Ok(outcome);
+    }
+
+    if let Some(path) = &args.append {
+        if !matches!(args.format, OutputFormat::Opml) {
+            bail!("--append only supports OPML output");
+        }
+
+        let added = append_to_opml_file(&feeds, path, &opml_options)?;
+        println!("Added {} new feed(s) to {}", added, path.display());
+        return Ok(outcome);
+    }
+
+    if let Some(template) = &args.output_template {
+        if !matches!(args.format, OutputFormat::Opml) {
+            bail!("--output-template only supports OPML output");
+        }
+
+        let paths = create_opml_files_from_template(&feeds, template, &opml_options)?;
+        println!("Wrote {} OPML file(s) from {}", paths.len(), template);
+        return Ok(outcome);
+    }
+
+    match args.format {
+        OutputFormat::Opml => {
+            // Create OPML file with the selected filter
+            create_opml_file_with_options(&feeds, &args.output, &opml_options)?;
+            println!(
+                "OPML file created: {}",
+                resolve_output_path(&args.output, DEFAULT_OPML_FILE_NAME).display()
+            );
+        }
+        OutputFormat::Json => {
+            if let Some(filter_type) = feed_type_filter {
+                feeds.retain(|feed| feed.feed_type == filter_type);
+            }
+            create_json_file(&feeds, &args.output)?;
+            println!(
+                "JSON file created: {}",
+                resolve_output_path(&args.output, DEFAULT_JSON_FILE_NAME).display()
+            );
+        }
+        OutputFormat::Urls => {
+            if let Some(filter_type) = feed_type_filter {
+                feeds.retain(|feed| feed.feed_type == filter_type);
+            }
+            create_url_list_file(&feeds, &args.output)?;
+            println!(
+                "URL list created: {}",
+                resolve_output_path(&args.output, DEFAULT_URLS_FILE_NAME).display()
+            );
+        }
+        OutputFormat::Bookmarks => {
+            if let Some(filter_type) = feed_type_filter {
+                feeds.retain(|feed| feed.feed_type == filter_type);
+            }
+            create_bookmarks_html(&feeds, &args.output)?;
+            println!(
+                "Bookmarks file created: {}",
+                resolve_output_path(&args.output, DEFAULT_BOOKMARKS_FILE_NAME).display()
+            );
+        }
+        OutputFormat::ReadingList => {
+            if let Some(filter_type) = feed_type_filter {
+                feeds.retain(|feed| feed.feed_type == filter_type);
+            }
+            create_reading_list_rss(&feeds, &args.output)?;
+            println!(
+                "Reading list created: {}",
+                resolve_output_path(&args.output, DEFAULT_READING_LIST_FILE_NAME).display()
+            );
+        }
+    }
+
+    Ok(outcome)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_cli_flags_override_config_file() {
+        let mut config_file = NamedTempFile::new().unwrap();
+        writeln!(config_file, "timeout = 30\nuse_head_probe = true").unwrap();
+        let config_path = config_file.path().to_str().unwrap();
+
+        let args =
+            Args::try_parse_from(["rss-miner", "-i", "urls.txt", "-c", config_path]).unwrap();
+        let config = miner_config(&args).unwrap();
+        assert_eq!(config.timeout, Duration::from_secs(30));
+        assert!(config.use_head_probe);
+
+        let args = Args::try_parse_from([
+            "rss-miner",
+            "-i",
+            "urls.txt",
+            "-c",
+            config_path,
+            "--timeout",
+            "5",
+        ])
+        .unwrap();
+        let config = miner_config(&args).unwrap();
+        assert_eq!(config.timeout, Duration::from_secs(5));
+        assert!(config.use_head_probe);
+    }
+
+    #[test]
+    fn test_probe_order_puts_preferred_paths_first() {
+        let preferred = vec![
+            "feed.xml".to_string(),
+            "/atom.xml".to_string(),
+            "blog/rss".to_string(),
+        ];
+        assert_eq!(
+            probe_order(&preferred),
+            vec![
+                "/feed.xml",
+                "/atom.xml",
+                "/blog/rss",
+                "/feed",
+                "/rss",
+                "/rss.xml",
+                "/index.xml"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exclude_file_drops_listed_urls() {
+        let mut exclude_file = NamedTempFile::new().unwrap();
+        writeln!(exclude_file, "skip.example.com\nhttps://example.com/blog").unwrap();
+        let mut input_file = NamedTempFile::new().unwrap();
+        writeln!(
+            input_file,
+            "https://skip.example.com/about\nhttps://example.com/blog\nkeep.example.com/news"
+        )
+        .unwrap();
+        let input_path = input_file.path().to_str().unwrap();
+
+        let args = Args::try_parse_from([
+            "rss-miner",
+            "-i",
+            input_path,
+            "--exclude-file",
+            exclude_file.path().to_str().unwrap(),
+        ])
+        .unwrap();
+        let urls: Vec<String> =
+            read_input_entries_with_options(&args.input, &input_options(&args).unwrap())
+                .unwrap()
+                .into_iter()
+                .map(|entry| entry.url)
+                .collect();
+        assert_eq!(urls, vec!["https://keep.example.com"]);
+
+        let args = Args::try_parse_from(["rss-miner", "-i", input_path]).unwrap();
+        let urls =
+            read_input_entries_with_options(&args.input, &input_options(&args).unwrap()).unwrap();
+        assert_eq!(urls.len(), 3);
+    }
+
+    #[test]
+    fn test_blocklist_patterns_kept_as_written() {
+        let mut blocklist = NamedTempFile::new().unwrap();
+        writeln!(blocklist, "# spam\n/comments/\n*.atom").unwrap();
+        let args = Args::try_parse_from([
+            "rss-miner",
+            "-i",
+            "urls.txt",
+            "--blocklist",
+            blocklist.path().to_str().unwrap(),
+        ])
+        .unwrap();
+        let config = miner_config(&args).unwrap();
+        assert_eq!(config.feed_url_denylist, vec!["/comments/", "*.atom"]);
+
+        let feed = |url: &str| RssFeed::new("Feed", url, "http://example.com", FeedType::Rss);
+        assert!(config.denies_feed(&feed("http://example.com/comments/feed")));
+        assert!(config.denies_feed(&feed("http://example.com/index.atom")));
+        assert!(!config.denies_feed(&feed("http://example.com/feed.xml")));
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MiB");
+    }
+}
@@ -0,0 +1,115 @@
+use assert_cmd::Command;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::thread;
+use tempfile::TempDir;
+
+const RSS_FEED: &str = r#"<?xml version="1.0"?>
+<rss version="2.0">
+  <channel>
+    <title>Example</title>
+    <link>https://example.com</link>
+    <description>Example feed</description>
+  </channel>
+</rss>"#;
+
+/// Starts a server whose home page has no feed links; `/feed.xml` serves an
+/// RSS feed when `with_feed` is set and everything else is a 404.
+fn start_server(with_feed: bool) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            let _ = reader.read_line(&mut request_line);
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 || line.trim().is_empty() {
+                    break;
+                }
+            }
+
+            let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+            let (status, content_type, body) = match path {
+                "/" => (200, "text/html", "<html><head></head><body></body></html>"),
+                "/feed.xml" if with_feed => (200, "application/rss+xml", RSS_FEED),
+                _ => (404, "text/plain", "not found"),
+            };
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {} Mock\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                content_type,
+                body.len(),
+                body
+            );
+        }
+    });
+
+    base
+}
+
+fn run_with_urls(urls: &[&str], extra_args: &[&str]) -> (TempDir, assert_cmd::assert::Assert) {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("urls.txt");
+    std::fs::write(&input, urls.join("\n")).unwrap();
+
+    let assert = Command::cargo_bin("rss-miner")
+        .unwrap()
+        .arg("--input")
+        .arg(&input)
+        .arg("--output")
+        .arg(dir.path().join("feeds.opml"))
+        .arg("--timeout")
+        .arg("5")
+        .args(extra_args)
+        .assert();
+    (dir, assert)
+}
+
+#[test]
+fn exits_zero_when_feeds_are_found() {
+    let server = start_server(true);
+    let (dir, assert) = run_with_urls(&[&server], &[]);
+    assert.code(0);
+    assert!(dir.path().join("feeds.opml").exists());
+}
+
+#[test]
+fn exits_one_when_no_feeds_are_found() {
+    let server = start_server(false);
+    let (_dir, assert) = run_with_urls(&[&server], &[]);
+    assert.code(1);
+}
+
+#[test]
+fn exits_two_when_some_urls_fail() {
+    let server = start_server(true);
+    let (_dir, assert) = run_with_urls(&[&server, "http://127.0.0.1:1/"], &[]);
+    assert.code(2);
+}
+
+#[test]
+fn exits_two_when_some_urls_fail_while_streaming() {
+    let server = start_server(true);
+    let (dir, assert) = run_with_urls(&[&server, "http://127.0.0.1:1/"], &["--stream"]);
+    assert.code(2);
+    assert!(dir.path().join("feeds.opml").exists());
+}
+
+#[test]
+fn exits_three_on_fatal_errors() {
+    Command::cargo_bin("rss-miner")
+        .unwrap()
+        .args(["--input", "does-not-exist.txt"])
+        .assert()
+        .code(3);
+
+    Command::cargo_bin("rss-miner")
+        .unwrap()
+        .arg("--no-such-flag")
+        .assert()
+        .code(3);
+}